@@ -1,26 +1,113 @@
 use cargo::{
-  core::{compiler::CompileMode, Workspace},
-  ops::{compile, CompileOptions},
-  util::config::Config,
+  core::{compiler::CompileMode, resolver::CliFeatures, Workspace},
+  ops::{compile, CompileOptions, Packages},
+  util::{config::Config, interning::InternedString, Filesystem},
 };
-use std::{fs, path::Path};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use super::BuildOptions;
+
+/// A cdylib copied to the dest directory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Artifact {
+  pub package: String,
+  pub name: String,
+  pub path: PathBuf,
+}
 
+/// What a build produced, `to_json` is what `lisp-builder --json` prints.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BuildOutput {
+  pub profile: String,
+  pub artifacts: Vec<Artifact>,
+}
+
+impl BuildOutput {
+  #[inline]
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).expect("failed to serialize build output")
+  }
+}
+
+/// Why a build failed, `to_json` is what `lisp-builder --json` prints instead
+/// of the output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BuildError {
+  pub error: String,
+}
+
+impl BuildError {
+  #[inline]
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).expect("failed to serialize build error")
+  }
+}
+
+/// Compiles the workspace of `manifest_path` and copies every cdylib it
+/// produced to `dest_path`.
 #[inline]
-pub fn build(manifest_path: &Path, dest_path: &Path) {
-  let config = Config::default().unwrap();
-  let workspace = Workspace::new(manifest_path, &config).unwrap();
-  let compile_options = CompileOptions::new(&config, CompileMode::Build).unwrap();
-  let complimation = compile(&workspace, &compile_options).unwrap();
-  for cdylib in complimation.cdylibs {
-    fs::copy(
-      cdylib.path.clone(),
-      dest_path.join(
-        cdylib
-          .path
-          .file_name()
-          .expect("Failed to get file name of binary"),
-      ),
-    )
-    .expect("Failed to copy file");
+pub fn build(
+  manifest_path: &Path,
+  dest_path: &Path,
+  options: &BuildOptions,
+) -> Result<BuildOutput, String> {
+  let config =
+    Config::default().map_err(|error| format!("failed to configure cargo: {:#}", error))?;
+  let mut workspace = Workspace::new(manifest_path, &config)
+    .map_err(|error| format!("failed to read {:?}: {:#}", manifest_path, error))?;
+
+  if let Some(ref target_dir) = options.target_dir {
+    workspace.set_target_dir(Filesystem::new(target_dir.clone()));
+  }
+
+  let mut compile_options = CompileOptions::new(&config, CompileMode::Build)
+    .map_err(|error| format!("failed to configure the build: {:#}", error))?;
+  compile_options.build_config.requested_profile = InternedString::new(&options.profile);
+  compile_options.cli_features = CliFeatures::from_command_line(
+    &options.features,
+    options.all_features,
+    !options.no_default_features,
+  )
+  .map_err(|error| format!("invalid features: {:#}", error))?;
+  if !options.packages.is_empty() {
+    compile_options.spec = Packages::Packages(options.packages.clone());
+  }
+
+  let compilation = compile(&workspace, &compile_options)
+    .map_err(|error| format!("failed to build {:?}: {:#}", manifest_path, error))?;
+
+  fs::create_dir_all(dest_path)
+    .map_err(|error| format!("failed to create {:?}: {}", dest_path, error))?;
+
+  let mut artifacts = Vec::new();
+
+  for cdylib in compilation.cdylibs {
+    let path = dest_path.join(
+      cdylib
+        .path
+        .file_name()
+        .expect("Failed to get file name of binary"),
+    );
+
+    // a new file leaves a loaded copy of the library intact
+    if path.exists() {
+      fs::remove_file(&path).map_err(|error| format!("failed to replace {:?}: {}", path, error))?;
+    }
+    fs::copy(&cdylib.path, &path)
+      .map_err(|error| format!("failed to copy {:?} to {:?}: {}", cdylib.path, path, error))?;
+
+    artifacts.push(Artifact {
+      package: cdylib.unit.pkg.name().to_string(),
+      name: cdylib.unit.target.name().to_owned(),
+      path,
+    });
   }
+
+  Ok(BuildOutput {
+    profile: options.profile.clone(),
+    artifacts,
+  })
 }
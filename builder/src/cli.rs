@@ -0,0 +1,113 @@
+use clap::{Arg, ArgMatches, Command};
+use std::path::PathBuf;
+
+use super::{BuildOptions, RELEASE_PROFILE};
+
+const NAME: &'static str = env!("CARGO_PKG_NAME");
+const DESCRIPTION: &'static str = env!("CARGO_PKG_DESCRIPTION");
+const AUTHORS: &'static str = env!("CARGO_PKG_AUTHORS");
+const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+/// The `lisp-builder` command line.
+#[inline]
+pub fn command() -> Command<'static> {
+  Command::new(NAME)
+    .version(VERSION)
+    .author(AUTHORS)
+    .about(DESCRIPTION)
+    .arg(
+      Arg::new("input")
+        .short('i')
+        .long("input")
+        .default_value("./Cargo.toml")
+        .help("Sets the input Cargo.toml to use"),
+    )
+    .arg(
+      Arg::new("dest")
+        .short('d')
+        .long("dest")
+        .default_value("./lisp-builder-out")
+        .help("Sets the output path to use"),
+    )
+    .arg(
+      Arg::new("release")
+        .short('r')
+        .long("release")
+        .conflicts_with("profile")
+        .help("Builds with the release profile"),
+    )
+    .arg(
+      Arg::new("profile")
+        .long("profile")
+        .takes_value(true)
+        .help("Builds with the named profile, defaults to dev"),
+    )
+    .arg(
+      Arg::new("features")
+        .short('F')
+        .long("features")
+        .takes_value(true)
+        .multiple_occurrences(true)
+        .help("Activates the comma or space separated features"),
+    )
+    .arg(
+      Arg::new("all-features")
+        .long("all-features")
+        .help("Activates all features"),
+    )
+    .arg(
+      Arg::new("no-default-features")
+        .long("no-default-features")
+        .help("Does not activate the default features"),
+    )
+    .arg(
+      Arg::new("target-dir")
+        .long("target-dir")
+        .takes_value(true)
+        .help("Sets the directory for all generated artifacts"),
+    )
+    .arg(
+      Arg::new("package")
+        .short('p')
+        .long("package")
+        .takes_value(true)
+        .multiple_occurrences(true)
+        .help("Builds only the given workspace members"),
+    )
+    .arg(
+      Arg::new("json")
+        .long("json")
+        .help("Prints the built artifacts, or the error, as JSON"),
+    )
+}
+
+/// The `BuildOptions` the `lisp-builder` arguments in `matches` ask for.
+#[inline]
+pub fn build_options(matches: &ArgMatches) -> BuildOptions {
+  let mut options = BuildOptions::default();
+
+  if matches.is_present("release") {
+    options.profile = RELEASE_PROFILE.to_owned();
+  } else if let Some(profile) = matches.value_of("profile") {
+    options.profile = profile.to_owned();
+  }
+  options.features = matches
+    .values_of("features")
+    .into_iter()
+    .flatten()
+    .flat_map(|features| features.split(|c: char| c == ',' || c.is_whitespace()))
+    .filter(|feature| !feature.is_empty())
+    .map(str::to_owned)
+    .collect();
+  options.all_features = matches.is_present("all-features");
+  options.no_default_features = matches.is_present("no-default-features");
+  options.target_dir = matches.value_of("target-dir").map(PathBuf::from);
+  options.packages = matches
+    .values_of("package")
+    .into_iter()
+    .flatten()
+    .map(str::to_owned)
+    .collect();
+
+  options
+}
@@ -1,5 +1,14 @@
 extern crate cargo;
+extern crate clap;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
 
 mod builder;
+mod cli;
+mod options;
 
-pub use self::builder::build;
+pub use self::builder::*;
+pub use self::cli::*;
+pub use self::options::*;
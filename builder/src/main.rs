@@ -1,41 +1,39 @@
-extern crate clap;
 extern crate lisp_builder;
 
-use clap::{Arg, Command};
+use lisp_builder::{build_options, command, BuildError};
 use std::fs;
-
-const NAME: &'static str = env!("CARGO_PKG_NAME");
-const DESCRIPTION: &'static str = env!("CARGO_PKG_DESCRIPTION");
-const AUTHORS: &'static str = env!("CARGO_PKG_AUTHORS");
-const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+use std::process;
 
 fn main() {
-  let matches = Command::new(NAME)
-    .version(VERSION)
-    .author(AUTHORS)
-    .about(DESCRIPTION)
-    .arg(
-      Arg::new("input")
-        .short('i')
-        .long("input")
-        .default_value("./Cargo.toml")
-        .help("Sets the input Cargo.toml to use"),
-    )
-    .arg(
-      Arg::new("dest")
-        .short('d')
-        .long("dest")
-        .default_value("./lisp-builder-out")
-        .help("Sets the output path to use"),
-    )
-    .get_matches();
+  let matches = command().get_matches();
 
-  let dest_dir = matches.value_of("dest").unwrap_or("./dest");
-  fs::create_dir_all(dest_dir).expect("Failed to create dest directory");
+  let result = fs::canonicalize(matches.value_of("input").unwrap_or("."))
+    .map_err(|error| format!("failed to find the input file: {}", error))
+    .and_then(|input| {
+      let dest_dir = matches.value_of("dest").unwrap_or("./lisp-builder-out");
+      fs::create_dir_all(dest_dir)
+        .and_then(|_| fs::canonicalize(dest_dir))
+        .map_err(|error| format!("failed to create the dest directory: {}", error))
+        .and_then(|dest| lisp_builder::build(&input, &dest, &build_options(&matches)))
+    });
 
-  lisp_builder::build(
-    &fs::canonicalize(matches.value_of("input").unwrap_or("."))
-      .expect("Failed to canonicalize input file"),
-    &fs::canonicalize(dest_dir).expect("Failed to canonicalize output file"),
-  );
+  match result {
+    Ok(output) => {
+      if matches.is_present("json") {
+        println!("{}", output.to_json());
+      } else {
+        for artifact in output.artifacts {
+          println!("built {}", artifact.path.display());
+        }
+      }
+    }
+    Err(error) => {
+      if matches.is_present("json") {
+        println!("{}", BuildError { error }.to_json());
+      } else {
+        eprintln!("error: {}", error);
+      }
+      process::exit(1);
+    }
+  }
 }
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+pub const DEV_PROFILE: &'static str = "dev";
+pub const RELEASE_PROFILE: &'static str = "release";
+
+/// How `build` compiles the workspace, the defaults match `cargo build`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildOptions {
+  pub profile: String,
+  pub features: Vec<String>,
+  pub all_features: bool,
+  pub no_default_features: bool,
+  pub target_dir: Option<PathBuf>,
+  /// The workspace members to build, the default members when empty.
+  pub packages: Vec<String>,
+}
+
+impl Default for BuildOptions {
+  #[inline]
+  fn default() -> Self {
+    BuildOptions {
+      profile: DEV_PROFILE.to_owned(),
+      features: Vec::new(),
+      all_features: false,
+      no_default_features: false,
+      target_dir: None,
+      packages: Vec::new(),
+    }
+  }
+}
@@ -0,0 +1,81 @@
+extern crate lisp_builder;
+
+use std::path::PathBuf;
+
+use lisp_builder::{
+  build_options, command, Artifact, BuildError, BuildOptions, BuildOutput, DEV_PROFILE,
+  RELEASE_PROFILE,
+};
+
+fn options(args: &[&str]) -> BuildOptions {
+  let matches = command()
+    .try_get_matches_from(Some("lisp-builder").iter().chain(args.iter()))
+    .unwrap();
+  build_options(&matches)
+}
+
+#[test]
+fn test_build_options_defaults() {
+  assert_eq!(options(&[]), BuildOptions::default());
+  assert_eq!(options(&[]).profile, DEV_PROFILE);
+}
+
+#[test]
+fn test_build_options_profile() {
+  assert_eq!(options(&["--release"]).profile, RELEASE_PROFILE);
+  assert_eq!(options(&["-r"]).profile, RELEASE_PROFILE);
+  assert_eq!(options(&["--profile", "bench"]).profile, "bench");
+  assert!(command()
+    .try_get_matches_from(["lisp-builder", "--release", "--profile", "bench"])
+    .is_err());
+}
+
+#[test]
+fn test_build_options_features_and_packages() {
+  let options = options(&[
+    "--features",
+    "a,b c",
+    "-F",
+    "d",
+    "--features",
+    " , e",
+    "--all-features",
+    "--no-default-features",
+    "--target-dir",
+    "out",
+    "-p",
+    "x",
+    "--package",
+    "y",
+  ]);
+
+  assert_eq!(options.features, vec!["a", "b", "c", "d", "e"]);
+  assert!(options.all_features);
+  assert!(options.no_default_features);
+  assert_eq!(options.target_dir, Some(PathBuf::from("out")));
+  assert_eq!(options.packages, vec!["x", "y"]);
+}
+
+#[test]
+fn test_to_json() {
+  let output = BuildOutput {
+    profile: RELEASE_PROFILE.to_owned(),
+    artifacts: vec![Artifact {
+      package: "my-lib".to_owned(),
+      name: "my_lib".to_owned(),
+      path: PathBuf::from("out/libmy_lib.so"),
+    }],
+  };
+
+  assert_eq!(
+    output.to_json(),
+    "{\"profile\":\"release\",\"artifacts\":[{\"package\":\"my-lib\",\"name\":\"my_lib\",\"path\":\"out/libmy_lib.so\"}]}"
+  );
+  assert_eq!(
+    BuildError {
+      error: "failed to build \"Cargo.toml\"".to_owned()
+    }
+    .to_json(),
+    "{\"error\":\"failed to build \\\"Cargo.toml\\\"\"}"
+  );
+}
@@ -0,0 +1,418 @@
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+  parse_macro_input, Attribute, AttributeArgs, Data, DeriveInput, Error, FnArg, Ident, ImplItem,
+  ImplItemMethod, ItemImpl, Lit, Meta, NestedMeta, Pat, Path, Type,
+};
+
+#[derive(Default)]
+struct Options {
+  krate: Option<Path>,
+  name: Option<String>,
+  skip: bool,
+  no_identity: bool,
+}
+
+impl Options {
+  #[inline]
+  fn krate(&self) -> Path {
+    self
+      .krate
+      .clone()
+      .unwrap_or_else(|| syn::parse_str("::lisp_runtime").unwrap())
+  }
+
+  #[inline]
+  fn parse_nested<'a, I>(&mut self, nested: I) -> Result<(), Error>
+  where
+    I: IntoIterator<Item = &'a NestedMeta>,
+  {
+    for meta in nested {
+      match meta {
+        &NestedMeta::Meta(Meta::NameValue(ref name_value)) => {
+          let value = match name_value.lit {
+            Lit::Str(ref string) => string.value(),
+            ref lit => return Err(Error::new(lit.span(), "expected a string")),
+          };
+
+          if name_value.path.is_ident("crate") {
+            self.krate = Some(syn::parse_str(&value)?);
+          } else if name_value.path.is_ident("name") {
+            self.name = Some(value);
+          } else {
+            return Err(Error::new(name_value.path.span(), "unknown lisp option"));
+          }
+        }
+        &NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("skip") => self.skip = true,
+        &NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("no_identity") => {
+          self.no_identity = true
+        }
+        meta => return Err(Error::new(meta.span(), "unknown lisp option")),
+      }
+    }
+    Ok(())
+  }
+
+  #[inline]
+  fn parse_attrs(&mut self, attrs: &[Attribute]) -> Result<(), Error> {
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("lisp")) {
+      match attr.parse_meta()? {
+        Meta::List(list) => self.parse_nested(list.nested.iter())?,
+        meta => return Err(Error::new(meta.span(), "expected #[lisp(...)]")),
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Exposes a struct as a lisp Kind.
+///
+/// Generates `LispKind`, a `Trace` impl tracing every `Gc` field or field
+/// marked `#[lisp(trace)]`, and identity based `PartialEq`, `Eq`,
+/// `PartialOrd` and `Hash` impls unless `#[lisp(no_identity)]` is given.
+/// The Kind name defaults to the struct name, `#[lisp(name = "...")]`
+/// overrides it, and `#[lisp(crate = "...")]` sets the runtime crate path.
+#[proc_macro_derive(LispKind, attributes(lisp))]
+pub fn derive_lisp_kind(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand_lisp_kind(input)
+    .unwrap_or_else(Error::into_compile_error)
+    .into()
+}
+
+#[inline]
+fn is_gc_type(ty: &Type) -> bool {
+  match ty {
+    Type::Path(path) => path
+      .path
+      .segments
+      .last()
+      .map(|segment| segment.ident == "Gc")
+      .unwrap_or(false),
+    _ => false,
+  }
+}
+
+#[inline]
+fn has_trace_attr(attrs: &[Attribute]) -> Result<bool, Error> {
+  let mut trace = false;
+
+  for attr in attrs.iter().filter(|attr| attr.path.is_ident("lisp")) {
+    match attr.parse_meta()? {
+      Meta::List(ref list) => {
+        for nested in list.nested.iter() {
+          match nested {
+            &NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("trace") => trace = true,
+            nested => return Err(Error::new(nested.span(), "unknown lisp field option")),
+          }
+        }
+      }
+      meta => return Err(Error::new(meta.span(), "expected #[lisp(trace)]")),
+    }
+  }
+  Ok(trace)
+}
+
+#[inline]
+fn expand_lisp_kind(input: DeriveInput) -> Result<TokenStream2, Error> {
+  let mut options = Options::default();
+  options.parse_attrs(&input.attrs)?;
+
+  let krate = options.krate();
+  let ident = &input.ident;
+  let name = options.name.clone().unwrap_or_else(|| ident.to_string());
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+  let fields = match input.data {
+    Data::Struct(ref data) => &data.fields,
+    _ => {
+      return Err(Error::new(
+        input.span(),
+        "LispKind can only be derived for structs",
+      ))
+    }
+  };
+  let mut traced = Vec::new();
+
+  for (index, field) in fields.iter().enumerate() {
+    if is_gc_type(&field.ty) || has_trace_attr(&field.attrs)? {
+      traced.push(match field.ident {
+        Some(ref ident) => quote!(#ident),
+        None => {
+          let index = syn::Index::from(index);
+          quote!(#index)
+        }
+      });
+    }
+  }
+
+  let identity = if options.no_identity {
+    quote!()
+  } else {
+    quote! {
+      impl #impl_generics #krate::core::cmp::PartialEq for #ident #ty_generics #where_clause {
+        #[inline]
+        fn eq(&self, other: &Self) -> bool {
+          #krate::core::ptr::eq(self, other)
+        }
+      }
+
+      impl #impl_generics #krate::core::cmp::Eq for #ident #ty_generics #where_clause {}
+
+      impl #impl_generics #krate::core::cmp::PartialOrd for #ident #ty_generics #where_clause {
+        #[inline]
+        fn partial_cmp(&self, _other: &Self) -> #krate::core::option::Option<#krate::core::cmp::Ordering> {
+          None
+        }
+      }
+
+      impl #impl_generics #krate::core::hash::Hash for #ident #ty_generics #where_clause {
+        #[inline]
+        fn hash<H: #krate::core::hash::Hasher>(&self, state: &mut H) {
+          #krate::core::ptr::hash(self, state)
+        }
+      }
+    }
+  };
+
+  Ok(quote! {
+    impl #impl_generics #krate::gc::Trace for #ident #ty_generics #where_clause {
+      #[inline]
+      #[allow(unused_imports)]
+      fn trace(&mut self, marked: bool) {
+        use #krate::gc::Trace;
+        #(self.#traced.trace(marked);)*
+      }
+    }
+
+    #identity
+
+    impl #impl_generics #krate::LispKind for #ident #ty_generics #where_clause {
+      #[inline]
+      fn kind_name() -> &'static str {
+        #name
+      }
+    }
+  })
+}
+
+/// Exposes the `pub` methods of an impl block as `type.method` builtins.
+///
+/// Arguments are converted with `FromValue` and results with `IntoResult`,
+/// conversion failures and `Err` results are thrown as catchable errors.
+/// `&self` and `&mut self` receivers take the value as the first argument,
+/// a `&Gc<Object<Scope>>` parameter receives the calling scope and a
+/// `&Gc<Object<Vector>>` parameter receives all arguments, allowing extra
+/// ones. Methods can be renamed with `#[lisp(name = "...")]` or left out
+/// with `#[lisp(skip)]`, `#[lisp_methods(name = "...")]` sets the prefix
+/// which defaults to the lowercased type name.
+#[proc_macro_attribute]
+pub fn lisp_methods(args: TokenStream, input: TokenStream) -> TokenStream {
+  let args = parse_macro_input!(args as AttributeArgs);
+  let input = parse_macro_input!(input as ItemImpl);
+  expand_lisp_methods(args, input)
+    .unwrap_or_else(Error::into_compile_error)
+    .into()
+}
+
+enum Param {
+  Scope,
+  Arguments(Ident),
+  Value(Ident, Box<Type>),
+}
+
+#[inline]
+fn type_string(ty: &Type) -> String {
+  quote!(#ty).to_string().replace(' ', "")
+}
+
+#[inline]
+fn method_params(method: &ImplItemMethod) -> Result<(Option<bool>, Vec<Param>), Error> {
+  let mut receiver = None;
+  let mut params = Vec::new();
+
+  for (index, input) in method.sig.inputs.iter().enumerate() {
+    match input {
+      FnArg::Receiver(receiver_arg) => {
+        if receiver_arg.reference.is_none() {
+          return Err(Error::new(
+            receiver_arg.span(),
+            "lisp methods must take self by reference",
+          ));
+        }
+        receiver = Some(receiver_arg.mutability.is_some());
+      }
+      FnArg::Typed(pat_type) => {
+        let ident = match *pat_type.pat {
+          Pat::Ident(ref pat_ident) => pat_ident.ident.clone(),
+          _ => Ident::new(&format!("arg{}", index), Span::call_site()),
+        };
+        let ty = type_string(&pat_type.ty);
+
+        params.push(
+          if ty.ends_with("Gc<Object<Scope>>") && ty.starts_with('&') {
+            Param::Scope
+          } else if ty.ends_with("Gc<Object<Vector>>") && ty.starts_with('&') {
+            Param::Arguments(ident)
+          } else {
+            Param::Value(ident, pat_type.ty.clone())
+          },
+        );
+      }
+    }
+  }
+
+  Ok((receiver, params))
+}
+
+#[inline]
+fn expand_lisp_methods(args: AttributeArgs, mut input: ItemImpl) -> Result<TokenStream2, Error> {
+  let mut options = Options::default();
+  options.parse_nested(args.iter())?;
+
+  let krate = options.krate();
+  let self_ty = input.self_ty.clone();
+  let prefix = match options.name {
+    Some(ref name) => name.clone(),
+    None => match *self_ty {
+      Type::Path(ref path) => path
+        .path
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string().to_lowercase())
+        .ok_or_else(|| Error::new(self_ty.span(), "expected a type name"))?,
+      _ => return Err(Error::new(self_ty.span(), "expected a type name")),
+    },
+  };
+  let mut builtins = Vec::new();
+
+  for item in input.items.iter_mut() {
+    let method = match item {
+      &mut ImplItem::Method(ref mut method) => method,
+      _ => continue,
+    };
+    let mut method_options = Options::default();
+    method_options.parse_attrs(&method.attrs)?;
+    method.attrs.retain(|attr| !attr.path.is_ident("lisp"));
+
+    match method.vis {
+      syn::Visibility::Public(_) => {}
+      _ => continue,
+    }
+    if method_options.skip {
+      continue;
+    }
+    if !method.sig.generics.params.is_empty() || method.sig.unsafety.is_some() {
+      return Err(Error::new(
+        method.sig.span(),
+        "generic or unsafe methods can not be lisp builtins, add #[lisp(skip)]",
+      ));
+    }
+
+    let method_ident = &method.sig.ident;
+    let builtin_name = format!(
+      "{}.{}",
+      prefix,
+      method_options
+        .name
+        .clone()
+        .unwrap_or_else(|| method_ident.to_string())
+    );
+    let (receiver, params) = method_params(method)?;
+
+    let mut param_names = Vec::new();
+    let mut conversions = Vec::new();
+    let mut call_args = Punctuated::<TokenStream2, syn::token::Comma>::new();
+    let mut index = 0_usize;
+    let mut variadic = false;
+
+    if let Some(mutable) = receiver {
+      let this_mut = if mutable { quote!(mut) } else { quote!() };
+      param_names.push(prefix.clone());
+      conversions.push(quote! {
+        let #this_mut this = match #krate::from_argument::<
+          #krate::gc::Gc<#krate::Object<#self_ty>>
+        >(scope, args, #index) {
+          Ok(value) => value,
+          Err(error) => return #krate::throw_error(scope, #krate::IntoValue::into_value(error, scope)),
+        };
+      });
+      index += 1;
+    }
+
+    for param in params.iter() {
+      match param {
+        Param::Scope => call_args.push(quote!(scope)),
+        Param::Arguments(ident) => {
+          param_names.push(format!("...{}", ident));
+          call_args.push(quote!(args));
+          variadic = true;
+        }
+        Param::Value(ident, ty) => {
+          param_names.push(ident.to_string());
+          conversions.push(quote! {
+            let #ident = match #krate::from_argument::<#ty>(scope, args, #index) {
+              Ok(value) => value,
+              Err(error) => return #krate::throw_error(scope, #krate::IntoValue::into_value(error, scope)),
+            };
+          });
+          call_args.push(quote!(#ident));
+          index += 1;
+        }
+      }
+    }
+
+    let arity_check = if variadic {
+      quote!()
+    } else {
+      quote! {
+        if let Err(error) = #krate::check_arity(args, #index) {
+          return #krate::throw_error(scope, #krate::IntoValue::into_value(error, scope));
+        }
+      }
+    };
+    let call = if receiver.is_some() {
+      quote!(this.#method_ident(#call_args))
+    } else {
+      quote!(<#self_ty>::#method_ident(#call_args))
+    };
+
+    builtins.push(quote! {
+      #krate::add_external_function(
+        scope, module_path!(),
+        #builtin_name,
+        vec![#(#param_names),*],
+        |scope: &#krate::gc::Gc<#krate::Object<#krate::Scope>>,
+         args: &#krate::gc::Gc<#krate::Object<#krate::Vector>>| {
+          #arity_check
+          #(#conversions)*
+          match #krate::IntoResult::into_result(#call, scope) {
+            Ok(value) => value,
+            Err(error) => #krate::throw_error(scope, error),
+          }
+        },
+      );
+    });
+  }
+
+  let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+
+  Ok(quote! {
+    #input
+
+    impl #impl_generics #krate::LispMethods for #self_ty #where_clause {
+      #[inline]
+      fn init_methods(scope: &#krate::gc::Gc<#krate::Object<#krate::Scope>>) {
+        #(#builtins)*
+      }
+    }
+  })
+}
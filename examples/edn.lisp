@@ -0,0 +1,8 @@
+(def config (edn.read "{:name lisp, :version #u8 1, :ratio 0.5, :tags #{:a :b}, #_ :ignored :chars [\a \space]}"))
+
+(println config)
+(println (edn.write config))
+
+(edn.register_tag (quote point) (fn [value] (vector.get value 0_usize)))
+(println (edn.read "#point [1 2]"))
+(println (edn.read "#color :red"))
\ No newline at end of file
@@ -0,0 +1,30 @@
+/* cc -shared -fPIC -I../../plugin/include -o libhello_c.so hello_c.c */
+#include <stdio.h>
+
+#include "lisp_plugin.h"
+
+lisp_value lisp_hello_c(const lisp_host *host, lisp_context *context, const lisp_value *args,
+                        size_t len) {
+  int64_t a, b;
+
+  if (len != 2 || !host->get_int(context, args[0], &a) || !host->get_int(context, args[1], &b)) {
+    static const char error[] = "expected two integers";
+    return host->throw_error(context, error, sizeof(error) - 1);
+  }
+  printf("Hello, world from C!\n");
+  return host->new_int(context, a + b);
+}
+
+static const lisp_plugin_function FUNCTIONS[] = {
+    {"lisp_hello_c", "a b", "Prints a greeting and adds two integers", lisp_hello_c},
+};
+
+static const lisp_plugin PLUGIN = {LISP_PLUGIN_ABI_VERSION, "hello_c", FUNCTIONS,
+                                   sizeof(FUNCTIONS) / sizeof(FUNCTIONS[0])};
+
+const lisp_plugin *lisp_plugin_init(const lisp_host *host) {
+  if (host->abi_version != LISP_PLUGIN_ABI_VERSION || host->size < sizeof(lisp_host)) {
+    return NULL;
+  }
+  return &PLUGIN;
+}
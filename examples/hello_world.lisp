@@ -1,3 +1,5 @@
-(import liblisp_hello_world "../builder/lisp-builder-out/liblisp_hello_world.so")
+; build with `lisp-builder -i ./examples/hello_world/Cargo.toml` from the project root
+(import lisp_hello_world "native:lisp_hello_world")
 
-(dylib.call liblisp_hello_world :lisp_hello_world)
\ No newline at end of file
+(lisp_hello_world)
+(lisp_hello_world "Lisp")
@@ -1,13 +1,29 @@
-extern crate lisp;
+#[macro_use]
+extern crate lisp_plugin;
 
-use lisp::{
-  gc::Gc,
-  runtime::{nil_value, Object, Scope, Value, Vector},
-};
+use lisp_plugin::{Context, LispContext, LispHost, LispValue};
+
+lisp_plugin!(
+  "hello_world",
+  lisp_hello_world("...name") "Prints a greeting to name, or to the world",
+);
 
 #[inline]
 #[no_mangle]
-pub fn lisp_hello_world(scope: &Gc<Object<Scope>>, _args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
-  println!("Hello, world from Rust!");
-  nil_value(scope).into_value()
+pub unsafe extern "C" fn lisp_hello_world(
+  host: *const LispHost,
+  context: *mut LispContext,
+  args: *const LispValue,
+  len: usize,
+) -> LispValue {
+  let context = Context::from_raw(host, context, args, len);
+
+  match context.arg(0) {
+    Some(name) => match context.get_str(name) {
+      Some(name) => println!("Hello, {} from Rust!", name),
+      None => return context.throw_error("expected a name"),
+    },
+    None => println!("Hello, world from Rust!"),
+  }
+  context.nil()
 }
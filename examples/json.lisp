@@ -0,0 +1,7 @@
+(def config {:name "lisp", :version "0.1.0", :deps [1, 2, 3], :debug false})
+
+(println (json.stringify config))
+(println (json.stringify config {:pretty true}))
+
+(println (json.parse "[1, 2.5, true, null, []]"))
+(println (json.parse (json.stringify config) {:keywords true}))
\ No newline at end of file
@@ -7,6 +7,9 @@ use core::hash::{Hash, Hasher};
 use core::ops::{Deref, DerefMut};
 use core::{mem, ptr};
 
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+
 use super::Trace;
 
 pub struct Gc<T>
@@ -21,7 +24,7 @@ unsafe impl<T> Sync for Gc<T> {}
 
 impl<T> Trace for Gc<T>
 where
-  T: Trace,
+  T: Trace + ?Sized,
 {
   fn trace(&mut self, marked: bool) {
     self.as_mut().trace(marked)
@@ -114,7 +117,7 @@ where
   pub unsafe fn unsafe_as_mut(&self) -> &mut T {
     &mut *self.as_ptr()
   }
-  
+
   #[inline(always)]
   pub unsafe fn unsafe_drop(self) {
     ptr::drop_in_place(self.as_ptr());
@@ -268,3 +271,17 @@ where
     Gc { ptr: self.ptr }
   }
 }
+
+#[cfg(feature = "serde")]
+impl<T> Serialize for Gc<T>
+where
+  T: Serialize + ?Sized,
+{
+  #[inline(always)]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    self.as_ref().serialize(serializer)
+  }
+}
@@ -2,6 +2,8 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 mod gc;
 mod trace;
@@ -1,4 +1,5 @@
 use alloc::string::String;
+use alloc::vec::Vec;
 
 pub trait Trace {
   #[inline(always)]
@@ -89,3 +90,34 @@ where
     }
   }
 }
+
+impl<T> Trace for Vec<T>
+where
+  T: Trace,
+{
+  #[inline(always)]
+  fn trace(&mut self, marked: bool) {
+    for value in self.iter_mut() {
+      value.trace(marked);
+    }
+  }
+}
+
+macro_rules! tuple_trace {
+  ($($name:ident : $index:tt),*) => {
+    impl<$($name),*> Trace for ($($name,)*)
+    where
+      $($name: Trace),*
+    {
+      #[inline(always)]
+      fn trace(&mut self, marked: bool) {
+        $(self.$index.trace(marked);)*
+      }
+    }
+  };
+}
+
+tuple_trace!(A: 0);
+tuple_trace!(A: 0, B: 1);
+tuple_trace!(A: 0, B: 1, C: 2);
+tuple_trace!(A: 0, B: 1, C: 2, D: 3);
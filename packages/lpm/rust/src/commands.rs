@@ -0,0 +1,270 @@
+use std::env::{join_paths, split_paths, var_os};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use lisp::{
+  VersionReq, NATIVE_BUILDER_OUT, NATIVE_PATH_ENV, PACKAGE_INSTALL_DIR, PACKAGE_PATH_ENV,
+};
+
+use super::{
+  hash_package, read_lock, read_manifest, resolve, resolve_registry_version, write_lock,
+  write_manifest, Dependency, Lock, LockedPackage, Manifest, Options, Source, IGNORED_DIRS,
+  MANIFEST_FILE,
+};
+
+/// Creates a `package.lisp` and an empty `src/lib.lisp` in the root.
+#[inline]
+pub fn init(options: &Options, name: Option<&str>) -> Result<Manifest, String> {
+  if options.root.join(MANIFEST_FILE).exists() {
+    return Err(format!(
+      "{:?} already exists",
+      options.root.join(MANIFEST_FILE)
+    ));
+  }
+  let name = match name {
+    Some(name) => name.to_owned(),
+    None => options
+      .root
+      .canonicalize()
+      .ok()
+      .and_then(|root| {
+        root
+          .file_name()
+          .and_then(|name| name.to_str())
+          .map(ToOwned::to_owned)
+      })
+      .ok_or_else(|| "failed to get a package name from the root directory".to_owned())?,
+  };
+  let manifest = Manifest::new(&name);
+  let src = options.root.join("src");
+  let lib = src.join("lib.lisp");
+
+  fs::create_dir_all(&src).map_err(|error| format!("failed to create {:?}: {}", src, error))?;
+  if !lib.exists() {
+    fs::write(&lib, "").map_err(|error| format!("failed to write {:?}: {}", lib, error))?;
+  }
+  write_manifest(&options.root, &manifest)?;
+
+  Ok(manifest)
+}
+
+/// Adds `dep` to the root manifest, registry dependencies without a version
+/// require the newest version in the registry or a compatible one.
+#[inline]
+pub fn add(options: &Options, mut dep: Dependency) -> Result<Dependency, String> {
+  let mut manifest = read_manifest(&options.root)?;
+  let requirement = match dep.version {
+    Some(ref version) => VersionReq::parse(version)?,
+    None => VersionReq::any(),
+  };
+
+  if let Some(ref path) = dep.path {
+    if !options.root.join(path).join(MANIFEST_FILE).is_file() {
+      return Err(format!(
+        "{:?} does not contain a {}",
+        options.root.join(path),
+        MANIFEST_FILE
+      ));
+    }
+  } else {
+    let version = resolve_registry_version(&options.registry, &dep.name, &[&requirement])?;
+
+    if dep.version.is_none() {
+      dep.version = Some(version.to_string());
+    }
+  }
+
+  manifest.set_dependency(dep.clone());
+  write_manifest(&options.root, &manifest)?;
+
+  Ok(dep)
+}
+
+/// Resolves the dependencies of the root package, copies registry packages
+/// into `lisp_packages` and writes the lock file.
+///
+/// Locked registry versions are kept while they match the manifest, and a
+/// locked package whose content hash changed is an error that is reported
+/// before the installed copy is replaced.
+#[inline]
+pub fn install(options: &Options) -> Result<Lock, String> {
+  let root = options
+    .root
+    .canonicalize()
+    .map_err(|error| format!("failed to read {:?}: {}", options.root, error))?;
+  let manifest = read_manifest(&root)?;
+  let previous = read_lock(&root)?;
+  let install_dir = root.join(PACKAGE_INSTALL_DIR);
+
+  let mut lock = Lock::default();
+
+  for package in resolve(options, &root, &manifest, &previous)? {
+    let version = package.version.to_string();
+    let hash = hash_package(&package.dir)?;
+
+    if let Some(locked) = previous.package(&package.name) {
+      if package.source == Source::Registry
+        && locked.source == package.source
+        && locked.version == version
+        && locked.hash != hash
+      {
+        return Err(format!(
+          "hash mismatch for {} {}, the lock file has {} but the registry has {}",
+          package.name, version, locked.hash, hash
+        ));
+      }
+    }
+
+    if package.source == Source::Registry {
+      let dir = install_dir.join(&package.name);
+
+      if dir.exists() {
+        fs::remove_dir_all(&dir)
+          .map_err(|error| format!("failed to remove {:?}: {}", dir, error))?;
+      }
+      copy_dir(&package.dir, &dir)?;
+    }
+
+    lock.packages.push(LockedPackage {
+      name: package.name,
+      version,
+      source: package.source,
+      hash,
+    });
+  }
+
+  lock.packages.sort_by(|a, b| a.name.cmp(&b.name));
+  remove_unlocked(&install_dir, &lock)?;
+  write_lock(&root, &lock)?;
+
+  Ok(lock)
+}
+
+/// Installs, then runs the builder for the root package and each locked
+/// package with a `rust/Cargo.toml`, returns the directories it built into.
+#[inline]
+pub fn build(options: &Options) -> Result<Vec<PathBuf>, String> {
+  let lock = install(options)?;
+  let root = options.root.canonicalize().unwrap();
+  let mut out_dirs = Vec::new();
+
+  for dir in Some(root.clone()).into_iter().chain(
+    lock
+      .packages
+      .iter()
+      .map(|package| package_dir(&root, package)),
+  ) {
+    let cargo_toml = dir.join("rust").join("Cargo.toml");
+
+    if !cargo_toml.is_file() {
+      continue;
+    }
+    let out_dir = dir.join(NATIVE_BUILDER_OUT);
+
+    let status = Command::new(&options.builder)
+      .arg("--input")
+      .arg(&cargo_toml)
+      .arg("--dest")
+      .arg(&out_dir)
+      .status()
+      .map_err(|error| {
+        format!(
+          "failed to run {:?}, set LPM_BUILDER to the lisp-builder executable: {}",
+          options.builder, error
+        )
+      })?;
+
+    if !status.success() {
+      return Err(format!("failed to build {:?}, {}", cargo_toml, status));
+    }
+    out_dirs.push(out_dir);
+  }
+
+  Ok(out_dirs)
+}
+
+/// Builds, then runs `file` or `src/main.lisp` with the installed packages on
+/// `LISP_PATH` and the built libraries on `LISP_NATIVE_PATH`, returns the exit
+/// code.
+#[inline]
+pub fn run(options: &Options, file: Option<&Path>) -> Result<i32, String> {
+  let out_dirs = build(options)?;
+  let root = options.root.canonicalize().unwrap();
+  let file = file
+    .map(Path::to_path_buf)
+    .unwrap_or_else(|| root.join("src").join("main.lisp"));
+
+  let status = Command::new(&options.lisp)
+    .arg(&file)
+    .env(
+      PACKAGE_PATH_ENV,
+      prepend_paths(vec![root.join(PACKAGE_INSTALL_DIR)], PACKAGE_PATH_ENV)?,
+    )
+    .env(NATIVE_PATH_ENV, prepend_paths(out_dirs, NATIVE_PATH_ENV)?)
+    .status()
+    .map_err(|error| {
+      format!(
+        "failed to run {:?}, set LPM_LISP to the lisp executable: {}",
+        options.lisp, error
+      )
+    })?;
+
+  Ok(status.code().unwrap_or(1))
+}
+
+#[inline]
+fn package_dir(root: &Path, package: &LockedPackage) -> PathBuf {
+  match package.source {
+    Source::Path(ref path) => root.join(path),
+    Source::Registry => root.join(PACKAGE_INSTALL_DIR).join(&package.name),
+  }
+}
+
+#[inline]
+fn prepend_paths(mut paths: Vec<PathBuf>, env: &str) -> Result<::std::ffi::OsString, String> {
+  if let Some(existing) = var_os(env) {
+    paths.extend(split_paths(&existing));
+  }
+  join_paths(paths).map_err(|error| format!("invalid {}: {}", env, error))
+}
+
+#[inline]
+fn remove_unlocked(install_dir: &Path, lock: &Lock) -> Result<(), String> {
+  if let Ok(entries) = fs::read_dir(install_dir) {
+    for entry in entries.filter_map(Result::ok) {
+      let name = entry.file_name().to_string_lossy().into_owned();
+
+      match lock.package(&name) {
+        Some(package) if package.source == Source::Registry => {}
+        _ => fs::remove_dir_all(entry.path())
+          .map_err(|error| format!("failed to remove {:?}: {}", entry.path(), error))?,
+      }
+    }
+  }
+  Ok(())
+}
+
+#[inline]
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+  fs::create_dir_all(to).map_err(|error| format!("failed to create {:?}: {}", to, error))?;
+
+  for entry in
+    fs::read_dir(from).map_err(|error| format!("failed to read {:?}: {}", from, error))?
+  {
+    let path = entry
+      .map_err(|error| format!("failed to read {:?}: {}", from, error))?
+      .path();
+    let name = path.file_name().unwrap();
+
+    if path.is_dir() {
+      if !IGNORED_DIRS.contains(&name.to_str().unwrap_or("")) {
+        copy_dir(&path, &to.join(name))?;
+      }
+    } else {
+      fs::copy(&path, to.join(name))
+        .map_err(|error| format!("failed to copy {:?}: {}", path, error))?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,102 @@
+use std::ops::Deref;
+use std::path::Path;
+
+use lisp::file_loader;
+use lisp::gc::Gc;
+use lisp::runtime::{
+  new_keyword, new_string, scope_get_with_kind, Keyword, Map, Object, Scope, Value, Vector,
+};
+
+/// Runs the lisp file at `path` in a new context and passes the map it
+/// exports as `name` to `f`.
+#[inline]
+pub fn load_export<T, F>(path: &Path, name: &str, f: F) -> Result<T, String>
+where
+  F: FnOnce(&Gc<Object<Scope>>, &Gc<Object<Map>>) -> Result<T, String>,
+{
+  let path = path
+    .canonicalize()
+    .map_err(|error| format!("failed to read {:?}: {}", path, error))?;
+  let scope = lisp::new();
+  let module = scope_get_with_kind::<Map>(&scope, "module")
+    .expect("module is not defined")
+    .clone();
+  let loaded = file_loader(&scope, &module, &path.to_str().unwrap().to_owned())?
+    .ok_or_else(|| format!("failed to load {:?}", path))?;
+  let value = loaded
+    .get(&new_string(&scope, "exports").into_value())
+    .and_then(|exports| exports.downcast_ref::<Object<Map>>())
+    .and_then(|exports| exports.get(&new_string(&scope, name).into_value()))
+    .and_then(|value| value.downcast_ref::<Object<Map>>())
+    .map(Clone::clone)
+    .ok_or_else(|| format!("{:?} does not export a {} map", path, name))?;
+
+  f(&scope, &value)
+}
+
+#[inline]
+pub fn get(scope: &Gc<Object<Scope>>, map: &Gc<Object<Map>>, key: &str) -> Option<Gc<dyn Value>> {
+  map
+    .get(&new_keyword(scope, key).into_value())
+    .or_else(|| map.get(&new_string(scope, key).into_value()))
+    .map(Clone::clone)
+}
+
+/// Returns a string or keyword value as a String.
+#[inline]
+pub fn to_name(value: &Gc<dyn Value>) -> Option<String> {
+  if let Some(string) = value.downcast_ref::<Object<String>>() {
+    Some(string.value().clone())
+  } else {
+    value
+      .downcast_ref::<Object<Keyword>>()
+      .map(|keyword| keyword.value().deref().clone())
+  }
+}
+
+#[inline]
+pub fn get_name(scope: &Gc<Object<Scope>>, map: &Gc<Object<Map>>, key: &str) -> Option<String> {
+  get(scope, map, key).and_then(|value| to_name(&value))
+}
+
+#[inline]
+pub fn get_map(
+  scope: &Gc<Object<Scope>>,
+  map: &Gc<Object<Map>>,
+  key: &str,
+) -> Option<Gc<Object<Map>>> {
+  get(scope, map, key).and_then(|value| value.downcast_ref::<Object<Map>>().map(Clone::clone))
+}
+
+#[inline]
+pub fn get_vector(
+  scope: &Gc<Object<Scope>>,
+  map: &Gc<Object<Map>>,
+  key: &str,
+) -> Option<Gc<Object<Vector>>> {
+  get(scope, map, key).and_then(|value| value.downcast_ref::<Object<Vector>>().map(Clone::clone))
+}
+
+/// Strings are read up to the next quote, so they can not contain one.
+#[inline]
+pub fn render_string(value: &str) -> Result<String, String> {
+  if value.contains('"') {
+    Err(format!("{:?} can not contain a quote", value))
+  } else {
+    Ok(format!("\"{}\"", value))
+  }
+}
+
+/// Renders `value` as a keyword when it reads back as one.
+#[inline]
+pub fn render_name(value: &str) -> Result<String, String> {
+  if !value.is_empty()
+    && value
+      .chars()
+      .all(|ch| ch.is_alphanumeric() || "-_.+*!?<>=/".contains(ch))
+  {
+    Ok(format!(":{}", value))
+  } else {
+    render_string(value)
+  }
+}
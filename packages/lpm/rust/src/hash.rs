@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+/// Directories that are build output or installed dependencies and are not
+/// part of a package's content.
+pub const IGNORED_DIRS: [&'static str; 4] = [".git", "target", "lisp-builder-out", "lisp_packages"];
+
+#[inline]
+pub fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[inline]
+pub fn sha256_hex(bytes: &[u8]) -> String {
+  to_hex(&Sha256::digest(bytes))
+}
+
+/// Hashes the relative path and contents of every file in a package, in path
+/// order, so the hash does not depend on where the package is installed.
+#[inline]
+pub fn hash_package(dir: &Path) -> Result<String, String> {
+  let mut files = Vec::new();
+  collect_files(dir, dir, &mut files)?;
+  files.sort();
+
+  let mut sha = Sha256::new();
+  for file in files {
+    let contents = fs::read(dir.join(&file))
+      .map_err(|error| format!("failed to read {:?}: {}", dir.join(&file), error))?;
+
+    sha.update(file.as_bytes());
+    sha.update([0]);
+    sha.update((contents.len() as u64).to_be_bytes());
+    sha.update(&contents);
+  }
+  Ok(format!("sha256-{}", to_hex(&sha.finalize())))
+}
+
+#[inline]
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), String> {
+  let entries =
+    fs::read_dir(dir).map_err(|error| format!("failed to read {:?}: {}", dir, error))?;
+
+  for entry in entries {
+    let path = entry
+      .map_err(|error| format!("failed to read {:?}: {}", dir, error))?
+      .path();
+    let name = path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .unwrap_or("");
+
+    if path.is_dir() {
+      if !IGNORED_DIRS.contains(&name) {
+        collect_files(root, &path, files)?;
+      }
+    } else {
+      let relative = path.strip_prefix(root).unwrap();
+      files.push(
+        relative
+          .components()
+          .map(|component| component.as_os_str().to_string_lossy().into_owned())
+          .collect::<Vec<String>>()
+          .join("/"),
+      );
+    }
+  }
+  Ok(())
+}
@@ -1 +1,19 @@
 extern crate lisp;
+extern crate sha2;
+
+mod commands;
+mod data;
+mod hash;
+mod lock;
+mod manifest;
+mod options;
+mod registry;
+mod resolve;
+
+pub use self::commands::*;
+pub use self::hash::*;
+pub use self::lock::*;
+pub use self::manifest::*;
+pub use self::options::*;
+pub use self::registry::*;
+pub use self::resolve::*;
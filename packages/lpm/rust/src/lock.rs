@@ -0,0 +1,127 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use lisp::runtime::{Map, Object};
+
+use super::data::{get_name, get_vector, load_export, render_name, render_string};
+
+pub const LOCK_FILE: &'static str = "package-lock.lisp";
+pub const LOCK_VERSION: &'static str = "1";
+
+/// Where a locked package came from, path sources are relative to the root
+/// package.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+  Path(String),
+  Registry,
+}
+
+impl Source {
+  #[inline]
+  pub fn parse(source: &str) -> Result<Self, String> {
+    if source == "registry" {
+      Ok(Source::Registry)
+    } else {
+      source
+        .strip_prefix("path:")
+        .map(|path| Source::Path(path.to_owned()))
+        .ok_or_else(|| format!("invalid package source {:?}", source))
+    }
+  }
+}
+
+impl fmt::Display for Source {
+  #[inline]
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      &Source::Path(ref path) => write!(f, "path:{}", path),
+      &Source::Registry => write!(f, "registry"),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockedPackage {
+  pub name: String,
+  pub version: String,
+  pub source: Source,
+  pub hash: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Lock {
+  pub packages: Vec<LockedPackage>,
+}
+
+impl Lock {
+  #[inline]
+  pub fn package(&self, name: &str) -> Option<&LockedPackage> {
+    self.packages.iter().find(|package| package.name == name)
+  }
+}
+
+/// Reads the lock file in `dir`, a missing lock file is an empty lock.
+#[inline]
+pub fn read_lock(dir: &Path) -> Result<Lock, String> {
+  let path = dir.join(LOCK_FILE);
+
+  if !path.is_file() {
+    return Ok(Lock::default());
+  }
+
+  load_export(&path, "lock", |scope, lock| {
+    let mut packages = Vec::new();
+
+    for package in get_vector(scope, lock, "packages")
+      .ok_or_else(|| format!("{:?} has no :packages", path))?
+      .iter()
+    {
+      let package = package
+        .downcast_ref::<Object<Map>>()
+        .ok_or_else(|| format!("{:?} has a package that is not a map", path))?;
+      let field = |key: &str| {
+        get_name(scope, package, key)
+          .ok_or_else(|| format!("{:?} has a package without :{}", path, key))
+      };
+
+      packages.push(LockedPackage {
+        name: field("name")?,
+        version: field("version")?,
+        source: Source::parse(&field("source")?)?,
+        hash: field("hash")?,
+      });
+    }
+
+    Ok(Lock { packages })
+  })
+}
+
+#[inline]
+pub fn render_lock(lock: &Lock) -> Result<String, String> {
+  let mut out = String::new();
+
+  out.push_str("(def lock {\n");
+  out.push_str(&format!("  :version {}\n", render_string(LOCK_VERSION)?));
+  out.push_str("  :packages [\n");
+  for package in lock.packages.iter() {
+    out.push_str(&format!(
+      "    {{ :name {} :version {} :source {} :hash {} }}\n",
+      render_name(&package.name)?,
+      render_string(&package.version)?,
+      render_string(&package.source.to_string())?,
+      render_string(&package.hash)?
+    ));
+  }
+  out.push_str("  ]\n})\n\n(export lock)\n");
+
+  Ok(out)
+}
+
+#[inline]
+pub fn write_lock(dir: &Path, lock: &Lock) -> Result<(), String> {
+  let path = dir.join(LOCK_FILE);
+
+  fs::write(&path, render_lock(lock)?)
+    .map_err(|error| format!("failed to write {:?}: {}", path, error))
+}
@@ -0,0 +1,120 @@
+extern crate clap;
+extern crate lpm;
+
+use clap::{Arg, ArgMatches, Command};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const NAME: &'static str = env!("CARGO_PKG_NAME");
+const DESCRIPTION: &'static str = env!("CARGO_PKG_DESCRIPTION");
+const AUTHORS: &'static str = env!("CARGO_PKG_AUTHORS");
+const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+fn main() {
+  let matches = Command::new(NAME)
+    .version(VERSION)
+    .author(AUTHORS)
+    .about(DESCRIPTION)
+    .subcommand_required(true)
+    .arg(
+      Arg::new("root")
+        .short('C')
+        .long("root")
+        .takes_value(true)
+        .default_value(".")
+        .help("Sets the directory of the root package"),
+    )
+    .arg(
+      Arg::new("registry")
+        .long("registry")
+        .takes_value(true)
+        .help("Sets the registry directory, defaults to LPM_REGISTRY or ~/.lpm/registry"),
+    )
+    .subcommand(
+      Command::new("init")
+        .about("Creates a package.lisp in the root directory")
+        .arg(
+          Arg::new("name")
+            .long("name")
+            .takes_value(true)
+            .help("Sets the package name, defaults to the directory name"),
+        ),
+    )
+    .subcommand(
+      Command::new("add")
+        .about("Adds a dependency to package.lisp")
+        .arg(Arg::new("name").index(1).required(true))
+        .arg(
+          Arg::new("version")
+            .long("version")
+            .takes_value(true)
+            .help("Sets the version constraint"),
+        )
+        .arg(
+          Arg::new("path")
+            .long("path")
+            .takes_value(true)
+            .help("Uses a local package instead of the registry"),
+        ),
+    )
+    .subcommand(Command::new("install").about("Installs dependencies and writes the lock file"))
+    .subcommand(Command::new("build").about("Builds the native libraries of all packages"))
+    .subcommand(
+      Command::new("run")
+        .about("Builds and runs a file, defaults to src/main.lisp")
+        .arg(Arg::new("file").index(1).required(false)),
+    )
+    .get_matches();
+
+  let mut options = lpm::Options::new(PathBuf::from(matches.value_of("root").unwrap_or(".")));
+  if let Some(registry) = matches.value_of("registry") {
+    options.registry = PathBuf::from(registry);
+  }
+
+  match run_command(&options, &matches) {
+    Ok(code) => process::exit(code),
+    Err(error) => {
+      eprintln!("error: {}", error);
+      process::exit(1);
+    }
+  }
+}
+
+fn run_command(options: &lpm::Options, matches: &ArgMatches) -> Result<i32, String> {
+  match matches.subcommand() {
+    Some(("init", matches)) => {
+      let manifest = lpm::init(options, matches.value_of("name"))?;
+      println!("created package {}", manifest.name);
+    }
+    Some(("add", matches)) => {
+      let dep = lpm::add(
+        options,
+        lpm::Dependency::new(
+          matches.value_of("name").unwrap(),
+          matches.value_of("version"),
+          matches.value_of("path"),
+        ),
+      )?;
+      println!(
+        "added {} {}",
+        dep.name,
+        dep.path.or(dep.version).unwrap_or_else(|| "*".to_owned())
+      );
+    }
+    Some(("install", _)) => {
+      for package in lpm::install(options)?.packages {
+        println!("{} {} {}", package.name, package.version, package.source);
+      }
+    }
+    Some(("build", _)) => {
+      for out_dir in lpm::build(options)? {
+        println!("built {}", out_dir.display());
+      }
+    }
+    Some(("run", matches)) => {
+      return lpm::run(options, matches.value_of("file").map(Path::new));
+    }
+    _ => unreachable!(),
+  }
+  Ok(0)
+}
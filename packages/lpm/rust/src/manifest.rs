@@ -0,0 +1,162 @@
+use std::fs;
+use std::path::Path;
+
+use lisp::runtime::{Map, Object};
+
+use super::data::{
+  get_map, get_name, get_vector, load_export, render_name, render_string, to_name,
+};
+
+pub const MANIFEST_FILE: &'static str = "package.lisp";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dependency {
+  pub name: String,
+  pub version: Option<String>,
+  pub path: Option<String>,
+}
+
+impl Dependency {
+  #[inline]
+  pub fn new(name: &str, version: Option<&str>, path: Option<&str>) -> Self {
+    Dependency {
+      name: name.to_owned(),
+      version: version.map(ToOwned::to_owned),
+      path: path.map(ToOwned::to_owned),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Manifest {
+  pub name: String,
+  pub version: String,
+  pub authors: Vec<String>,
+  pub description: Option<String>,
+  pub deps: Vec<Dependency>,
+}
+
+impl Manifest {
+  #[inline]
+  pub fn new(name: &str) -> Self {
+    Manifest {
+      name: name.to_owned(),
+      version: "0.1.0".to_owned(),
+      authors: Vec::new(),
+      description: None,
+      deps: Vec::new(),
+    }
+  }
+
+  #[inline]
+  pub fn dependency(&self, name: &str) -> Option<&Dependency> {
+    self.deps.iter().find(|dep| dep.name == name)
+  }
+
+  /// Adds `dep` or replaces the dependency with the same name.
+  #[inline]
+  pub fn set_dependency(&mut self, dep: Dependency) -> &mut Self {
+    self.deps.retain(|existing| existing.name != dep.name);
+    self.deps.push(dep);
+    self.deps.sort_by(|a, b| a.name.cmp(&b.name));
+    self
+  }
+}
+
+/// Reads the `package` map exported by the manifest in `dir`.
+#[inline]
+pub fn read_manifest(dir: &Path) -> Result<Manifest, String> {
+  let path = dir.join(MANIFEST_FILE);
+
+  load_export(&path, "package", |scope, package| {
+    let name =
+      get_name(scope, package, "name").ok_or_else(|| format!("{:?} has no :name", path))?;
+    let mut manifest = Manifest::new(&name);
+
+    if let Some(version) = get_name(scope, package, "version") {
+      manifest.version = version;
+    }
+    if let Some(authors) = get_vector(scope, package, "authors") {
+      manifest.authors = authors.iter().filter_map(to_name).collect();
+    }
+    manifest.description = get_name(scope, package, "description");
+
+    if let Some(deps) = get_map(scope, package, "deps") {
+      for (dep_name, dep) in deps.iter() {
+        let dep_name =
+          to_name(dep_name).ok_or_else(|| format!("{:?} has an invalid dependency name", path))?;
+        let dep = dep
+          .downcast_ref::<Object<Map>>()
+          .ok_or_else(|| format!("dependency {} in {:?} is not a map", dep_name, path))?;
+
+        manifest.set_dependency(Dependency {
+          name: dep_name,
+          version: get_name(scope, dep, "version"),
+          path: get_name(scope, dep, "path"),
+        });
+      }
+    }
+
+    Ok(manifest)
+  })
+}
+
+#[inline]
+pub fn render_manifest(manifest: &Manifest) -> Result<String, String> {
+  let mut out = String::new();
+
+  out.push_str("(def package {\n");
+  out.push_str(&format!("  :name {}\n", render_name(&manifest.name)?));
+  out.push_str(&format!(
+    "  :version {}\n",
+    render_string(&manifest.version)?
+  ));
+  if !manifest.authors.is_empty() {
+    let authors = manifest
+      .authors
+      .iter()
+      .map(|author| render_string(author))
+      .collect::<Result<Vec<String>, String>>()?;
+    out.push_str(&format!("  :authors [{}]\n", authors.join(" ")));
+  }
+  if let Some(ref description) = manifest.description {
+    out.push_str(&format!("  :description {}\n", render_string(description)?));
+  }
+
+  if manifest.deps.is_empty() {
+    out.push_str("  :deps {}\n");
+  } else {
+    out.push_str("  :deps {\n");
+    for dep in manifest.deps.iter() {
+      let mut fields = Vec::new();
+
+      if let Some(ref version) = dep.version {
+        fields.push(format!(":version {}", render_string(version)?));
+      }
+      if let Some(ref path) = dep.path {
+        fields.push(format!(":path {}", render_string(path)?));
+      }
+      if fields.is_empty() {
+        out.push_str(&format!("    {} {{}}\n", render_name(&dep.name)?));
+      } else {
+        out.push_str(&format!(
+          "    {} {{ {} }}\n",
+          render_name(&dep.name)?,
+          fields.join(" ")
+        ));
+      }
+    }
+    out.push_str("  }\n");
+  }
+  out.push_str("})\n\n(export package)\n");
+
+  Ok(out)
+}
+
+#[inline]
+pub fn write_manifest(dir: &Path, manifest: &Manifest) -> Result<(), String> {
+  let path = dir.join(MANIFEST_FILE);
+
+  fs::write(&path, render_manifest(manifest)?)
+    .map_err(|error| format!("failed to write {:?}: {}", path, error))
+}
@@ -0,0 +1,57 @@
+use std::env::{current_exe, var_os};
+use std::path::PathBuf;
+
+pub const REGISTRY_ENV: &'static str = "LPM_REGISTRY";
+pub const BUILDER_ENV: &'static str = "LPM_BUILDER";
+pub const LISP_ENV: &'static str = "LPM_LISP";
+
+/// Where the root package lives and which registry and executables the
+/// commands use.
+#[derive(Clone, Debug)]
+pub struct Options {
+  pub root: PathBuf,
+  pub registry: PathBuf,
+  pub builder: PathBuf,
+  pub lisp: PathBuf,
+}
+
+impl Options {
+  /// Uses `LPM_REGISTRY`, `LPM_BUILDER` and `LPM_LISP` when set, otherwise
+  /// `~/.lpm/registry` and the executables next to lpm or on the `PATH`.
+  #[inline]
+  pub fn new(root: PathBuf) -> Self {
+    Options {
+      root,
+      registry: var_os(REGISTRY_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(default_registry),
+      builder: var_os(BUILDER_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| executable("lisp-builder")),
+      lisp: var_os(LISP_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| executable("lisp")),
+    }
+  }
+}
+
+#[inline]
+fn default_registry() -> PathBuf {
+  var_os("HOME")
+    .or_else(|| var_os("USERPROFILE"))
+    .map(PathBuf::from)
+    .unwrap_or_else(|| PathBuf::from("."))
+    .join(".lpm")
+    .join("registry")
+}
+
+#[inline]
+fn executable(name: &str) -> PathBuf {
+  let filename = format!("{}{}", name, ::std::env::consts::EXE_SUFFIX);
+
+  current_exe()
+    .ok()
+    .and_then(|exe| exe.parent().map(|dir| dir.join(&filename)))
+    .filter(|path| path.is_file())
+    .unwrap_or_else(|| PathBuf::from(filename))
+}
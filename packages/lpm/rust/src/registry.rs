@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lisp::{Version, VersionReq};
+
+use super::MANIFEST_FILE;
+
+/// Versions of `name` in a registry laid out as `<registry>/<name>/<version>`,
+/// oldest first, directories that are not versions are skipped.
+#[inline]
+pub fn registry_versions(registry: &Path, name: &str) -> Vec<Version> {
+  let mut versions = fs::read_dir(registry.join(name))
+    .map(|entries| {
+      entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().join(MANIFEST_FILE).is_file())
+        .filter_map(|entry| {
+          entry
+            .file_name()
+            .to_str()
+            .and_then(|version| Version::parse(version).ok())
+        })
+        .collect::<Vec<Version>>()
+    })
+    .unwrap_or_else(|_| Vec::new());
+
+  versions.sort();
+  versions
+}
+
+#[inline]
+pub fn registry_package_dir(registry: &Path, name: &str, version: &Version) -> PathBuf {
+  registry.join(name).join(version.to_string())
+}
+
+/// Returns the newest version of `name` matching all `requirements`.
+#[inline]
+pub fn resolve_registry_version(
+  registry: &Path,
+  name: &str,
+  requirements: &[&VersionReq],
+) -> Result<Version, String> {
+  let versions = registry_versions(registry, name);
+
+  versions
+    .iter()
+    .rev()
+    .find(|version| {
+      requirements
+        .iter()
+        .all(|requirement| requirement.matches(version))
+    })
+    .map(Clone::clone)
+    .ok_or_else(|| {
+      if versions.is_empty() {
+        format!("package {} was not found in registry {:?}", name, registry)
+      } else {
+        format!(
+          "no version of {} in registry {:?} matches {}, found {}",
+          name,
+          registry,
+          requirements
+            .iter()
+            .map(|requirement| requirement.to_string())
+            .collect::<Vec<String>>()
+            .join(" and "),
+          versions
+            .iter()
+            .map(|version| version.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+        )
+      }
+    })
+}
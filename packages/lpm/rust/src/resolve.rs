@@ -0,0 +1,223 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Component, Path, PathBuf};
+
+use lisp::{Version, VersionReq};
+
+use super::{
+  read_manifest, registry_package_dir, registry_versions, resolve_registry_version, Dependency,
+  Lock, Manifest, Options, Source,
+};
+
+const MAX_RESOLVE_PASSES: usize = 32;
+
+/// A package chosen for the dependency graph, `dir` is where its content is
+/// read from, the registry for registry packages.
+#[derive(Clone, Debug)]
+pub struct Resolved {
+  pub name: String,
+  pub version: Version,
+  pub source: Source,
+  pub dir: PathBuf,
+  pub requirements: Vec<(String, VersionReq)>,
+}
+
+/// Resolves one version of every package in the dependency graph of
+/// `manifest`, the newest registry version matching every requirement on it
+/// unless the locked version still matches.
+#[inline]
+pub fn resolve(
+  options: &Options,
+  root: &Path,
+  manifest: &Manifest,
+  lock: &Lock,
+) -> Result<Vec<Resolved>, String> {
+  let mut chosen = lock
+    .packages
+    .iter()
+    .filter(|package| package.source == Source::Registry)
+    .filter_map(|package| {
+      Version::parse(&package.version)
+        .ok()
+        .filter(|version| registry_versions(&options.registry, &package.name).contains(version))
+        .map(|version| (package.name.clone(), version))
+    })
+    .collect::<BTreeMap<String, Version>>();
+
+  for _ in 0..MAX_RESOLVE_PASSES {
+    let packages = resolve_pass(options, root, manifest, &chosen)?;
+    let mut changed = false;
+
+    for package in packages.values() {
+      if package
+        .requirements
+        .iter()
+        .all(|&(_, ref requirement)| requirement.matches(&package.version))
+      {
+        continue;
+      }
+
+      let requirements = package
+        .requirements
+        .iter()
+        .map(|&(_, ref requirement)| requirement)
+        .collect::<Vec<&VersionReq>>();
+      let version = match package.source {
+        Source::Registry => {
+          resolve_registry_version(&options.registry, &package.name, &requirements).ok()
+        }
+        Source::Path(_) => None,
+      };
+
+      match version {
+        Some(version) => {
+          chosen.insert(package.name.clone(), version);
+          changed = true;
+        }
+        None => return Err(conflict_error(options, package)),
+      }
+    }
+
+    if !changed {
+      return Ok(packages.into_values().collect());
+    }
+  }
+
+  Err(format!(
+    "failed to resolve the dependencies of {} in {} passes",
+    manifest.name, MAX_RESOLVE_PASSES
+  ))
+}
+
+#[inline]
+fn resolve_pass(
+  options: &Options,
+  root: &Path,
+  manifest: &Manifest,
+  chosen: &BTreeMap<String, Version>,
+) -> Result<BTreeMap<String, Resolved>, String> {
+  let mut packages = BTreeMap::<String, Resolved>::new();
+  let mut queue = manifest
+    .deps
+    .iter()
+    .map(|dep| (dep.clone(), root.to_path_buf(), manifest.name.clone()))
+    .collect::<VecDeque<(Dependency, PathBuf, String)>>();
+
+  while let Some((dep, base, parent)) = queue.pop_front() {
+    let requirement = match dep.version {
+      Some(ref version) => VersionReq::parse(version)
+        .map_err(|error| format!("dependency {} of {} has {}", dep.name, parent, error))?,
+      None => VersionReq::any(),
+    };
+    let source = match dep.path {
+      Some(ref path) => {
+        let dir = base.join(path).canonicalize().map_err(|error| {
+          format!(
+            "dependency {} of {} has :path {:?} which can not be read: {}",
+            dep.name, parent, path, error
+          )
+        })?;
+        Source::Path(relative_path(root, &dir))
+      }
+      None => Source::Registry,
+    };
+
+    if let Some(package) = packages.get_mut(&dep.name) {
+      if package.source != source {
+        return Err(format!(
+          "conflicting sources for {}: {} uses {} but it is already {}",
+          dep.name, parent, source, package.source
+        ));
+      }
+      package.requirements.push((parent, requirement));
+      continue;
+    }
+
+    let (version, dir) = match source {
+      Source::Path(ref path) => {
+        let dir = root.join(path);
+        let version = read_manifest(&dir)?.version;
+        let version = Version::parse(&version)
+          .map_err(|error| format!("package {} at {:?} has {}", dep.name, dir, error))?;
+        (version, dir)
+      }
+      Source::Registry => {
+        let version = match chosen.get(&dep.name) {
+          Some(version) if requirement.matches(version) => version.clone(),
+          _ => resolve_registry_version(&options.registry, &dep.name, &[&requirement])
+            .map_err(|error| format!("dependency {} of {}: {}", dep.name, parent, error))?,
+        };
+        let dir = registry_package_dir(&options.registry, &dep.name, &version);
+        (version, dir)
+      }
+    };
+
+    for child in read_manifest(&dir)?.deps {
+      queue.push_back((child, dir.clone(), dep.name.clone()));
+    }
+
+    packages.insert(
+      dep.name.clone(),
+      Resolved {
+        name: dep.name.clone(),
+        version,
+        source,
+        dir,
+        requirements: vec![(parent, requirement)],
+      },
+    );
+  }
+
+  Ok(packages)
+}
+
+#[inline]
+fn conflict_error(options: &Options, package: &Resolved) -> String {
+  let requirements = package
+    .requirements
+    .iter()
+    .map(|&(ref parent, ref requirement)| format!("{} requires {}", parent, requirement))
+    .collect::<Vec<String>>()
+    .join(", ");
+
+  match package.source {
+    Source::Path(ref path) => format!(
+      "conflicting requirements for {}: {}, but {} is {}",
+      package.name, requirements, path, package.version
+    ),
+    Source::Registry => format!(
+      "conflicting requirements for {}: {}, found {}",
+      package.name,
+      requirements,
+      registry_versions(&options.registry, &package.name)
+        .iter()
+        .map(|version| version.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+    ),
+  }
+}
+
+/// `to` relative to `from`, both have to be canonical.
+#[inline]
+pub fn relative_path(from: &Path, to: &Path) -> String {
+  let from = from.components().collect::<Vec<Component>>();
+  let to = to.components().collect::<Vec<Component>>();
+  let common = from
+    .iter()
+    .zip(to.iter())
+    .take_while(|&(a, b)| a == b)
+    .count();
+
+  let mut parts = vec!["..".to_owned(); from.len() - common];
+  parts.extend(
+    to[common..]
+      .iter()
+      .map(|component| component.as_os_str().to_string_lossy().into_owned()),
+  );
+
+  if parts.is_empty() {
+    ".".to_owned()
+  } else {
+    parts.join("/")
+  }
+}
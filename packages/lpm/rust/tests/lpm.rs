@@ -0,0 +1,276 @@
+extern crate lpm;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lpm::{Dependency, Options, Source};
+
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = env::temp_dir().join(format!("lpm-{}-{}", name, std::process::id()));
+  if dir.exists() {
+    fs::remove_dir_all(&dir).unwrap();
+  }
+  fs::create_dir_all(&dir).unwrap();
+  dir.canonicalize().unwrap()
+}
+
+fn write(path: &Path, content: &str) {
+  fs::create_dir_all(path.parent().unwrap()).unwrap();
+  fs::write(path, content).unwrap();
+}
+
+fn publish(registry: &Path, name: &str, version: &str, lib: &str) {
+  let dir = registry.join(name).join(version);
+  write(
+    &dir.join("package.lisp"),
+    &format!(
+      "(def package {{ :name :{} :version \"{}\" }}) (export package)",
+      name, version
+    ),
+  );
+  write(&dir.join("src/lib.lisp"), lib);
+}
+
+fn options(dir: &Path) -> Options {
+  let mut options = Options::new(dir.join("app"));
+  options.registry = dir.join("registry");
+  options
+}
+
+#[test]
+fn test_sha256() {
+  assert_eq!(
+    lpm::sha256_hex(b""),
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+  );
+  assert_eq!(
+    lpm::sha256_hex(b"abc"),
+    "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+  );
+  assert_eq!(
+    lpm::sha256_hex(&[b'a'; 1000]),
+    "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+  );
+}
+
+#[test]
+fn test_install_writes_lock() {
+  let dir = temp_dir("install");
+  let options = options(&dir);
+  fs::create_dir_all(&options.root).unwrap();
+
+  publish(
+    &options.registry,
+    "text",
+    "0.1.0",
+    "(def-fn upper [s] s) (export upper)",
+  );
+  publish(
+    &options.registry,
+    "text",
+    "0.1.2",
+    "(def-fn upper [s] s) (export upper)",
+  );
+  publish(&options.registry, "text", "0.2.0", "");
+  write(
+    &dir.join("util/package.lisp"),
+    "(def package { :name :util :version \"1.0.0\" :deps { :text { :version \"0.1\" } } })
+     (export package)",
+  );
+  write(&dir.join("util/src/lib.lisp"), "");
+
+  assert_eq!(lpm::init(&options, Some("app")).unwrap().name, "app");
+  assert!(lpm::init(&options, None).is_err());
+  assert_eq!(
+    lpm::add(&options, Dependency::new("text", None, None)).unwrap(),
+    Dependency::new("text", Some("0.2.0"), None)
+  );
+  lpm::add(&options, Dependency::new("text", Some("0.1"), None)).unwrap();
+  lpm::add(&options, Dependency::new("util", None, Some("../util"))).unwrap();
+  assert!(lpm::add(&options, Dependency::new("missing", None, None)).is_err());
+
+  let manifest = lpm::read_manifest(&options.root).unwrap();
+  assert_eq!(
+    manifest.deps,
+    vec![
+      Dependency::new("text", Some("0.1"), None),
+      Dependency::new("util", None, Some("../util")),
+    ]
+  );
+
+  let lock = lpm::install(&options).unwrap();
+  assert_eq!(lock, lpm::read_lock(&options.root).unwrap());
+  assert_eq!(
+    lock
+      .packages
+      .iter()
+      .map(|package| (
+        package.name.as_str(),
+        package.version.as_str(),
+        package.source.clone()
+      ))
+      .collect::<Vec<_>>(),
+    vec![
+      ("text", "0.1.2", Source::Registry),
+      ("util", "1.0.0", Source::Path("../util".to_owned())),
+    ]
+  );
+  assert!(lock.packages[0].hash.starts_with("sha256-"));
+  assert!(options
+    .root
+    .join("lisp_packages/text/src/lib.lisp")
+    .is_file());
+
+  // a newer matching version does not replace the locked one
+  publish(&options.registry, "text", "0.1.3", "");
+  assert_eq!(lpm::install(&options).unwrap(), lock);
+
+  // a requirement the locked version does not match picks a new one
+  lpm::add(
+    &options,
+    Dependency::new("text", Some(">=0.1.3, <0.2"), None),
+  )
+  .unwrap();
+  assert_eq!(
+    lpm::install(&options)
+      .unwrap()
+      .package("text")
+      .unwrap()
+      .version,
+    "0.1.3"
+  );
+  lpm::add(&options, Dependency::new("text", Some("0.1"), None)).unwrap();
+  lpm::install(&options).unwrap();
+  fs::write(
+    options.root.join("package-lock.lisp"),
+    lpm::render_lock(&lock).unwrap(),
+  )
+  .unwrap();
+
+  // but changed content of the locked version is an error
+  write(
+    &options.registry.join("text/0.1.2/src/lib.lisp"),
+    "(def-fn upper [s] nil) (export upper)",
+  );
+  let error = lpm::install(&options).unwrap_err();
+  assert!(
+    error.starts_with("hash mismatch for text 0.1.2"),
+    "{}",
+    error
+  );
+  // and is reported before the installed copy is replaced
+  assert_eq!(
+    fs::read_to_string(options.root.join("lisp_packages/text/src/lib.lisp")).unwrap(),
+    ""
+  );
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_resolve_conflicts() {
+  let dir = temp_dir("conflicts");
+  let options = options(&dir);
+  let registry = &options.registry;
+
+  publish(registry, "text", "0.1.0", "");
+  publish(registry, "text", "0.1.5", "");
+  publish(registry, "text", "1.0.0", "");
+  publish(registry, "text", "1.2.0", "");
+  let dependent = |name: &str, requirement: &str| {
+    let dir = registry.join(name).join("1.0.0");
+    write(
+      &dir.join("package.lisp"),
+      &format!(
+        "(def package {{ :name :{} :version \"1.0.0\" :deps {{ :text {{ :version \"{}\" }} }} }})
+         (export package)",
+        name, requirement
+      ),
+    );
+    write(&dir.join("src/lib.lisp"), "");
+  };
+  dependent("a", ">=0.1, <1.1");
+  dependent("b", "~1.0");
+  dependent("c", "^0.1");
+
+  lpm::init(&options, Some("app")).unwrap();
+  lpm::add(&options, Dependency::new("text", Some("*"), None)).unwrap();
+  lpm::add(&options, Dependency::new("a", Some("1"), None)).unwrap();
+  lpm::add(&options, Dependency::new("b", Some("1"), None)).unwrap();
+
+  // the newest text, 1.2.0, is replaced by the newest matching all three
+  let lock = lpm::install(&options).unwrap();
+  assert_eq!(lock.package("text").unwrap().version, "1.0.0");
+
+  lpm::add(&options, Dependency::new("c", Some("1"), None)).unwrap();
+  let error = lpm::install(&options).unwrap_err();
+  assert!(
+    error.starts_with("conflicting requirements for text: app requires *, a requires >=0.1, <1.1, b requires ~1.0, c requires ^0.1"),
+    "{}",
+    error
+  );
+  assert!(
+    error.ends_with("found 0.1.0, 0.1.5, 1.0.0, 1.2.0"),
+    "{}",
+    error
+  );
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_build_and_run() {
+  use std::os::unix::fs::PermissionsExt;
+
+  let dir = temp_dir("build");
+  let mut options = options(&dir);
+  let script = |path: &Path, content: &str| {
+    write(path, content);
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+  };
+
+  script(
+    &dir.join("bin/lisp-builder"),
+    "#!/bin/sh\nmkdir -p \"$4\"\necho \"$2\" > \"$4/built\"\n",
+  );
+  script(
+    &dir.join("bin/lisp"),
+    "#!/bin/sh\necho \"$1\n$LISP_PATH\n$LISP_NATIVE_PATH\" > \"$(dirname \"$0\")/ran\"\nexit 3\n",
+  );
+  options.builder = dir.join("bin/lisp-builder");
+  options.lisp = dir.join("bin/lisp");
+
+  lpm::init(&options, Some("app")).unwrap();
+  write(&options.root.join("rust/Cargo.toml"), "");
+
+  assert_eq!(
+    lpm::build(&options).unwrap(),
+    vec![options.root.join("lisp-builder-out")]
+  );
+  assert_eq!(
+    fs::read_to_string(options.root.join("lisp-builder-out/built")).unwrap(),
+    format!("{}\n", options.root.join("rust/Cargo.toml").display())
+  );
+
+  assert_eq!(lpm::run(&options, None), Ok(3));
+  let ran = fs::read_to_string(dir.join("bin/ran")).unwrap();
+  let mut lines = ran.lines();
+  assert_eq!(lines.next(), options.root.join("src/main.lisp").to_str());
+  assert!(lines
+    .next()
+    .unwrap()
+    .starts_with(options.root.join("lisp_packages").to_str().unwrap()));
+  assert!(lines
+    .next()
+    .unwrap()
+    .starts_with(options.root.join("lisp-builder-out").to_str().unwrap()));
+
+  options.builder = dir.join("bin/missing");
+  assert!(lpm::build(&options)
+    .unwrap_err()
+    .contains("set LPM_BUILDER"));
+
+  fs::remove_dir_all(dir).unwrap();
+}
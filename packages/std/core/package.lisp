@@ -1,4 +1,4 @@
-(let package {
+(def package {
   :name :std-core
   :version "0.1.0"
   :authors ["Nathan Faucett nathanfaucett@gmail.com"]
@@ -6,17 +6,17 @@
 
 (def-fn option.from [value]
   (if (= value nil)
-    (option.some value)
-    (option.none)))
+    (option.none)
+    (option.some value)))
 
 (def-fn option.is_some [option]
-  (!= (map.get option, :value) NONE_VALUE))
+  (!= (map.get option, :value) :OPTION_NONE))
 
 (def-fn option.is_none [option]
   (! (option.is_some option)))
 
 (def-fn option.expect [option, error]
-  (if (is_some option)
+  (if (option.is_some option)
     (map.get option, :value)
     (panic error)))
 
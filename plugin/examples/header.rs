@@ -0,0 +1,5 @@
+extern crate lisp_plugin;
+
+fn main() {
+  print!("{}", lisp_plugin::c_header());
+}
@@ -0,0 +1,81 @@
+/* Generated by lisp-plugin, do not edit. */
+#ifndef LISP_PLUGIN_H
+#define LISP_PLUGIN_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define LISP_PLUGIN_ABI_VERSION 2
+#define LISP_NULL 0
+
+typedef struct lisp_context lisp_context;
+typedef uint32_t lisp_value;
+typedef uint32_t lisp_type;
+
+#define LISP_TYPE_INVALID 0
+#define LISP_TYPE_NIL 1
+#define LISP_TYPE_BOOL 2
+#define LISP_TYPE_INT 3
+#define LISP_TYPE_FLOAT 4
+#define LISP_TYPE_STRING 5
+#define LISP_TYPE_KEYWORD 6
+#define LISP_TYPE_SYMBOL 7
+#define LISP_TYPE_VECTOR 8
+#define LISP_TYPE_LIST 9
+#define LISP_TYPE_MAP 10
+#define LISP_TYPE_FUNCTION 11
+#define LISP_TYPE_OTHER 12
+
+typedef struct lisp_host {
+  uint32_t abi_version;
+  uint32_t size;
+  lisp_type (*type_of)(lisp_context *context, lisp_value value);
+  lisp_value (*nil)(lisp_context *context);
+  lisp_value (*new_bool)(lisp_context *context, bool value);
+  lisp_value (*new_int)(lisp_context *context, int64_t value);
+  lisp_value (*new_float)(lisp_context *context, double value);
+  lisp_value (*new_string)(lisp_context *context, const char *ptr, size_t len);
+  lisp_value (*new_keyword)(lisp_context *context, const char *ptr, size_t len);
+  lisp_value (*new_vector)(lisp_context *context, const lisp_value *values, size_t len);
+  bool (*get_bool)(lisp_context *context, lisp_value value, bool *out);
+  bool (*get_int)(lisp_context *context, lisp_value value, int64_t *out);
+  bool (*get_float)(lisp_context *context, lisp_value value, double *out);
+  /* Sets `ptr` and `len` to the UTF-8 bytes of a string, keyword or symbol,
+   * the bytes are not NUL terminated. They point into memory owned by the
+   * garbage collector and stay valid only while `value`'s handle is rooted,
+   * that is until the plugin function that received it returns. */
+  bool (*get_string)(lisp_context *context, lisp_value value, const char **ptr, size_t *len);
+  bool (*vector_len)(lisp_context *context, lisp_value value, size_t *out);
+  lisp_value (*vector_get)(lisp_context *context, lisp_value value, size_t index);
+  lisp_value (*throw_error)(lisp_context *context, const char *ptr, size_t len);
+} lisp_host;
+
+typedef lisp_value (*lisp_function)(const lisp_host *host, lisp_context *context,
+                                   const lisp_value *args, size_t len);
+
+typedef struct lisp_plugin_function {
+  const char *name;
+  const char *params;
+  const char *doc;
+  lisp_function function;
+} lisp_plugin_function;
+
+typedef struct lisp_plugin {
+  uint32_t abi_version;
+  const char *name;
+  const lisp_plugin_function *functions;
+  size_t functions_len;
+} lisp_plugin;
+
+const lisp_plugin *lisp_plugin_init(const lisp_host *host);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
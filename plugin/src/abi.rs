@@ -0,0 +1,167 @@
+use std::os::raw::c_char;
+use std::ptr;
+
+pub const LISP_PLUGIN_ABI_VERSION: u32 = 2;
+pub const LISP_NULL: LispValue = 0;
+
+pub type LispValue = u32;
+pub type LispType = u32;
+
+#[repr(C)]
+pub struct LispContext {
+  _private: [u8; 0],
+}
+
+macro_rules! lisp_types {
+  ($($name:ident = $value:expr,)*) => {
+    $(pub const $name: LispType = $value;)*
+
+    pub(crate) const LISP_TYPES: &'static [(&'static str, LispType)] = &[$((stringify!($name), $value)),*];
+  };
+}
+
+lisp_types! {
+  LISP_TYPE_INVALID = 0,
+  LISP_TYPE_NIL = 1,
+  LISP_TYPE_BOOL = 2,
+  LISP_TYPE_INT = 3,
+  LISP_TYPE_FLOAT = 4,
+  LISP_TYPE_STRING = 5,
+  LISP_TYPE_KEYWORD = 6,
+  LISP_TYPE_SYMBOL = 7,
+  LISP_TYPE_VECTOR = 8,
+  LISP_TYPE_LIST = 9,
+  LISP_TYPE_MAP = 10,
+  LISP_TYPE_FUNCTION = 11,
+  LISP_TYPE_OTHER = 12,
+}
+
+macro_rules! lisp_host {
+  ($($(#[doc = $doc:expr])* $name:ident: fn($($arg:ident: $arg_type:ty),*) -> $ret:ty;)*) => {
+    #[repr(C)]
+    pub struct LispHost {
+      pub abi_version: u32,
+      pub size: u32,
+      $($(#[doc = $doc])* pub $name: unsafe extern "C" fn($($arg: $arg_type),*) -> $ret,)*
+    }
+
+    pub(crate) const LISP_HOST_FUNCTIONS: &'static [(
+      &'static str,
+      &'static [&'static str],
+      &'static str,
+      &'static [(&'static str, &'static str)],
+    )] = &[$((stringify!($name), &[$($doc),*], stringify!($ret), &[$((stringify!($arg), stringify!($arg_type))),*])),*];
+  };
+}
+
+lisp_host! {
+  type_of: fn(context: *mut LispContext, value: LispValue) -> LispType;
+  nil: fn(context: *mut LispContext) -> LispValue;
+  new_bool: fn(context: *mut LispContext, value: bool) -> LispValue;
+  new_int: fn(context: *mut LispContext, value: i64) -> LispValue;
+  new_float: fn(context: *mut LispContext, value: f64) -> LispValue;
+  new_string: fn(context: *mut LispContext, ptr: *const c_char, len: usize) -> LispValue;
+  new_keyword: fn(context: *mut LispContext, ptr: *const c_char, len: usize) -> LispValue;
+  new_vector: fn(context: *mut LispContext, values: *const LispValue, len: usize) -> LispValue;
+  get_bool: fn(context: *mut LispContext, value: LispValue, out: *mut bool) -> bool;
+  get_int: fn(context: *mut LispContext, value: LispValue, out: *mut i64) -> bool;
+  get_float: fn(context: *mut LispContext, value: LispValue, out: *mut f64) -> bool;
+  /// Sets `ptr` and `len` to the UTF-8 bytes of a string, keyword or symbol,
+  /// the bytes are not NUL terminated. They point into memory owned by the
+  /// garbage collector and stay valid only while `value`'s handle is rooted,
+  /// that is until the plugin function that received it returns.
+  get_string: fn(context: *mut LispContext, value: LispValue, ptr: *mut *const c_char, len: *mut usize) -> bool;
+  vector_len: fn(context: *mut LispContext, value: LispValue, out: *mut usize) -> bool;
+  vector_get: fn(context: *mut LispContext, value: LispValue, index: usize) -> LispValue;
+  throw_error: fn(context: *mut LispContext, ptr: *const c_char, len: usize) -> LispValue;
+}
+
+pub type LispFunction = unsafe extern "C" fn(
+  host: *const LispHost,
+  context: *mut LispContext,
+  args: *const LispValue,
+  len: usize,
+) -> LispValue;
+
+/// An exported function, `params` are separated by spaces and `doc` may be null.
+#[repr(C)]
+pub struct LispPluginFunction {
+  pub name: *const c_char,
+  pub params: *const c_char,
+  pub doc: *const c_char,
+  pub function: LispFunction,
+}
+
+unsafe impl Sync for LispPluginFunction {}
+
+#[repr(C)]
+pub struct LispPlugin {
+  pub abi_version: u32,
+  pub name: *const c_char,
+  pub functions: *const LispPluginFunction,
+  pub functions_len: usize,
+}
+
+unsafe impl Sync for LispPlugin {}
+
+pub type LispPluginInit = unsafe extern "C" fn(host: *const LispHost) -> *const LispPlugin;
+
+pub const LISP_PLUGIN_INIT: &'static str = "lisp_plugin_init";
+
+/// Returns true if `host` was built against the same ABI as this crate.
+///
+/// # Safety
+///
+/// `host` must be null or point to a `LispHost`, like the one the host passes
+/// to `lisp_plugin_init`.
+#[inline]
+pub unsafe fn is_compatible_host(host: *const LispHost) -> bool {
+  !host.is_null()
+    && (*host).abi_version == LISP_PLUGIN_ABI_VERSION
+    && (*host).size as usize >= ::std::mem::size_of::<LispHost>()
+}
+
+/// Returns `plugin` if `host` is compatible and null otherwise, the body of
+/// the `lisp_plugin_init` that `lisp_plugin!` exports.
+///
+/// # Safety
+///
+/// `host` must be null or point to a `LispHost`, see `is_compatible_host`.
+#[inline]
+pub unsafe fn plugin_init(host: *const LispHost, plugin: &'static LispPlugin) -> *const LispPlugin {
+  if is_compatible_host(host) {
+    plugin
+  } else {
+    ptr::null()
+  }
+}
+
+/// Exports `lisp_plugin_init` for a plugin named `$name` along with the
+/// functions a module gets when the plugin is imported.
+///
+/// ```ignore
+/// lisp_plugin!("math", add("a b") "Adds two integers");
+/// ```
+#[macro_export]
+macro_rules! lisp_plugin {
+  ($name:expr $(, $function:ident($params:expr) $doc:expr)* $(,)*) => {
+    #[no_mangle]
+    pub unsafe extern "C" fn lisp_plugin_init(
+      host: *const $crate::LispHost,
+    ) -> *const $crate::LispPlugin {
+      const FUNCTIONS: &'static [$crate::LispPluginFunction] = &[$($crate::LispPluginFunction {
+        name: concat!(stringify!($function), "\0").as_ptr() as *const _,
+        params: concat!($params, "\0").as_ptr() as *const _,
+        doc: concat!($doc, "\0").as_ptr() as *const _,
+        function: $function,
+      }),*];
+      static PLUGIN: $crate::LispPlugin = $crate::LispPlugin {
+        abi_version: $crate::LISP_PLUGIN_ABI_VERSION,
+        name: concat!($name, "\0").as_ptr() as *const _,
+        functions: FUNCTIONS.as_ptr(),
+        functions_len: FUNCTIONS.len(),
+      };
+      $crate::plugin_init(host, &PLUGIN)
+    }
+  };
+}
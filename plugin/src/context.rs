@@ -0,0 +1,147 @@
+use std::os::raw::c_char;
+use std::{ptr, slice, str};
+
+use super::{LispContext, LispHost, LispType, LispValue};
+
+/// Safe view over the arguments a host passes to a `LispFunction`.
+pub struct Context<'a> {
+  host: &'a LispHost,
+  context: *mut LispContext,
+  args: &'a [LispValue],
+}
+
+impl<'a> Context<'a> {
+  /// Wraps the arguments of a `LispFunction` call.
+  ///
+  /// # Safety
+  ///
+  /// `host`, `context`, `args` and `len` must be the arguments the host passed
+  /// to the `LispFunction` and `'a` must end before that function returns, the
+  /// handles in `args` and the strings `get_str` borrows from them are only
+  /// rooted until then.
+  #[inline]
+  pub unsafe fn from_raw(
+    host: *const LispHost,
+    context: *mut LispContext,
+    args: *const LispValue,
+    len: usize,
+  ) -> Self {
+    Context {
+      host: &*host,
+      context,
+      args: if len == 0 {
+        &[]
+      } else {
+        slice::from_raw_parts(args, len)
+      },
+    }
+  }
+
+  #[inline]
+  pub fn args(&self) -> &'a [LispValue] {
+    self.args
+  }
+  #[inline]
+  pub fn arg(&self, index: usize) -> Option<LispValue> {
+    self.args.get(index).map(Clone::clone)
+  }
+
+  #[inline]
+  pub fn type_of(&self, value: LispValue) -> LispType {
+    unsafe { (self.host.type_of)(self.context, value) }
+  }
+
+  #[inline]
+  pub fn nil(&self) -> LispValue {
+    unsafe { (self.host.nil)(self.context) }
+  }
+  #[inline]
+  pub fn new_bool(&self, value: bool) -> LispValue {
+    unsafe { (self.host.new_bool)(self.context, value) }
+  }
+  #[inline]
+  pub fn new_int(&self, value: i64) -> LispValue {
+    unsafe { (self.host.new_int)(self.context, value) }
+  }
+  #[inline]
+  pub fn new_float(&self, value: f64) -> LispValue {
+    unsafe { (self.host.new_float)(self.context, value) }
+  }
+  #[inline]
+  pub fn new_string(&self, value: &str) -> LispValue {
+    unsafe { (self.host.new_string)(self.context, value.as_ptr() as *const c_char, value.len()) }
+  }
+  #[inline]
+  pub fn new_keyword(&self, value: &str) -> LispValue {
+    unsafe { (self.host.new_keyword)(self.context, value.as_ptr() as *const c_char, value.len()) }
+  }
+  #[inline]
+  pub fn new_vector(&self, values: &[LispValue]) -> LispValue {
+    unsafe { (self.host.new_vector)(self.context, values.as_ptr(), values.len()) }
+  }
+
+  #[inline]
+  pub fn get_bool(&self, value: LispValue) -> Option<bool> {
+    let mut out = false;
+    if unsafe { (self.host.get_bool)(self.context, value, &mut out) } {
+      Some(out)
+    } else {
+      None
+    }
+  }
+  #[inline]
+  pub fn get_int(&self, value: LispValue) -> Option<i64> {
+    let mut out = 0;
+    if unsafe { (self.host.get_int)(self.context, value, &mut out) } {
+      Some(out)
+    } else {
+      None
+    }
+  }
+  #[inline]
+  pub fn get_float(&self, value: LispValue) -> Option<f64> {
+    let mut out = 0.0;
+    if unsafe { (self.host.get_float)(self.context, value, &mut out) } {
+      Some(out)
+    } else {
+      None
+    }
+  }
+  /// Borrows the text of a string, keyword or symbol from the host's memory,
+  /// see `LispHost::get_string`.
+  #[inline]
+  pub fn get_str(&self, value: LispValue) -> Option<&'a str> {
+    let mut ptr = ptr::null();
+    let mut len = 0;
+    if unsafe { (self.host.get_string)(self.context, value, &mut ptr, &mut len) } {
+      let bytes = unsafe { slice::from_raw_parts(ptr as *const u8, len) };
+      str::from_utf8(bytes).ok()
+    } else {
+      None
+    }
+  }
+  #[inline]
+  pub fn vector_len(&self, value: LispValue) -> Option<usize> {
+    let mut out = 0;
+    if unsafe { (self.host.vector_len)(self.context, value, &mut out) } {
+      Some(out)
+    } else {
+      None
+    }
+  }
+  #[inline]
+  pub fn vector_get(&self, value: LispValue, index: usize) -> LispValue {
+    unsafe { (self.host.vector_get)(self.context, value, index) }
+  }
+
+  #[inline]
+  pub fn throw_error(&self, message: &str) -> LispValue {
+    unsafe {
+      (self.host.throw_error)(
+        self.context,
+        message.as_ptr() as *const c_char,
+        message.len(),
+      )
+    }
+  }
+}
@@ -0,0 +1,94 @@
+use std::fmt::Write;
+
+use super::{LISP_HOST_FUNCTIONS, LISP_PLUGIN_ABI_VERSION, LISP_TYPES};
+
+#[inline]
+fn c_type(rust_type: &str) -> &'static str {
+  match rust_type.replace(' ', "").as_str() {
+    "bool" => "bool",
+    "i64" => "int64_t",
+    "f64" => "double",
+    "usize" => "size_t",
+    "LispValue" => "lisp_value",
+    "LispType" => "lisp_type",
+    "*mutLispContext" => "lisp_context *",
+    "*constLispValue" => "const lisp_value *",
+    "*constc_char" => "const char *",
+    "*mut*constc_char" => "const char **",
+    "*mutbool" => "bool *",
+    "*muti64" => "int64_t *",
+    "*mutf64" => "double *",
+    "*mutusize" => "size_t *",
+    other => panic!("no C type for {}", other),
+  }
+}
+
+/// Renders the C header for the plugin ABI, see `include/lisp_plugin.h`.
+pub fn c_header() -> String {
+  let mut out = String::new();
+
+  out.push_str("/* Generated by lisp-plugin, do not edit. */\n");
+  out.push_str("#ifndef LISP_PLUGIN_H\n#define LISP_PLUGIN_H\n\n");
+  out.push_str("#include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n\n");
+  out.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+
+  writeln!(
+    out,
+    "#define LISP_PLUGIN_ABI_VERSION {}\n#define LISP_NULL 0\n",
+    LISP_PLUGIN_ABI_VERSION
+  )
+  .unwrap();
+
+  out.push_str("typedef struct lisp_context lisp_context;\n");
+  out.push_str("typedef uint32_t lisp_value;\n");
+  out.push_str("typedef uint32_t lisp_type;\n\n");
+
+  for &(name, value) in LISP_TYPES {
+    writeln!(out, "#define {} {}", name, value).unwrap();
+  }
+
+  out.push_str("\ntypedef struct lisp_host {\n");
+  out.push_str("  uint32_t abi_version;\n  uint32_t size;\n");
+  for &(name, doc, ret, args) in LISP_HOST_FUNCTIONS {
+    for (index, line) in doc.iter().enumerate() {
+      let prefix = if index == 0 { "  /*" } else { "   *" };
+      let suffix = if index + 1 == doc.len() { " */" } else { "" };
+      writeln!(out, "{}{}{}", prefix, line, suffix).unwrap();
+    }
+
+    let args = args
+      .iter()
+      .map(|&(arg, arg_type)| {
+        let arg_type = c_type(arg_type);
+        if arg_type.ends_with('*') {
+          format!("{}{}", arg_type, arg)
+        } else {
+          format!("{} {}", arg_type, arg)
+        }
+      })
+      .collect::<Vec<String>>()
+      .join(", ");
+    writeln!(out, "  {} (*{})({});", c_type(ret), name, args).unwrap();
+  }
+  out.push_str("} lisp_host;\n\n");
+
+  out.push_str(
+    "typedef lisp_value (*lisp_function)(const lisp_host *host, lisp_context *context,\n",
+  );
+  out.push_str("                                   const lisp_value *args, size_t len);\n\n");
+
+  out.push_str("typedef struct lisp_plugin_function {\n");
+  out.push_str("  const char *name;\n  const char *params;\n  const char *doc;\n");
+  out.push_str("  lisp_function function;\n");
+  out.push_str("} lisp_plugin_function;\n\n");
+
+  out.push_str("typedef struct lisp_plugin {\n");
+  out.push_str("  uint32_t abi_version;\n  const char *name;\n");
+  out.push_str("  const lisp_plugin_function *functions;\n  size_t functions_len;\n");
+  out.push_str("} lisp_plugin;\n\n");
+
+  out.push_str("const lisp_plugin *lisp_plugin_init(const lisp_host *host);\n\n");
+
+  out.push_str("#ifdef __cplusplus\n}\n#endif\n\n#endif\n");
+  out
+}
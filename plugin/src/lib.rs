@@ -0,0 +1,7 @@
+mod abi;
+mod context;
+mod header;
+
+pub use self::abi::*;
+pub use self::context::*;
+pub use self::header::*;
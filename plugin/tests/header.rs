@@ -0,0 +1,14 @@
+extern crate lisp_plugin;
+
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_header_is_up_to_date() {
+  let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/lisp_plugin.h");
+  let header = fs::read_to_string(path).expect("failed to read include/lisp_plugin.h");
+  assert!(
+    header == lisp_plugin::c_header(),
+    "include/lisp_plugin.h is out of date, run `cargo run -p lisp-plugin --example header > plugin/include/lisp_plugin.h`"
+  );
+}
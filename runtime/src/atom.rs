@@ -110,9 +110,15 @@ impl Atom {
 
   #[inline]
   pub(crate) fn init_scope(scope: &Gc<Object<Scope>>) {
-    add_external_function(scope, "atom.new", vec!["value"], atom_new);
-    add_external_function(scope, "atom.get", vec!["atom"], atom_get);
-    add_external_function(scope, "atom.set", vec!["atom", "value"], atom_set);
+    add_external_function(scope, "lisp_runtime", "atom.new", vec!["value"], atom_new);
+    add_external_function(scope, "lisp_runtime", "atom.get", vec!["atom"], atom_get);
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "atom.set",
+      vec!["atom", "value"],
+      atom_set,
+    );
   }
 }
 
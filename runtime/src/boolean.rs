@@ -17,7 +17,7 @@ pub fn init_bool_kind(scope: &Gc<Object<Scope>>) {
 
 #[inline]
 pub fn init_bool_scope(scope: &Gc<Object<Scope>>) {
-  add_external_function(scope, "bool.not", vec!["value"], bool_not);
+  add_external_function(scope, "lisp_runtime", "bool.not", vec!["value"], bool_not);
 }
 
 #[inline]
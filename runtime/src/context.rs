@@ -2,15 +2,22 @@ use alloc::string::{String, ToString};
 use hashbrown::HashMap;
 
 use super::{
-  add_external_function, init_bool_kind, init_bool_scope, init_numbers_kind, init_numbers_scope,
-  new_kind, new_object, run_in_scope, scope_get_with_kind, scope_set, Atom, Escape, Function,
-  GcAllocator, Keyword, Kind, List, Map, Object, Scope, Set, SpecialForm, Stack, Symbol, Value,
-  Vector,
+  add_external_function, init_bool_kind, init_bool_scope, init_edn_scope, init_json_scope,
+  init_numbers_kind, init_numbers_scope, new_kind, new_object, run_in_scope, scope_get_with_kind,
+  scope_set, Atom, Escape, Function, GcAllocator, Keyword, Kind, List, Map, Object, Scope, Set,
+  SpecialForm, Stack, Symbol, Tagged, Value, Vector,
 };
 use gc::Gc;
 
 #[inline]
 pub fn new_context() -> Gc<Object<Scope>> {
+  let scope = new_base_context();
+  run_in_scope(&scope, include_str!("lisp/bootstrap.lisp"));
+  scope
+}
+
+#[inline]
+pub fn new_base_context() -> Gc<Object<Scope>> {
   unsafe {
     let scope = init_root_scope();
 
@@ -30,6 +37,7 @@ pub fn new_context() -> Gc<Object<Scope>> {
     Vector::init_kind(&scope);
     Map::init_kind(&scope);
     Set::init_kind(&scope);
+    Tagged::init_kind(&scope);
 
     init_numbers_scope(&scope);
     init_bool_scope(&scope);
@@ -43,16 +51,17 @@ pub fn new_context() -> Gc<Object<Scope>> {
     Vector::init_scope(&scope);
     Map::init_scope(&scope);
     Set::init_scope(&scope);
+    init_json_scope(&scope);
+    init_edn_scope(&scope);
 
     add_external_function(
       &scope,
+      "lisp_runtime",
       "global_error_handler",
       vec!["error"],
       global_error_handler,
     );
 
-    run_in_scope(&scope, include_str!("lisp/bootstrap.lisp"));
-
     scope
   }
 }
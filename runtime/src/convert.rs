@@ -0,0 +1,527 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::any::type_name;
+use core::convert::TryFrom;
+use core::fmt::{Debug, Display};
+use core::hash::{BuildHasher, Hash};
+
+use gc::{Gc, Trace};
+use hashbrown::HashMap;
+#[cfg(feature = "std")]
+use std::collections::HashMap as StdHashMap;
+
+use super::{
+  new_bool, new_char, new_external_function, new_f32, new_f64, new_i16, new_i32, new_i64, new_i8,
+  new_isize, new_map_from, new_string, new_symbol, new_u16, new_u32, new_u64, new_u8, new_usize,
+  new_vector_from, nil_value, register_external_function, scope_set, throw_error, Function, List,
+  Map, Object, Scope, Symbol, Value, Vector, F32, F64,
+};
+
+pub trait FromValue: Sized {
+  fn from_value(scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String>;
+}
+
+pub trait IntoValue {
+  fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value>;
+}
+
+pub trait IntoResult {
+  fn into_result(self, scope: &Gc<Object<Scope>>) -> Result<Gc<dyn Value>, Gc<dyn Value>>;
+}
+
+impl<T> IntoResult for T
+where
+  T: IntoValue,
+{
+  #[inline]
+  fn into_result(self, scope: &Gc<Object<Scope>>) -> Result<Gc<dyn Value>, Gc<dyn Value>> {
+    Ok(self.into_value(scope))
+  }
+}
+
+impl<T, E> IntoResult for Result<T, E>
+where
+  T: IntoValue,
+  E: Display,
+{
+  #[inline]
+  fn into_result(self, scope: &Gc<Object<Scope>>) -> Result<Gc<dyn Value>, Gc<dyn Value>> {
+    self
+      .map(|value| value.into_value(scope))
+      .map_err(|error| new_string(scope, error.to_string()).into_value())
+  }
+}
+
+#[inline]
+pub fn type_error(expected: &str, value: &Gc<dyn Value>) -> String {
+  format!("expected {}, found {}", expected, value.kind().name())
+}
+
+impl FromValue for Gc<dyn Value> {
+  #[inline]
+  fn from_value(_scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+    Ok(value.clone())
+  }
+}
+impl IntoValue for Gc<dyn Value> {
+  #[inline]
+  fn into_value(self, _scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+    self
+  }
+}
+
+impl<T> FromValue for Gc<Object<T>>
+where
+  T: 'static + PartialEq + PartialOrd + Hash + Debug + Trace,
+{
+  #[inline]
+  fn from_value(_scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+    value
+      .downcast_ref::<Object<T>>()
+      .map(Clone::clone)
+      .ok_or_else(|| {
+        type_error(
+          type_name::<T>().rsplit("::").next().unwrap_or_default(),
+          value,
+        )
+      })
+  }
+}
+impl<T> IntoValue for Gc<Object<T>>
+where
+  T: 'static + PartialEq + PartialOrd + Hash + Debug + Trace,
+{
+  #[inline]
+  fn into_value(self, _scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+    Object::into_value(self)
+  }
+}
+
+impl FromValue for () {
+  #[inline]
+  fn from_value(scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+    if value == &nil_value(scope).into_value() {
+      Ok(())
+    } else {
+      Err(type_error("Nil", value))
+    }
+  }
+}
+impl IntoValue for () {
+  #[inline]
+  fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+    nil_value(scope).into_value()
+  }
+}
+
+macro_rules! primitive {
+  ($type:ty, $kind:expr, $new_func:ident) => {
+    impl FromValue for $type {
+      #[inline]
+      fn from_value(_scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+        value
+          .downcast_ref::<Object<$type>>()
+          .map(|object| object.value().clone())
+          .ok_or_else(|| type_error($kind, value))
+      }
+    }
+    impl IntoValue for $type {
+      #[inline]
+      fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+        $new_func(scope, self).into_value()
+      }
+    }
+  };
+}
+
+primitive!(bool, "Bool", new_bool);
+primitive!(char, "Char", new_char);
+primitive!(String, "String", new_string);
+
+impl<'a> IntoValue for &'a str {
+  #[inline]
+  fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+    new_string(scope, self).into_value()
+  }
+}
+
+#[inline]
+fn integer_value(value: &Gc<dyn Value>) -> Option<i128> {
+  macro_rules! downcast_integer {
+    ($($type:ty),*) => {
+      $(
+        if let Some(object) = value.downcast_ref::<Object<$type>>() {
+          return Some(*object.value() as i128);
+        }
+      )*
+    };
+  }
+  downcast_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+  None
+}
+
+macro_rules! integer {
+  ($type:ty, $kind:expr, $new_func:ident) => {
+    impl FromValue for $type {
+      #[inline]
+      fn from_value(_scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+        match integer_value(value) {
+          Some(integer) => <$type>::try_from(integer).map_err(|_| {
+            format!(
+              "expected {}, found {} {} which is out of range",
+              $kind,
+              value.kind().name(),
+              integer
+            )
+          }),
+          None => Err(type_error($kind, value)),
+        }
+      }
+    }
+    impl IntoValue for $type {
+      #[inline]
+      fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+        $new_func(scope, self).into_value()
+      }
+    }
+  };
+}
+
+integer!(u8, "U8", new_u8);
+integer!(u16, "U16", new_u16);
+integer!(u32, "U32", new_u32);
+integer!(u64, "U64", new_u64);
+integer!(usize, "USize", new_usize);
+integer!(i8, "I8", new_i8);
+integer!(i16, "I16", new_i16);
+integer!(i32, "I32", new_i32);
+integer!(i64, "I64", new_i64);
+integer!(isize, "ISize", new_isize);
+
+macro_rules! float {
+  ($type:ty, $kind:expr, $new_func:ident) => {
+    impl FromValue for $type {
+      #[inline]
+      fn from_value(_scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+        if let Some(object) = value.downcast_ref::<Object<F32>>() {
+          Ok(object.value().0 as $type)
+        } else if let Some(object) = value.downcast_ref::<Object<F64>>() {
+          Ok(object.value().0 as $type)
+        } else {
+          Err(type_error($kind, value))
+        }
+      }
+    }
+    impl IntoValue for $type {
+      #[inline]
+      fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+        $new_func(scope, self).into_value()
+      }
+    }
+  };
+}
+
+float!(f32, "F32", new_f32);
+float!(f64, "F64", new_f64);
+
+impl<T> FromValue for Option<T>
+where
+  T: FromValue,
+{
+  #[inline]
+  fn from_value(scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+    if value == &nil_value(scope).into_value() {
+      Ok(None)
+    } else {
+      T::from_value(scope, value).map(Some)
+    }
+  }
+}
+impl<T> IntoValue for Option<T>
+where
+  T: IntoValue,
+{
+  #[inline]
+  fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+    match self {
+      Some(value) => value.into_value(scope),
+      None => nil_value(scope).into_value(),
+    }
+  }
+}
+
+#[inline]
+fn sequence_values(value: &Gc<dyn Value>, expected: &str) -> Result<Vec<Gc<dyn Value>>, String> {
+  if let Some(vector) = value.downcast_ref::<Object<Vector>>() {
+    Ok(vector.iter().map(Clone::clone).collect())
+  } else if let Some(list) = value.downcast_ref::<Object<List>>() {
+    Ok(list.iter().map(Clone::clone).collect())
+  } else {
+    Err(type_error(expected, value))
+  }
+}
+
+impl<T> FromValue for Vec<T>
+where
+  T: FromValue,
+{
+  #[inline]
+  fn from_value(scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+    sequence_values(value, "Vector")?
+      .iter()
+      .enumerate()
+      .map(|(index, value)| {
+        T::from_value(scope, value).map_err(|error| format!("at index {}: {}", index, error))
+      })
+      .collect()
+  }
+}
+impl<T> IntoValue for Vec<T>
+where
+  T: IntoValue,
+{
+  #[inline]
+  fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+    new_vector_from(
+      scope,
+      self
+        .into_iter()
+        .map(|value| value.into_value(scope))
+        .collect::<Vec<Gc<dyn Value>>>()
+        .into(),
+    )
+    .into_value()
+  }
+}
+
+macro_rules! hash_map {
+  ($hash_map:ident) => {
+    impl<K, V, S> FromValue for $hash_map<K, V, S>
+    where
+      K: FromValue + Eq + Hash,
+      V: FromValue,
+      S: BuildHasher + Default,
+    {
+      #[inline]
+      fn from_value(scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+        let map = value
+          .downcast_ref::<Object<Map>>()
+          .ok_or_else(|| type_error("Map", value))?;
+        let mut hash_map = $hash_map::with_capacity_and_hasher(map.len(), S::default());
+
+        for (key, value) in map.iter() {
+          let key_value =
+            K::from_value(scope, key).map_err(|error| format!("at key {:?}: {}", key, error))?;
+          let value_value =
+            V::from_value(scope, value).map_err(|error| format!("at key {:?}: {}", key, error))?;
+          hash_map.insert(key_value, value_value);
+        }
+
+        Ok(hash_map)
+      }
+    }
+    impl<K, V, S> IntoValue for $hash_map<K, V, S>
+    where
+      K: IntoValue,
+      V: IntoValue,
+    {
+      #[inline]
+      fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+        let mut map = Map::new();
+
+        for (key, value) in self {
+          map.set(key.into_value(scope), value.into_value(scope));
+        }
+
+        new_map_from(scope, map).into_value()
+      }
+    }
+  };
+}
+
+hash_map!(HashMap);
+#[cfg(feature = "std")]
+hash_map!(StdHashMap);
+
+macro_rules! tuple {
+  ($len:expr, $($name:ident : $index:tt),*) => {
+    impl<$($name),*> FromValue for ($($name,)*)
+    where
+      $($name: FromValue),*
+    {
+      #[inline]
+      fn from_value(scope: &Gc<Object<Scope>>, value: &Gc<dyn Value>) -> Result<Self, String> {
+        let values = sequence_values(value, "Vector")?;
+
+        if values.len() != $len {
+          return Err(format!(
+            "expected Vector of length {}, found length {}",
+            $len,
+            values.len()
+          ));
+        }
+
+        Ok(($(
+          $name::from_value(scope, &values[$index])
+            .map_err(|error| format!("at index {}: {}", $index, error))?,
+        )*))
+      }
+    }
+    impl<$($name),*> IntoValue for ($($name,)*)
+    where
+      $($name: IntoValue),*
+    {
+      #[inline]
+      fn into_value(self, scope: &Gc<Object<Scope>>) -> Gc<dyn Value> {
+        let mut vector = Vector::new();
+        $(vector.push(self.$index.into_value(scope));)*
+        new_vector_from(scope, vector).into_value()
+      }
+    }
+  };
+}
+
+tuple!(1, A: 0);
+tuple!(2, A: 0, B: 1);
+tuple!(3, A: 0, B: 1, C: 2);
+tuple!(4, A: 0, B: 1, C: 2, D: 3);
+tuple!(5, A: 0, B: 1, C: 2, D: 3, E: 4);
+tuple!(6, A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+
+#[inline]
+pub fn from_argument<T>(
+  scope: &Gc<Object<Scope>>,
+  args: &Gc<Object<Vector>>,
+  index: usize,
+) -> Result<T, String>
+where
+  T: FromValue,
+{
+  let nil = nil_value(scope).into_value();
+  T::from_value(scope, args.get(index).unwrap_or(&nil))
+    .map_err(|error| format!("invalid argument {}: {}", index, error))
+}
+
+#[inline]
+pub fn check_arity(args: &Gc<Object<Vector>>, arity: usize) -> Result<(), String> {
+  if args.len() > arity {
+    Err(format!(
+      "expected {} arguments, found {}",
+      arity,
+      args.len()
+    ))
+  } else {
+    Ok(())
+  }
+}
+
+pub trait TypedFunction<A>: 'static {
+  fn arity(&self) -> usize;
+  fn call_typed(
+    &self,
+    scope: &Gc<Object<Scope>>,
+    args: &Gc<Object<Vector>>,
+  ) -> Result<Gc<dyn Value>, Gc<dyn Value>>;
+}
+
+macro_rules! typed_function {
+  ($len:expr $(, $name:ident : $index:tt)*) => {
+    impl<Func, Ret $(, $name)*> TypedFunction<($($name,)*)> for Func
+    where
+      Func: 'static + Fn($($name),*) -> Ret,
+      Ret: IntoResult,
+      $($name: FromValue),*
+    {
+      #[inline]
+      fn arity(&self) -> usize {
+        $len
+      }
+      #[inline]
+      #[allow(unused_variables)]
+      fn call_typed(
+        &self,
+        scope: &Gc<Object<Scope>>,
+        args: &Gc<Object<Vector>>,
+      ) -> Result<Gc<dyn Value>, Gc<dyn Value>> {
+        (self)($(
+          from_argument::<$name>(scope, args, $index)
+            .map_err(|error| new_string(scope, error).into_value())?
+        ),*)
+        .into_result(scope)
+      }
+    }
+  };
+}
+
+typed_function!(0);
+typed_function!(1, A: 0);
+typed_function!(2, A: 0, B: 1);
+typed_function!(3, A: 0, B: 1, C: 2);
+typed_function!(4, A: 0, B: 1, C: 2, D: 3);
+typed_function!(5, A: 0, B: 1, C: 2, D: 3, E: 4);
+typed_function!(6, A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+
+#[inline]
+pub fn call_typed_function<F, A>(
+  scope: &Gc<Object<Scope>>,
+  args: &Gc<Object<Vector>>,
+  function: &F,
+) -> Gc<dyn Value>
+where
+  F: TypedFunction<A>,
+{
+  if let Err(error) = check_arity(args, function.arity()) {
+    return throw_error(scope, new_string(scope, error).into_value());
+  }
+
+  match function.call_typed(scope, args) {
+    Ok(value) => value,
+    Err(error) => throw_error(scope, error),
+  }
+}
+
+#[inline]
+pub fn new_typed_function<F, A>(
+  scope: &Gc<Object<Scope>>,
+  name: Option<Gc<Object<Symbol>>>,
+  params: Gc<Object<Vector>>,
+  function: F,
+) -> Gc<Object<Function>>
+where
+  F: TypedFunction<A>,
+  A: 'static,
+{
+  new_external_function(scope, name, params, move |scope, args| {
+    call_typed_function(scope, args, &function)
+  })
+}
+
+/// Binds a typed function to `name` and registers it under `owner` like
+/// `add_external_function`.
+#[inline]
+pub fn add_typed_function<F, A, N>(
+  scope: &Gc<Object<Scope>>,
+  owner: &str,
+  name: N,
+  params: Vec<N>,
+  function: F,
+) where
+  F: TypedFunction<A>,
+  A: 'static,
+  N: ToString,
+{
+  let mut vector = Vector::new();
+
+  for param in params {
+    vector.push(new_symbol(scope, param).into_value());
+  }
+
+  let function = new_typed_function(
+    scope,
+    Some(new_symbol(scope, name.to_string())),
+    new_vector_from(scope, vector),
+    function,
+  );
+  register_external_function(scope, owner, &function);
+  scope_set(scope, &(name.to_string()), function.into_value());
+}
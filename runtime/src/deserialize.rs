@@ -0,0 +1,181 @@
+use alloc::string::String;
+use core::fmt;
+
+use gc::Gc;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use super::{
+  new_bool, new_char, new_f32, new_f64, new_i16, new_i32, new_i64, new_i8, new_map_from,
+  new_string, new_u16, new_u32, new_u64, new_u8, new_vector_from, nil_value, Map, Object, Scope,
+  Value, Vector,
+};
+
+#[derive(Clone, Copy)]
+pub struct ValueSeed<'a> {
+  scope: &'a Gc<Object<Scope>>,
+}
+
+impl<'a> ValueSeed<'a> {
+  #[inline]
+  pub fn new(scope: &'a Gc<Object<Scope>>) -> Self {
+    ValueSeed { scope }
+  }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ValueSeed<'a> {
+  type Value = Gc<dyn Value>;
+
+  #[inline]
+  fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    deserializer.deserialize_any(self)
+  }
+}
+
+impl<'de, 'a> Visitor<'de> for ValueSeed<'a> {
+  type Value = Gc<dyn Value>;
+
+  #[inline]
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("a nil, bool, number, char, string, sequence or map value")
+  }
+
+  #[inline]
+  fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E> {
+    Ok(new_bool(self.scope, value).into_value())
+  }
+
+  #[inline]
+  fn visit_i8<E>(self, value: i8) -> Result<Self::Value, E> {
+    Ok(new_i8(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_i16<E>(self, value: i16) -> Result<Self::Value, E> {
+    Ok(new_i16(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_i32<E>(self, value: i32) -> Result<Self::Value, E> {
+    Ok(new_i32(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E> {
+    Ok(new_i64(self.scope, value).into_value())
+  }
+
+  #[inline]
+  fn visit_u8<E>(self, value: u8) -> Result<Self::Value, E> {
+    Ok(new_u8(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_u16<E>(self, value: u16) -> Result<Self::Value, E> {
+    Ok(new_u16(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_u32<E>(self, value: u32) -> Result<Self::Value, E> {
+    Ok(new_u32(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E> {
+    Ok(new_u64(self.scope, value).into_value())
+  }
+
+  #[inline]
+  fn visit_f32<E>(self, value: f32) -> Result<Self::Value, E> {
+    Ok(new_f32(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E> {
+    Ok(new_f64(self.scope, value).into_value())
+  }
+
+  #[inline]
+  fn visit_char<E>(self, value: char) -> Result<Self::Value, E> {
+    Ok(new_char(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_str<E>(self, value: &str) -> Result<Self::Value, E> {
+    Ok(new_string(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_string<E>(self, value: String) -> Result<Self::Value, E> {
+    Ok(new_string(self.scope, value).into_value())
+  }
+  #[inline]
+  fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E> {
+    Ok(
+      new_vector_from(
+        self.scope,
+        value
+          .iter()
+          .map(|byte| new_u8(self.scope, *byte).into_value())
+          .collect::<::alloc::vec::Vec<Gc<dyn Value>>>()
+          .into(),
+      )
+      .into_value(),
+    )
+  }
+
+  #[inline]
+  fn visit_none<E>(self) -> Result<Self::Value, E> {
+    Ok(nil_value(self.scope).into_value())
+  }
+  #[inline]
+  fn visit_unit<E>(self) -> Result<Self::Value, E> {
+    Ok(nil_value(self.scope).into_value())
+  }
+  #[inline]
+  fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    deserializer.deserialize_any(self)
+  }
+  #[inline]
+  fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    deserializer.deserialize_any(self)
+  }
+
+  #[inline]
+  fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+  where
+    A: SeqAccess<'de>,
+  {
+    let mut vector = Vector::new();
+
+    while let Some(value) = seq.next_element_seed(self)? {
+      vector.push(value);
+    }
+
+    Ok(new_vector_from(self.scope, vector).into_value())
+  }
+
+  #[inline]
+  fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+  where
+    A: MapAccess<'de>,
+  {
+    let mut map = Map::new();
+
+    while let Some((key, value)) = access.next_entry_seed(self, self)? {
+      map.set(key, value);
+    }
+
+    Ok(new_map_from(self.scope, map).into_value())
+  }
+}
+
+#[inline]
+pub fn deserialize_value<'de, D>(
+  scope: &Gc<Object<Scope>>,
+  deserializer: D,
+) -> Result<Gc<dyn Value>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  ValueSeed::new(scope).deserialize(deserializer)
+}
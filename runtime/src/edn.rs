@@ -0,0 +1,823 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use gc::{Gc, Trace};
+
+use super::{
+  add_external_function, call_function, from_argument, new_bool, new_char, new_f32, new_f64,
+  new_i16, new_i32, new_i64, new_i8, new_isize, new_keyword, new_kind, new_list_from, new_map_from,
+  new_object, new_scope, new_set_from, new_string, new_symbol, new_u16, new_u32, new_u64, new_u8,
+  new_usize, new_vector, new_vector_from, nil_value, scope_get_with_kind, scope_set, throw_error,
+  type_error, Function, Keyword, Kind, List, Map, Object, Reader, Scope, Set, Symbol, Value,
+  Vector, F32, F64,
+};
+
+#[derive(Clone, PartialEq, PartialOrd, Eq, Hash)]
+pub struct Tagged {
+  pub(crate) tag: Gc<Object<Symbol>>,
+  pub(crate) value: Gc<dyn Value>,
+}
+
+impl Trace for Tagged {
+  #[inline]
+  fn trace(&mut self, marked: bool) {
+    self.tag.trace(marked);
+    self.value.trace(marked);
+  }
+}
+
+impl fmt::Debug for Tagged {
+  #[inline]
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "#{:?} {:?}", self.tag, self.value)
+  }
+}
+
+impl Tagged {
+  #[inline]
+  pub fn new(tag: Gc<Object<Symbol>>, value: Gc<dyn Value>) -> Self {
+    Tagged { tag, value }
+  }
+
+  #[inline]
+  pub fn tag(&self) -> &Gc<Object<Symbol>> {
+    &self.tag
+  }
+  #[inline]
+  pub fn value(&self) -> &Gc<dyn Value> {
+    &self.value
+  }
+
+  #[inline]
+  pub(crate) fn init_kind(scope: &Gc<Object<Scope>>) {
+    let tagged_kind = new_kind::<Tagged>(scope, "Tagged");
+    scope_set(scope, "Tagged", tagged_kind.into_value());
+  }
+}
+
+#[inline]
+pub fn init_edn_scope(scope: &Gc<Object<Scope>>) {
+  scope_set(
+    scope,
+    "__edn_tags",
+    new_map_from(scope, Map::new()).into_value(),
+  );
+
+  add_external_function(scope, "lisp_runtime", "edn.read", vec!["string"], edn_read);
+  add_external_function(scope, "lisp_runtime", "edn.write", vec!["value"], edn_write);
+  add_external_function(
+    scope,
+    "lisp_runtime",
+    "edn.register_tag",
+    vec!["tag", "handler"],
+    edn_register_tag,
+  );
+}
+
+#[inline]
+pub fn edn_read(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  match from_argument::<String>(scope, args, 0)
+    .and_then(|string| read_edn(scope, &string))
+    .map_err(|error| format!("failed to read EDN: {}", error))
+  {
+    Ok(value) => value,
+    Err(error) => throw_error(scope, new_string(scope, error).into_value()),
+  }
+}
+
+#[inline]
+pub fn edn_write(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let value = args
+    .get(0)
+    .map(Clone::clone)
+    .unwrap_or_else(|| nil_value(scope).clone().into_value());
+
+  match write_edn(&value) {
+    Ok(string) => new_string(scope, string).into_value(),
+    Err(error) => throw_error(
+      scope,
+      new_string(scope, format!("failed to write EDN: {}", error)).into_value(),
+    ),
+  }
+}
+
+#[inline]
+pub fn edn_register_tag(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let tag_value = args
+    .get(0)
+    .map(Clone::clone)
+    .unwrap_or_else(|| nil_value(scope).clone().into_value());
+  let tag = if let Some(symbol) = tag_value.downcast_ref::<Object<Symbol>>() {
+    symbol.value().to_string()
+  } else if let Some(keyword) = tag_value.downcast_ref::<Object<Keyword>>() {
+    keyword.value().as_str().to_owned()
+  } else if let Some(string) = tag_value.downcast_ref::<Object<String>>() {
+    string.value().clone()
+  } else {
+    return throw_error(
+      scope,
+      new_string(
+        scope,
+        format!(
+          "invalid argument 0: {}",
+          type_error("Symbol, Keyword or String", &tag_value)
+        ),
+      )
+      .into_value(),
+    );
+  };
+  let handler = match from_argument::<Gc<Object<Function>>>(scope, args, 1) {
+    Ok(handler) => handler,
+    Err(error) => return throw_error(scope, new_string(scope, error).into_value()),
+  };
+
+  register_edn_tag(scope, &tag, handler);
+  nil_value(scope).clone().into_value()
+}
+
+#[inline]
+pub fn edn_tags(scope: &Gc<Object<Scope>>) -> Gc<Object<Map>> {
+  scope_get_with_kind::<Map>(scope, "__edn_tags").expect("failed to get __edn_tags")
+}
+
+#[inline]
+pub fn register_edn_tag(scope: &Gc<Object<Scope>>, tag: &str, handler: Gc<Object<Function>>) {
+  edn_tags(scope).set(new_string(scope, tag).into_value(), handler.into_value());
+}
+
+#[inline]
+pub fn tagged_kind(scope: &Gc<Object<Scope>>) -> Gc<Object<Kind>> {
+  scope_get_with_kind::<Kind>(scope, "Tagged").expect("failed to get Tagged Kind")
+}
+#[inline]
+pub fn new_tagged<T>(scope: &Gc<Object<Scope>>, tag: T, value: Gc<dyn Value>) -> Gc<Object<Tagged>>
+where
+  T: ToString,
+{
+  new_object(
+    scope,
+    Object::new(
+      tagged_kind(scope).clone(),
+      Tagged::new(new_symbol(scope, tag), value),
+    ),
+  )
+}
+
+#[inline]
+pub fn read_edn(scope: &Gc<Object<Scope>>, string: &str) -> Result<Gc<dyn Value>, String> {
+  let mut reader = Reader::new(None, string.chars().collect());
+
+  let mut value = None;
+
+  loop {
+    skip_whitespace(&mut reader);
+
+    if reader.peek().is_none() {
+      return Ok(value.unwrap_or_else(|| nil_value(scope).into_value()));
+    } else if let Some(form) = read_form(scope, &mut reader)? {
+      if value.is_some() {
+        return Err(error(&reader, "unexpected input after the value"));
+      }
+      value = Some(form);
+    }
+  }
+}
+
+#[inline]
+fn read_form(
+  scope: &Gc<Object<Scope>>,
+  reader: &mut Reader,
+) -> Result<Option<Gc<dyn Value>>, String> {
+  let ch = reader
+    .peek()
+    .ok_or_else(|| error(reader, "unexpected end of input"))?;
+
+  match ch {
+    '(' => {
+      reader.consume();
+      let values = read_forms(scope, reader, ')')?;
+      Ok(Some(
+        new_list_from(scope, values.iter().collect::<List>()).into_value(),
+      ))
+    }
+    '[' => {
+      reader.consume();
+      let values = read_forms(scope, reader, ']')?;
+      Ok(Some(new_vector_from(scope, values.into()).into_value()))
+    }
+    '{' => {
+      reader.consume();
+      read_map(scope, reader).map(Some)
+    }
+    '"' => {
+      reader.consume();
+      read_string(scope, reader).map(Some)
+    }
+    '\\' => {
+      reader.consume();
+      read_char(scope, reader).map(Some)
+    }
+    ':' => {
+      reader.consume();
+      let name = read_token(reader);
+      if name.is_empty() {
+        Err(error(reader, "invalid keyword"))
+      } else {
+        Ok(Some(new_keyword(scope, name).into_value()))
+      }
+    }
+    '#' => {
+      reader.consume();
+      read_dispatch(scope, reader)
+    }
+    ')' | ']' | '}' => Err(error(reader, &format!("unexpected `{}`", ch))),
+    ch => {
+      if is_number_start(reader, ch) {
+        let token = read_token(reader);
+        read_number(scope, reader, &token).map(Some)
+      } else {
+        let token = read_token(reader);
+        Ok(Some(match token.as_str() {
+          "nil" => nil_value(scope).into_value(),
+          "true" => new_bool(scope, true).into_value(),
+          "false" => new_bool(scope, false).into_value(),
+          _ => new_symbol(scope, token).into_value(),
+        }))
+      }
+    }
+  }
+}
+
+#[inline]
+fn read_forms(
+  scope: &Gc<Object<Scope>>,
+  reader: &mut Reader,
+  closer: char,
+) -> Result<Vec<Gc<dyn Value>>, String> {
+  let mut values = Vec::new();
+
+  loop {
+    skip_whitespace(reader);
+
+    match reader.peek() {
+      Some(ch) if ch == closer => {
+        reader.consume();
+        return Ok(values);
+      }
+      Some(_) => {
+        if let Some(value) = read_form(scope, reader)? {
+          values.push(value);
+        }
+      }
+      None => {
+        return Err(error(
+          reader,
+          &format!("unexpected end of input, expected `{}`", closer),
+        ))
+      }
+    }
+  }
+}
+
+#[inline]
+fn read_map(scope: &Gc<Object<Scope>>, reader: &mut Reader) -> Result<Gc<dyn Value>, String> {
+  let values = read_forms(scope, reader, '}')?;
+
+  if values.len() % 2 != 0 {
+    return Err(error(
+      reader,
+      "map literal must contain an even number of forms",
+    ));
+  }
+
+  let mut map = Map::new();
+  let mut iter = values.into_iter();
+
+  while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+    map.set(key, value);
+  }
+
+  Ok(new_map_from(scope, map).into_value())
+}
+
+#[inline]
+fn read_dispatch(
+  scope: &Gc<Object<Scope>>,
+  reader: &mut Reader,
+) -> Result<Option<Gc<dyn Value>>, String> {
+  match reader.peek() {
+    Some('{') => {
+      reader.consume();
+      let mut set = Set::new();
+      for value in read_forms(scope, reader, '}')? {
+        set.add(value);
+      }
+      Ok(Some(new_set_from(scope, set).into_value()))
+    }
+    Some('_') => {
+      reader.consume();
+      read_tagged_form(scope, reader)?;
+      Ok(None)
+    }
+    Some('#') => {
+      reader.consume();
+      let value = match read_token(reader).as_str() {
+        "NaN" => ::core::f64::NAN,
+        "Inf" => ::core::f64::INFINITY,
+        "-Inf" => ::core::f64::NEG_INFINITY,
+        token => {
+          return Err(error(
+            reader,
+            &format!("invalid symbolic value ##{}", token),
+          ))
+        }
+      };
+      Ok(Some(new_f64(scope, value).into_value()))
+    }
+    Some(ch) if ch.is_alphabetic() => {
+      let tag = read_token(reader);
+
+      skip_whitespace(reader);
+      if let Some(value) = read_number_tag(scope, reader, &tag)? {
+        return Ok(Some(value));
+      }
+
+      let value = read_tagged_form(scope, reader)?;
+      let handler = edn_tags(scope)
+        .get(&new_string(scope, &tag).into_value())
+        .and_then(|handler| handler.downcast_ref::<Object<Function>>().map(Clone::clone));
+
+      match handler {
+        Some(handler) => {
+          let handler_scope = new_scope(scope);
+          let mut args = new_vector(scope);
+
+          args.push(value);
+
+          Ok(Some(call_function(&handler_scope, handler, args)))
+        }
+        None => default_tag_handler(scope, reader, &tag, value).map(Some),
+      }
+    }
+    _ => Err(error(reader, "invalid dispatch character after `#`")),
+  }
+}
+
+#[inline]
+fn read_tagged_form(
+  scope: &Gc<Object<Scope>>,
+  reader: &mut Reader,
+) -> Result<Gc<dyn Value>, String> {
+  loop {
+    skip_whitespace(reader);
+
+    match reader.peek() {
+      Some(')') | Some(']') | Some('}') | None => {
+        return Err(error(reader, "expected a value after tag"))
+      }
+      Some(_) => {
+        if let Some(value) = read_form(scope, reader)? {
+          return Ok(value);
+        }
+      }
+    }
+  }
+}
+
+#[inline]
+fn default_tag_handler(
+  scope: &Gc<Object<Scope>>,
+  reader: &Reader,
+  tag: &str,
+  value: Gc<dyn Value>,
+) -> Result<Gc<dyn Value>, String> {
+  let valid = match tag {
+    "inst" => value
+      .downcast_ref::<Object<String>>()
+      .map(|string| is_inst(string.value()))
+      .unwrap_or(false),
+    "uuid" => value
+      .downcast_ref::<Object<String>>()
+      .map(|string| is_uuid(string.value()))
+      .unwrap_or(false),
+    _ => true,
+  };
+
+  if valid {
+    Ok(new_tagged(scope, tag, value).into_value())
+  } else {
+    Err(error(
+      reader,
+      &format!("invalid #{} value {:?}", tag, value),
+    ))
+  }
+}
+
+macro_rules! read_number_tags {
+  ($scope:ident, $reader:ident, $tag:ident, $($name:expr => $type:ty, $new_func:ident),*) => {
+    match $tag {
+      $(
+        $name => {
+          let token = read_token($reader);
+          let token = token.trim_start_matches('+');
+
+          <$type>::from_str(token)
+            .map(|value| Some($new_func($scope, value).into_value()))
+            .map_err(|_| error($reader, &format!("invalid #{} value {}", $tag, token)))
+        }
+      )*
+      _ => Ok(None),
+    }
+  };
+}
+
+#[inline]
+fn read_number_tag(
+  scope: &Gc<Object<Scope>>,
+  reader: &mut Reader,
+  tag: &str,
+) -> Result<Option<Gc<dyn Value>>, String> {
+  read_number_tags!(
+    scope, reader, tag,
+    "u8" => u8, new_u8,
+    "u16" => u16, new_u16,
+    "u32" => u32, new_u32,
+    "u64" => u64, new_u64,
+    "usize" => usize, new_usize,
+    "i8" => i8, new_i8,
+    "i16" => i16, new_i16,
+    "i32" => i32, new_i32,
+    "i64" => i64, new_i64,
+    "isize" => isize, new_isize,
+    "f32" => f32, new_f32,
+    "f64" => f64, new_f64
+  )
+}
+
+#[inline]
+fn read_number(
+  scope: &Gc<Object<Scope>>,
+  reader: &Reader,
+  token: &str,
+) -> Result<Gc<dyn Value>, String> {
+  let digits = token.trim_start_matches('+');
+
+  if digits.ends_with('M') || digits.contains('.') || digits.contains('e') || digits.contains('E') {
+    f64::from_str(digits.trim_end_matches('M'))
+      .map(|value| new_f64(scope, value).into_value())
+      .map_err(|_| error(reader, &format!("invalid float {}", token)))
+  } else {
+    let digits = digits.trim_end_matches('N');
+
+    isize::from_str(digits)
+      .map(|value| new_isize(scope, value).into_value())
+      .or_else(|_| u64::from_str(digits).map(|value| new_u64(scope, value).into_value()))
+      .map_err(|_| error(reader, &format!("invalid integer {}", token)))
+  }
+}
+
+#[inline]
+fn read_string(scope: &Gc<Object<Scope>>, reader: &mut Reader) -> Result<Gc<dyn Value>, String> {
+  let mut string = String::new();
+
+  loop {
+    match reader.next() {
+      Some('"') => return Ok(new_string(scope, string).into_value()),
+      Some('\\') => match reader.next() {
+        Some('"') => string.push('"'),
+        Some('\\') => string.push('\\'),
+        Some('n') => string.push('\n'),
+        Some('t') => string.push('\t'),
+        Some('r') => string.push('\r'),
+        Some('b') => string.push('\u{8}'),
+        Some('f') => string.push('\u{c}'),
+        Some('u') => {
+          let mut hex = String::new();
+          for _ in 0..4 {
+            hex.extend(reader.next());
+          }
+          string.push(
+            u32::from_str_radix(&hex, 16)
+              .ok()
+              .and_then(::core::char::from_u32)
+              .ok_or_else(|| error(reader, &format!("invalid unicode escape \\u{}", hex)))?,
+          );
+        }
+        Some(ch) => return Err(error(reader, &format!("invalid string escape \\{}", ch))),
+        None => break,
+      },
+      Some(ch) => string.push(ch),
+      None => break,
+    }
+  }
+
+  Err(error(reader, "unexpected end of input in string"))
+}
+
+#[inline]
+fn read_char(scope: &Gc<Object<Scope>>, reader: &mut Reader) -> Result<Gc<dyn Value>, String> {
+  let mut token = String::new();
+
+  token.extend(reader.next());
+  token.push_str(&read_token(reader));
+
+  let mut chars = token.chars();
+  let ch = match (chars.next(), chars.next()) {
+    (Some(ch), None) => ch,
+    _ => match token.as_str() {
+      "newline" => '\n',
+      "space" => ' ',
+      "tab" => '\t',
+      "return" => '\r',
+      "backspace" => '\u{8}',
+      "formfeed" => '\u{c}',
+      _ if token.starts_with('u') && token.len() == 5 => u32::from_str_radix(&token[1..], 16)
+        .ok()
+        .and_then(::core::char::from_u32)
+        .ok_or_else(|| error(reader, &format!("invalid character \\{}", token)))?,
+      _ => return Err(error(reader, &format!("invalid character \\{}", token))),
+    },
+  };
+
+  Ok(new_char(scope, ch).into_value())
+}
+
+#[inline]
+fn read_token(reader: &mut Reader) -> String {
+  let mut token = String::new();
+
+  while let Some(ch) = reader.peek() {
+    if is_whitespace(ch) || is_delimiter(ch) {
+      break;
+    } else {
+      reader.consume();
+      token.push(ch);
+    }
+  }
+
+  token
+}
+
+#[inline]
+fn skip_whitespace(reader: &mut Reader) {
+  while let Some(ch) = reader.peek() {
+    if ch == ';' {
+      while let Some(ch) = reader.peek() {
+        if ch == '\n' {
+          break;
+        }
+        reader.consume();
+      }
+    } else if is_whitespace(ch) {
+      reader.consume();
+    } else {
+      break;
+    }
+  }
+}
+
+#[inline]
+fn error(reader: &Reader, message: &str) -> String {
+  format!(
+    "{} at line {} column {}",
+    message,
+    reader.line(),
+    reader.col()
+  )
+}
+
+#[inline]
+fn is_whitespace(ch: char) -> bool {
+  ch.is_whitespace() || ch == ','
+}
+
+#[inline]
+fn is_delimiter(ch: char) -> bool {
+  match ch {
+    '(' | ')' | '[' | ']' | '{' | '}' | '"' | ';' => true,
+    _ => false,
+  }
+}
+
+#[inline]
+fn is_number_start(reader: &Reader, ch: char) -> bool {
+  if ch.is_ascii_digit() {
+    true
+  } else if ch == '-' || ch == '+' {
+    reader
+      .peek_nth(1)
+      .map(|ch| ch.is_ascii_digit())
+      .unwrap_or(false)
+  } else {
+    false
+  }
+}
+
+#[inline]
+fn is_inst(string: &str) -> bool {
+  let bytes = string.as_bytes();
+  let digits = |range: ::core::ops::Range<usize>| {
+    bytes.len() >= range.end && bytes[range].iter().all(u8::is_ascii_digit)
+  };
+
+  if !digits(0..4) {
+    return false;
+  }
+  if bytes.len() == 4 {
+    return true;
+  }
+  if bytes[4] != b'-' || !digits(5..7) {
+    return false;
+  }
+  if bytes.len() == 7 {
+    return true;
+  }
+  if bytes[7] != b'-' || !digits(8..10) {
+    return false;
+  }
+  if bytes.len() == 10 {
+    return true;
+  }
+  if bytes[10] != b'T' || !digits(11..13) || bytes.get(13) != Some(&b':') || !digits(14..16) {
+    return false;
+  }
+
+  let mut index = 16;
+  if bytes.get(index) == Some(&b':') {
+    if !digits(17..19) {
+      return false;
+    }
+    index = 19;
+    if bytes.get(index) == Some(&b'.') {
+      index += 1;
+      let start = index;
+      while bytes.get(index).map(u8::is_ascii_digit).unwrap_or(false) {
+        index += 1;
+      }
+      if index == start {
+        return false;
+      }
+    }
+  }
+
+  match bytes.get(index) {
+    None => true,
+    Some(&b'Z') => bytes.len() == index + 1,
+    Some(&b'+') | Some(&b'-') => {
+      bytes.len() == index + 6
+        && digits(index + 1..index + 3)
+        && bytes[index + 3] == b':'
+        && digits(index + 4..index + 6)
+    }
+    _ => false,
+  }
+}
+
+#[inline]
+fn is_uuid(string: &str) -> bool {
+  string.len() == 36
+    && string.char_indices().all(|(index, ch)| match index {
+      8 | 13 | 18 | 23 => ch == '-',
+      _ => ch.is_ascii_hexdigit(),
+    })
+}
+
+#[inline]
+pub fn write_edn(value: &Gc<dyn Value>) -> Result<String, String> {
+  let mut string = String::new();
+  write_value(&mut string, value)?;
+  Ok(string)
+}
+
+macro_rules! write_integers {
+  ($out:ident, $value:ident, $($type:ty),*) => {
+    $(
+      if let Some(number) = $value.downcast_ref::<Object<$type>>() {
+        $out.push_str(&number.value().to_string());
+        return Ok(());
+      }
+    )*
+  };
+}
+
+#[inline]
+fn write_value(out: &mut String, value: &Gc<dyn Value>) -> Result<(), String> {
+  if value.is::<Object<()>>() {
+    out.push_str("nil");
+    return Ok(());
+  } else if let Some(boolean) = value.downcast_ref::<Object<bool>>() {
+    out.push_str(if *boolean.value() { "true" } else { "false" });
+    return Ok(());
+  } else if let Some(number) = value.downcast_ref::<Object<F64>>() {
+    write_float(out, number.value().0);
+    return Ok(());
+  } else if let Some(number) = value.downcast_ref::<Object<F32>>() {
+    out.push_str("#f32 ");
+    if number.value().0.is_finite() {
+      out.push_str(&format!("{:?}", number.value().0));
+    } else {
+      return Err(format!("failed to write non finite F32 {:?}", number));
+    }
+    return Ok(());
+  }
+
+  // every integer kind is written as a plain EDN integer, the kind tags are
+  // only read
+  write_integers!(out, value, isize, i8, i16, i32, i64, usize, u8, u16, u32, u64);
+
+  if let Some(ch) = value.downcast_ref::<Object<char>>() {
+    write_char(out, *ch.value());
+  } else if let Some(string) = value.downcast_ref::<Object<String>>() {
+    write_string(out, string.value());
+  } else if let Some(keyword) = value.downcast_ref::<Object<Keyword>>() {
+    out.push(':');
+    out.push_str(keyword.value());
+  } else if let Some(symbol) = value.downcast_ref::<Object<Symbol>>() {
+    out.push_str(symbol.value());
+  } else if let Some(list) = value.downcast_ref::<Object<List>>() {
+    write_values(out, "(", list.iter(), ")")?;
+  } else if let Some(vector) = value.downcast_ref::<Object<Vector>>() {
+    write_values(out, "[", vector.iter(), "]")?;
+  } else if let Some(set) = value.downcast_ref::<Object<Set>>() {
+    write_values(out, "#{", set.iter(), "}")?;
+  } else if let Some(map) = value.downcast_ref::<Object<Map>>() {
+    write_values(
+      out,
+      "{",
+      map.iter().flat_map(|(key, value)| vec![key, value]),
+      "}",
+    )?;
+  } else if let Some(tagged) = value.downcast_ref::<Object<Tagged>>() {
+    out.push('#');
+    out.push_str(tagged.value().tag().value());
+    out.push(' ');
+    write_value(out, tagged.value().value())?;
+  } else {
+    return Err(format!(
+      "failed to write value of Kind {}, only data values can be written",
+      value.kind().name()
+    ));
+  }
+
+  Ok(())
+}
+
+#[inline]
+fn write_values<'a, I>(out: &mut String, open: &str, values: I, close: &str) -> Result<(), String>
+where
+  I: Iterator<Item = &'a Gc<dyn Value>>,
+{
+  out.push_str(open);
+  for (index, value) in values.enumerate() {
+    if index != 0 {
+      out.push(' ');
+    }
+    write_value(out, value)?;
+  }
+  out.push_str(close);
+  Ok(())
+}
+
+#[inline]
+fn write_float(out: &mut String, value: f64) {
+  if value.is_nan() {
+    out.push_str("##NaN");
+  } else if value.is_infinite() {
+    out.push_str(if value > 0.0 { "##Inf" } else { "##-Inf" });
+  } else {
+    out.push_str(&format!("{:?}", value));
+  }
+}
+
+#[inline]
+fn write_char(out: &mut String, ch: char) {
+  out.push('\\');
+  match ch {
+    '\n' => out.push_str("newline"),
+    ' ' => out.push_str("space"),
+    '\t' => out.push_str("tab"),
+    '\r' => out.push_str("return"),
+    '\u{8}' => out.push_str("backspace"),
+    '\u{c}' => out.push_str("formfeed"),
+    ch if ch.is_control() => out.push_str(&format!("u{:04x}", ch as u32)),
+    ch => out.push(ch),
+  }
+}
+
+#[inline]
+fn write_string(out: &mut String, string: &str) {
+  out.push('"');
+  for ch in string.chars() {
+    match ch {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\t' => out.push_str("\\t"),
+      '\r' => out.push_str("\\r"),
+      ch => out.push(ch),
+    }
+  }
+  out.push('"');
+}
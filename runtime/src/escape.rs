@@ -6,7 +6,7 @@ use gc::{Gc, Trace};
 use super::{new_kind, new_object, scope_get_with_kind, scope_set, Kind, Object, Scope, Value};
 
 #[derive(Clone, PartialEq, PartialOrd, Eq, Hash)]
-pub struct Escape(Gc<dyn Value>);
+pub struct Escape(pub(crate) Gc<dyn Value>);
 
 impl Trace for Escape {
   #[inline]
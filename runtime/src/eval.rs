@@ -1,7 +1,9 @@
 use alloc::{
+  collections::LinkedList,
   string::{String, ToString},
   vec::Vec,
 };
+use core::mem;
 use core::ops::Deref;
 
 use gc::Gc;
@@ -9,9 +11,10 @@ use gc::Gc;
 use super::{
   escape_kind, expand_special_form, function_kind, get_stack, list_kind, macro_kind, map_kind,
   new_keyword, new_list, new_list_from, new_map, new_scope, new_string, new_usize, new_vector,
-  new_vector_from, nil_value, read_value, scope_get, scope_get_with_kind, scope_set,
-  special_form_kind, symbol_kind, vector_kind, Escape, EvalState, Function, FunctionKind, List,
-  Map, Object, Reader, Scope, SpecialForm, Stack, Symbol, UnwindResult, Value, Vector,
+  new_vector_from, nil_value, read_value, scope_get_qualified, scope_get_with_kind, scope_set,
+  special_form_kind, symbol_kind, take_thrown_error, vector_kind, Escape, EvalState, Function,
+  FunctionKind, List, Map, Object, Reader, Scope, SpecialForm, Stack, Symbol, UnwindResult, Value,
+  Vector,
 };
 
 #[inline]
@@ -39,24 +42,35 @@ where
 pub fn eval(scope: &Gc<Object<Scope>>, value: Gc<dyn Value>) -> Gc<dyn Value> {
   let mut stack = get_stack(scope).clone();
 
+  with_nested_state(&mut stack, |stack| {
+    eval_evaluated_stack(stack, scope, value)
+  })
+}
+
+#[inline]
+fn eval_evaluated_stack(
+  stack: &mut Stack,
+  scope: &Gc<Object<Scope>>,
+  value: Gc<dyn Value>,
+) -> Gc<dyn Value> {
   stack.push_scope_and_value(scope.clone(), value);
 
   loop {
     match stack.state.pop_front() {
       Some(state) => match state {
-        EvalState::Eval => eval_eval_evaluated(&mut stack),
+        EvalState::Eval => eval_eval_evaluated(stack),
         EvalState::EvalVec => panic!("Invalid state EvalVec"),
         EvalState::EvalMap => panic!("Invalid state EvalMap"),
         EvalState::EvalMapKeyValue => panic!("Invalid state EvalMapKeyValue"),
-        EvalState::Call => eval_call_evaluated(&mut stack),
-        EvalState::CallFunction => eval_call_function(&mut stack),
-        EvalState::PopValue => eval_pop_value(&mut stack),
-        EvalState::PopScope => eval_pop_scope(&mut stack),
-        EvalState::Throw => eval_throw(&mut stack),
-        EvalState::Catch => panic!("Invalid state Catch"),
-        EvalState::If => eval_if(&mut stack),
-        EvalState::Def => eval_def(&mut stack),
-        EvalState::Expand => eval_expand(&mut stack),
+        EvalState::Call => eval_call_evaluated(stack),
+        EvalState::CallFunction => eval_call_function(stack),
+        EvalState::PopValue => eval_pop_value(stack),
+        EvalState::PopScope => eval_pop_scope(stack),
+        EvalState::Throw => eval_throw(stack),
+        EvalState::Catch => eval_catch(stack),
+        EvalState::If => eval_if(stack),
+        EvalState::Def => eval_def(stack),
+        EvalState::Expand => eval_expand(stack),
       },
       None => break,
     }
@@ -67,28 +81,96 @@ pub fn eval(scope: &Gc<Object<Scope>>, value: Gc<dyn Value>) -> Gc<dyn Value> {
     .expect("failed to get value from stack")
 }
 
+#[inline]
+pub fn call_function(
+  scope: &Gc<Object<Scope>>,
+  callable: Gc<Object<Function>>,
+  arguments: Gc<Object<Vector>>,
+) -> Gc<dyn Value> {
+  let mut stack_object = get_stack(scope).clone();
+
+  with_nested_state(stack_object.value_mut(), |stack| {
+    stack.scope.push_front(scope.clone());
+    stack.value.push_front(callable.into_value());
+    stack.value.push_front(arguments.into_value());
+    stack.state.push_front(EvalState::PopScope);
+    stack.state.push_front(EvalState::CallFunction);
+
+    eval_stack(stack)
+  })
+}
+
 #[inline]
 fn eval_raw(scope: &Gc<Object<Scope>>, value: Gc<dyn Value>) -> Gc<dyn Value> {
   let mut stack = get_stack(scope).clone();
 
-  stack.push_scope_and_value(scope.clone(), value);
+  with_nested_state(&mut stack, |stack| {
+    stack.push_scope_and_value(scope.clone(), value);
+
+    eval_stack(stack)
+  })
+}
+
+/// Runs `f` like an external function, an error the evaluations it starts do
+/// not catch ends them and is returned instead of unwinding.
+#[inline]
+pub fn catch_uncaught<F, T>(scope: &Gc<Object<Scope>>, f: F) -> Result<T, Gc<dyn Value>>
+where
+  F: FnOnce() -> T,
+{
+  let mut stack_object = get_stack(scope).clone();
+  let stack = stack_object.value_mut();
+  let lengths = (stack.value.len(), stack.scope.len(), stack.callable.len());
+  stack.nested.push_front(lengths);
+
+  // f evaluates on the same stack, so it is borrowed again afterwards
+  let value = f();
+  let stack = stack_object.value_mut();
+  stack.nested.pop_front();
+
+  match stack.error.take() {
+    Some(error) => Err(error),
+    None => Ok(value),
+  }
+}
 
+/// Evaluates on the shared stack with an empty state list, so evaluations
+/// started from external functions, like loaders running a module, do not
+/// consume the states of the evaluation that called them. An error the
+/// nested evaluation does not catch is thrown into the outer states once the
+/// external function that started it returns.
+#[inline]
+fn with_nested_state<F>(stack: &mut Stack, f: F) -> Gc<dyn Value>
+where
+  F: FnOnce(&mut Stack) -> Gc<dyn Value>,
+{
+  let lengths = (stack.value.len(), stack.scope.len(), stack.callable.len());
+  let states = mem::replace(&mut stack.state, LinkedList::new());
+  stack.nested.push_front(lengths);
+  let value = f(stack);
+  stack.nested.pop_front();
+  stack.state = states;
+  value
+}
+
+#[inline]
+fn eval_stack(stack: &mut Stack) -> Gc<dyn Value> {
   loop {
     match stack.state.pop_front() {
       Some(state) => match state {
-        EvalState::Eval => eval_eval(&mut stack),
-        EvalState::EvalVec => eval_eval_vec(&mut stack),
-        EvalState::EvalMap => eval_eval_map(&mut stack),
-        EvalState::EvalMapKeyValue => eval_eval_map_key_value(&mut stack),
-        EvalState::Call => eval_call(&mut stack),
-        EvalState::CallFunction => eval_call_function(&mut stack),
-        EvalState::PopValue => eval_pop_value(&mut stack),
-        EvalState::PopScope => eval_pop_scope(&mut stack),
-        EvalState::Throw => eval_throw(&mut stack),
-        EvalState::Catch => panic!("Invalid state Catch"),
-        EvalState::If => eval_if(&mut stack),
-        EvalState::Def => eval_def(&mut stack),
-        EvalState::Expand => eval_expand(&mut stack),
+        EvalState::Eval => eval_eval(stack),
+        EvalState::EvalVec => eval_eval_vec(stack),
+        EvalState::EvalMap => eval_eval_map(stack),
+        EvalState::EvalMapKeyValue => eval_eval_map_key_value(stack),
+        EvalState::Call => eval_call(stack),
+        EvalState::CallFunction => eval_call_function(stack),
+        EvalState::PopValue => eval_pop_value(stack),
+        EvalState::PopScope => eval_pop_scope(stack),
+        EvalState::Throw => eval_throw(stack),
+        EvalState::Catch => eval_catch(stack),
+        EvalState::If => eval_if(stack),
+        EvalState::Def => eval_def(stack),
+        EvalState::Expand => eval_expand(stack),
       },
       None => break,
     }
@@ -109,7 +191,7 @@ fn eval_eval(stack: &mut Stack) {
       .downcast_ref::<Object<Symbol>>()
       .expect("failed to downcast value to Symbol");
 
-    if let Some(value) = scope_get(scope, symbol.value().deref()) {
+    if let Some(value) = scope_get_qualified(scope, symbol.value().deref()) {
       stack.value.push_front(value.clone());
     } else {
       stack
@@ -473,8 +555,14 @@ fn eval_call_function(stack: &mut Stack) {
       stack.state.push_front(EvalState::Eval);
     }
     &FunctionKind::External(ref body) => {
-      let value = (&**body)(&scope, &arguments);
-      stack.value.push_front(value);
+      let value = body.call(&scope, &arguments);
+      let thrown_error = take_thrown_error(&scope);
+
+      if let Some(error) = stack.error.take().or(thrown_error) {
+        stack.throw_error(error);
+      } else {
+        stack.value.push_front(value);
+      }
     }
   }
 }
@@ -484,6 +572,17 @@ fn eval_pop_value(stack: &mut Stack) {
   stack.value.pop_front().expect("failed to pop value");
 }
 
+#[inline]
+fn eval_catch(stack: &mut Stack) {
+  // the block did not throw, so drop the handler under its value
+  let value = stack.value.pop_front().expect("failed to get value");
+  stack
+    .value
+    .pop_front()
+    .expect("failed to pop catch handler");
+  stack.value.push_front(value);
+}
+
 #[inline]
 fn eval_pop_scope(stack: &mut Stack) {
   stack.callable.pop_front().expect("failed to pop callable");
@@ -563,7 +662,16 @@ fn eval_throw(stack: &mut Stack) {
         break;
       }
       UnwindResult::Uncaught => {
-        panic!("Uncaught Error: {:?}", error.into_value());
+        let error_value = error
+          .get(&new_keyword(&scope, "value").into_value())
+          .expect("failed to get error value")
+          .clone();
+        let nil = nil_value(&scope).clone().into_value();
+
+        if !stack.throw_nested(scope, nil, error_value) {
+          panic!("Uncaught Error: {:?}", error.into_value());
+        }
+        break;
       }
     }
   }
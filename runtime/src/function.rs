@@ -1,22 +1,24 @@
-use alloc::string::ToString;
+use alloc::string::{String, ToString};
 use core::cmp::Ordering;
 use core::fmt;
 use core::hash::{Hash, Hasher};
+use core::ops::Deref;
 use core::ptr;
 
 use gc::{Gc, Trace};
 
 use super::{
-  eval, new_kind, new_list_from, new_object, new_symbol, new_vector_from, scope_get_with_kind,
-  scope_set, FunctionKind, Kind, List, Map, Object, Scope, Symbol, Value, Vector,
+  get_scope_root, new_kind, new_map, new_object, new_string, new_symbol, new_vector_from,
+  nil_value, scope_get_with_kind, scope_set, Captured, ExternalFunction, FunctionKind, Kind, Map,
+  Object, Scope, Symbol, Value, Vector,
 };
 
 #[derive(Eq)]
 pub struct Function {
-  name: Option<Gc<Object<Symbol>>>,
-  scope: Gc<Object<Scope>>,
-  params: Gc<Object<Vector>>,
-  body: FunctionKind,
+  pub(crate) name: Option<Gc<Object<Symbol>>>,
+  pub(crate) scope: Gc<Object<Scope>>,
+  pub(crate) params: Gc<Object<Vector>>,
+  pub(crate) body: FunctionKind,
 }
 
 impl Trace for Function {
@@ -89,7 +91,7 @@ impl Function {
     body: F,
   ) -> Self
   where
-    F: 'static + Fn(&Gc<Object<Scope>>, &Gc<Object<Vector>>) -> Gc<dyn Value>,
+    F: ExternalFunction,
   {
     Function {
       name,
@@ -166,9 +168,12 @@ where
   )
 }
 
+/// Binds an external function to `name` and registers it under `owner`, a
+/// stable key like the crate defining it, so an image can re-link it.
 #[inline]
 pub fn add_external_function<F, N>(
   scope: &Gc<Object<Scope>>,
+  owner: &str,
   name: N,
   params: ::alloc::vec::Vec<N>,
   body: F,
@@ -188,6 +193,58 @@ pub fn add_external_function<F, N>(
     new_vector_from(scope, vector),
     body,
   );
+  register_external_function(scope, owner, &function);
+  scope_set(scope, &(name.to_string()), function.into_value());
+}
+
+#[inline]
+pub fn new_external_closure<C, F>(
+  scope: &Gc<Object<Scope>>,
+  name: Option<Gc<Object<Symbol>>>,
+  params: Gc<Object<Vector>>,
+  captured: C,
+  body: F,
+) -> Gc<Object<Function>>
+where
+  C: 'static + Trace,
+  F: 'static + Fn(&C, &Gc<Object<Scope>>, &Gc<Object<Vector>>) -> Gc<dyn Value>,
+{
+  new_object(
+    scope,
+    Object::new(
+      function_kind(scope).clone(),
+      Function::new_external(name, scope.clone(), params, Captured::new(captured, body)),
+    ),
+  )
+}
+
+/// Unlike external functions closures are not registered, an image can not
+/// re-link their captured values and registering would keep them alive.
+#[inline]
+pub fn add_external_closure<C, F, N>(
+  scope: &Gc<Object<Scope>>,
+  name: N,
+  params: ::alloc::vec::Vec<N>,
+  captured: C,
+  body: F,
+) where
+  C: 'static + Trace,
+  F: 'static + Fn(&C, &Gc<Object<Scope>>, &Gc<Object<Vector>>) -> Gc<dyn Value>,
+  N: ToString,
+{
+  let mut vector = Vector::new();
+
+  for param in params {
+    vector.push(new_symbol(scope, param).into_value());
+  }
+
+  let function = new_external_closure(
+    scope,
+    Some(new_symbol(scope, name.to_string())),
+    new_vector_from(scope, vector),
+    captured,
+    body,
+  );
   scope_set(scope, &(name.to_string()), function.clone().into_value());
 }
 
@@ -229,9 +286,12 @@ where
   )
 }
 
+/// Binds an external macro to `name` and registers it under `owner`, a
+/// stable key like the crate defining it, so an image can re-link it.
 #[inline]
 pub fn add_external_macro<F, N>(
   scope: &Gc<Object<Scope>>,
+  owner: &str,
   name: N,
   params: ::alloc::vec::Vec<N>,
   body: F,
@@ -251,16 +311,105 @@ pub fn add_external_macro<F, N>(
     new_vector_from(scope, vector),
     body,
   );
-  scope_set(scope, &(name.to_string()), function.clone().into_value());
+  register_external_function(scope, owner, &function);
+  scope_set(scope, &(name.to_string()), function.into_value());
 }
 
+/// Registers a named external `function` so an image can re-link it, the
+/// function is identified by its name and its `owner`.
 #[inline]
-pub fn call_function(
+pub fn register_external_function(
   scope: &Gc<Object<Scope>>,
-  callable: Gc<Object<Function>>,
-  arguments: Gc<Object<Vector>>,
-) -> Gc<dyn Value> {
-  let mut list = arguments.value().iter().collect::<List>();
-  list.push_front(callable.into_value());
-  eval(scope, new_list_from(&scope, list).into_value())
+  owner: &str,
+  function: &Gc<Object<Function>>,
+) {
+  if let Some(name) = function.name() {
+    let root = get_scope_root(scope);
+    let mut externals = scope_get_with_kind::<Map>(root, "__externals").unwrap_or_else(|| {
+      let externals = new_map(root);
+      scope_set(root, "__externals", externals.clone().into_value());
+      externals
+    });
+    let owner_key = new_string(root, owner).into_value();
+    let mut owned = match externals
+      .get(&owner_key)
+      .and_then(|owned| owned.downcast_ref::<Object<Map>>())
+    {
+      Some(owned) => owned.clone(),
+      None => {
+        let owned = new_map(root);
+        externals.set(owner_key, owned.clone().into_value());
+        owned
+      }
+    };
+
+    owned.set(
+      new_string(root, name.value().deref()).into_value(),
+      function.clone().into_value(),
+    );
+  }
+}
+
+#[inline]
+pub fn get_external_function(
+  scope: &Gc<Object<Scope>>,
+  owner: &str,
+  name: &str,
+) -> Option<Gc<Object<Function>>> {
+  scope_get_with_kind::<Map>(get_scope_root(scope), "__externals")
+    .and_then(|externals| {
+      externals
+        .get(&new_string(scope, owner).into_value())
+        .and_then(|owned| owned.downcast_ref::<Object<Map>>())
+        .and_then(|owned| {
+          owned
+            .get(&new_string(scope, name).into_value())
+            .map(Clone::clone)
+        })
+    })
+    .and_then(|function| {
+      function
+        .downcast_ref::<Object<Function>>()
+        .map(Clone::clone)
+    })
+}
+
+/// The owner `function` was registered under, `None` when it is not
+/// registered.
+#[inline]
+pub fn get_external_function_owner(
+  scope: &Gc<Object<Scope>>,
+  function: &Gc<Object<Function>>,
+) -> Option<String> {
+  let name = new_string(scope, function.name()?.value().deref()).into_value();
+  let externals = scope_get_with_kind::<Map>(get_scope_root(scope), "__externals")?;
+
+  externals
+    .iter()
+    .find(|&(_, owned)| {
+      owned
+        .downcast_ref::<Object<Map>>()
+        .and_then(|owned| owned.get(&name))
+        .map(|registered| registered.as_ptr() as *const u8 == function.as_ptr() as *const u8)
+        .unwrap_or(false)
+    })
+    .and_then(|(owner, _)| owner.downcast_ref::<Object<String>>())
+    .map(|owner| owner.value().clone())
+}
+
+/// Throws `error` from an external function once it returns, the returned
+/// value should be returned from the external function and is discarded.
+#[inline]
+pub fn throw_error(scope: &Gc<Object<Scope>>, error: Gc<dyn Value>) -> Gc<dyn Value> {
+  scope_set(scope, "__error", error);
+  nil_value(scope).into_value()
+}
+
+#[inline]
+pub(crate) fn take_thrown_error(scope: &Gc<Object<Scope>>) -> Option<Gc<dyn Value>> {
+  let error = scope.get("__error");
+  if error.is_some() {
+    scope.remove("__error");
+  }
+  error
 }
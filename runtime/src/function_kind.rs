@@ -6,9 +6,61 @@ use gc::{Gc, Trace};
 
 use super::{Object, Scope, Value, Vector};
 
+pub trait ExternalFunction: 'static {
+  fn call(&self, scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value>;
+
+  #[inline]
+  fn trace(&mut self, _marked: bool) {}
+}
+
+impl<F> ExternalFunction for F
+where
+  F: 'static + Fn(&Gc<Object<Scope>>, &Gc<Object<Vector>>) -> Gc<dyn Value>,
+{
+  #[inline]
+  fn call(&self, scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+    self(scope, args)
+  }
+}
+
+pub struct Captured<C, F> {
+  captured: C,
+  function: F,
+}
+
+impl<C, F> Captured<C, F>
+where
+  C: 'static + Trace,
+  F: 'static + Fn(&C, &Gc<Object<Scope>>, &Gc<Object<Vector>>) -> Gc<dyn Value>,
+{
+  #[inline]
+  pub fn new(captured: C, function: F) -> Self {
+    Captured { captured, function }
+  }
+  #[inline]
+  pub fn captured(&self) -> &C {
+    &self.captured
+  }
+}
+
+impl<C, F> ExternalFunction for Captured<C, F>
+where
+  C: 'static + Trace,
+  F: 'static + Fn(&C, &Gc<Object<Scope>>, &Gc<Object<Vector>>) -> Gc<dyn Value>,
+{
+  #[inline]
+  fn call(&self, scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+    (self.function)(&self.captured, scope, args)
+  }
+  #[inline]
+  fn trace(&mut self, marked: bool) {
+    self.captured.trace(marked);
+  }
+}
+
 pub enum FunctionKind {
   Internal(Gc<dyn Value>),
-  External(Box<dyn Fn(&Gc<Object<Scope>>, &Gc<Object<Vector>>) -> Gc<dyn Value>>),
+  External(Box<dyn ExternalFunction>),
 }
 
 impl Trace for FunctionKind {
@@ -18,7 +70,9 @@ impl Trace for FunctionKind {
       FunctionKind::Internal(ref mut v) => {
         v.trace(marked);
       }
-      _ => {}
+      FunctionKind::External(ref mut f) => {
+        ExternalFunction::trace(&mut **f, marked);
+      }
     }
   }
 }
@@ -69,7 +123,7 @@ impl FunctionKind {
   #[inline]
   pub fn new_external<F>(body: F) -> Self
   where
-    F: 'static + Fn(&Gc<Object<Scope>>, &Gc<Object<Vector>>) -> Gc<dyn Value>,
+    F: ExternalFunction,
   {
     FunctionKind::External(Box::new(body))
   }
@@ -211,6 +211,7 @@ impl GcAllocator {
   pub(crate) fn init_scope(scope: &Gc<Object<Scope>>) {
     add_external_macro(
       scope,
+      "lisp_runtime",
       "gc_allocator.collect",
       vec!["gc_allocator"],
       gc_allocator_collect,
@@ -0,0 +1,933 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+
+use gc::Gc;
+use hashbrown::HashMap;
+
+use super::{
+  get_external_function, get_external_function_owner, get_scope_root, new_object, new_symbol,
+  new_vector, nil_value, scope_get, scope_get_with_kind, stack_kind, Atom, Escape, Function,
+  FunctionKind, Keyword, Kind, List, Map, Object, Scope, Set, Stack, Symbol, Tagged, Value, Vector,
+  F32, F64,
+};
+
+const IMAGE_MAGIC: &[u8; 8] = b"LISPIMG\0";
+const IMAGE_VERSION: u32 = 2;
+
+// root bindings owned by the running context, never written to or restored from an image
+const IMAGE_SKIPPED: [&str; 4] = ["__externals", "__image", "__stack", "default_gc_allocator"];
+
+const TAG_NIL: u8 = 0;
+const TAG_KIND: u8 = 1;
+const TAG_ROOT: u8 = 2;
+const TAG_STACK: u8 = 3;
+const TAG_EXTERNAL: u8 = 4;
+const TAG_BOOL: u8 = 5;
+const TAG_CHAR: u8 = 6;
+const TAG_U8: u8 = 7;
+const TAG_U16: u8 = 8;
+const TAG_U32: u8 = 9;
+const TAG_U64: u8 = 10;
+const TAG_USIZE: u8 = 11;
+const TAG_I8: u8 = 12;
+const TAG_I16: u8 = 13;
+const TAG_I32: u8 = 14;
+const TAG_I64: u8 = 15;
+const TAG_ISIZE: u8 = 16;
+const TAG_F32: u8 = 17;
+const TAG_F64: u8 = 18;
+const TAG_STRING: u8 = 19;
+const TAG_SYMBOL: u8 = 20;
+const TAG_KEYWORD: u8 = 21;
+const TAG_LIST: u8 = 22;
+const TAG_VECTOR: u8 = 23;
+const TAG_SET: u8 = 24;
+const TAG_MAP: u8 = 25;
+const TAG_ATOM: u8 = 26;
+const TAG_ESCAPE: u8 = 27;
+const TAG_TAGGED: u8 = 28;
+const TAG_SCOPE: u8 = 29;
+const TAG_FUNCTION: u8 = 30;
+
+enum Entry {
+  Nil,
+  Kind(String),
+  Root(String),
+  Stack,
+  // the owner and name an external function was registered under
+  External(String, String),
+  Object {
+    kind: u32,
+    meta: Option<u32>,
+    data: Data,
+  },
+}
+
+enum Data {
+  Bool(bool),
+  Char(char),
+  U8(u8),
+  U16(u16),
+  U32(u32),
+  U64(u64),
+  USize(u64),
+  I8(i8),
+  I16(i16),
+  I32(i32),
+  I64(i64),
+  ISize(i64),
+  F32(f32),
+  F64(f64),
+  String(String),
+  Symbol(String),
+  Keyword(String),
+  List(Vec<u32>),
+  Vector(Vec<u32>),
+  Set(Vec<u32>),
+  Map(Vec<(u32, u32)>),
+  Atom(u32),
+  Escape(u32),
+  Tagged(u32, u32),
+  Scope(Option<u32>, Vec<(String, u32)>),
+  Function(Option<u32>, u32, u32, u32),
+}
+
+#[inline]
+pub fn write_image(scope: &Gc<Object<Scope>>) -> Result<Vec<u8>, String> {
+  let root = get_scope_root(scope);
+  let mut writer = ImageWriter::new(root);
+  let mut entries = Vec::new();
+
+  writer.reference(&root.clone().into_value());
+
+  while entries.len() < writer.values.len() {
+    let value = writer.values[entries.len()].clone();
+    let entry = writer.entry(&value, entries.is_empty())?;
+    entries.push(entry);
+  }
+
+  let mut bytes = Vec::new();
+
+  bytes.extend_from_slice(IMAGE_MAGIC);
+  write_u32(&mut bytes, IMAGE_VERSION);
+  write_u32(&mut bytes, entries.len() as u32);
+
+  for entry in entries.iter() {
+    entry.write(&mut bytes);
+  }
+
+  Ok(bytes)
+}
+
+#[inline]
+pub fn read_image(scope: &Gc<Object<Scope>>, bytes: &[u8]) -> Result<(), String> {
+  let root = get_scope_root(scope).clone();
+  let entries = parse_image(bytes)?;
+  // every restored value is kept in a rooted vector so a collection mid-restore can not free it
+  let guard = new_vector(&root);
+
+  root.set("__image", guard.clone().into_value());
+  let result = restore_entries(&root, guard, &entries);
+  root.remove("__image");
+
+  result
+}
+
+struct ImageWriter {
+  roots: HashMap<usize, String>,
+  indices: HashMap<usize, u32>,
+  values: Vec<Gc<dyn Value>>,
+}
+
+impl ImageWriter {
+  #[inline]
+  fn new(root: &Gc<Object<Scope>>) -> Self {
+    let roots = root
+      .map
+      .read()
+      .iter()
+      .map(|(name, value)| (address(value), name.clone()))
+      .collect();
+
+    ImageWriter {
+      roots,
+      indices: HashMap::default(),
+      values: Vec::new(),
+    }
+  }
+
+  #[inline]
+  fn reference(&mut self, value: &Gc<dyn Value>) -> u32 {
+    let address = address(value);
+
+    if let Some(index) = self.indices.get(&address) {
+      *index
+    } else {
+      let index = self.values.len() as u32;
+      self.indices.insert(address, index);
+      self.values.push(value.clone());
+      index
+    }
+  }
+
+  #[inline]
+  fn references<'a, I>(&mut self, values: I) -> Vec<u32>
+  where
+    I: Iterator<Item = &'a Gc<dyn Value>>,
+  {
+    values.map(|value| self.reference(value)).collect()
+  }
+
+  #[inline]
+  fn entry(&mut self, value: &Gc<dyn Value>, is_root: bool) -> Result<Entry, String> {
+    if value.is::<Object<()>>() {
+      return Ok(Entry::Nil);
+    } else if let Some(kind) = value.downcast_ref::<Object<Kind>>() {
+      return Ok(Entry::Kind(kind.name().clone()));
+    } else if value.is::<Object<Stack>>() {
+      return Ok(Entry::Stack);
+    }
+
+    let data = if let Some(value) = value.downcast_ref::<Object<bool>>() {
+      Data::Bool(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<char>>() {
+      Data::Char(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<u8>>() {
+      Data::U8(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<u16>>() {
+      Data::U16(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<u32>>() {
+      Data::U32(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<u64>>() {
+      Data::U64(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<usize>>() {
+      Data::USize(*value.value() as u64)
+    } else if let Some(value) = value.downcast_ref::<Object<i8>>() {
+      Data::I8(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<i16>>() {
+      Data::I16(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<i32>>() {
+      Data::I32(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<i64>>() {
+      Data::I64(*value.value())
+    } else if let Some(value) = value.downcast_ref::<Object<isize>>() {
+      Data::ISize(*value.value() as i64)
+    } else if let Some(value) = value.downcast_ref::<Object<F32>>() {
+      Data::F32(value.value().0)
+    } else if let Some(value) = value.downcast_ref::<Object<F64>>() {
+      Data::F64(value.value().0)
+    } else if let Some(value) = value.downcast_ref::<Object<String>>() {
+      Data::String(value.value().clone())
+    } else if let Some(value) = value.downcast_ref::<Object<Symbol>>() {
+      Data::Symbol(value.value().deref().clone())
+    } else if let Some(value) = value.downcast_ref::<Object<Keyword>>() {
+      Data::Keyword(value.value().deref().clone())
+    } else if let Some(value) = value.downcast_ref::<Object<List>>() {
+      Data::List(self.references(value.iter()))
+    } else if let Some(value) = value.downcast_ref::<Object<Vector>>() {
+      Data::Vector(self.references(value.iter()))
+    } else if let Some(value) = value.downcast_ref::<Object<Set>>() {
+      Data::Set(self.references(value.iter()))
+    } else if let Some(value) = value.downcast_ref::<Object<Map>>() {
+      Data::Map(
+        value
+          .iter()
+          .map(|(key, value)| (self.reference(key), self.reference(value)))
+          .collect(),
+      )
+    } else if let Some(value) = value.downcast_ref::<Object<Atom>>() {
+      Data::Atom(self.reference(value.value().deref()))
+    } else if let Some(value) = value.downcast_ref::<Object<Escape>>() {
+      Data::Escape(self.reference(value.escape_value()))
+    } else if let Some(value) = value.downcast_ref::<Object<Tagged>>() {
+      Data::Tagged(
+        self.reference(&value.value().tag.clone().into_value()),
+        self.reference(&value.value().value),
+      )
+    } else if let Some(value) = value.downcast_ref::<Object<Scope>>() {
+      let parent = value
+        .parent
+        .as_ref()
+        .map(|parent| self.reference(&parent.clone().into_value()));
+      let mut bindings = value
+        .map
+        .read()
+        .iter()
+        .filter(|(name, _)| !is_root || !IMAGE_SKIPPED.contains(&name.as_str()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect::<Vec<_>>();
+
+      bindings.sort_by(|a, b| a.0.cmp(&b.0));
+
+      Data::Scope(
+        parent,
+        bindings
+          .iter()
+          .map(|(name, value)| (name.clone(), self.reference(value)))
+          .collect(),
+      )
+    } else if let Some(function) = value.downcast_ref::<Object<Function>>() {
+      match function.body {
+        FunctionKind::Internal(ref body) => Data::Function(
+          function
+            .name
+            .as_ref()
+            .map(|name| self.reference(&name.clone().into_value())),
+          self.reference(&function.scope.clone().into_value()),
+          self.reference(&function.params.clone().into_value()),
+          self.reference(body),
+        ),
+        FunctionKind::External(_) => {
+          return match function.name() {
+            Some(name) => match get_external_function_owner(&function.scope, function) {
+              Some(owner) => Ok(Entry::External(owner, name.value().deref().clone())),
+              None => Err(format!(
+                "failed to write image, external function {} is not registered",
+                name.value().deref()
+              )),
+            },
+            None => Err(
+              "failed to write image, anonymous external functions can not be re-linked"
+                .to_string(),
+            ),
+          };
+        }
+      }
+    } else if let Some(name) = self.roots.get(&address(value)) {
+      return Ok(Entry::Root(name.clone()));
+    } else {
+      return Err(format!(
+        "failed to write image, values of Kind {} can not be stored",
+        value.kind().name()
+      ));
+    };
+
+    Ok(Entry::Object {
+      kind: self.reference(&value.kind().into_value()),
+      meta: value.meta().map(|meta| self.reference(&meta.into_value())),
+      data,
+    })
+  }
+}
+
+#[inline]
+fn address(value: &Gc<dyn Value>) -> usize {
+  value.as_ptr() as *const u8 as usize
+}
+
+#[inline]
+fn restore_entries(
+  root: &Gc<Object<Scope>>,
+  mut guard: Gc<Object<Vector>>,
+  entries: &[Entry],
+) -> Result<(), String> {
+  let mut values = Vec::with_capacity(entries.len());
+
+  for entry in entries.iter() {
+    values.push(match entry {
+      &Entry::Nil => Some(nil_value(root).into_value()),
+      &Entry::Kind(ref name) => Some(
+        scope_get_with_kind::<Kind>(root, name)
+          .ok_or_else(|| format!("failed to read image, Kind {} is not defined", name))?
+          .into_value(),
+      ),
+      &Entry::Root(ref name) => Some(
+        scope_get(root, name)
+          .ok_or_else(|| format!("failed to read image, {} is not defined", name))?,
+      ),
+      &Entry::Stack => {
+        Some(new_object(root, Object::new(stack_kind(root), Stack::new())).into_value())
+      }
+      &Entry::External(ref owner, ref name) => Some(
+        get_external_function(root, owner, name)
+          .ok_or_else(|| {
+            format!(
+              "failed to read image, external function {} of {} is not registered",
+              name, owner
+            )
+          })?
+          .into_value(),
+      ),
+      &Entry::Object { .. } => None,
+    });
+  }
+
+  for (index, entry) in entries.iter().enumerate() {
+    if let &Entry::Object { kind, ref data, .. } = entry {
+      let kind = get_value(&values, kind)?
+        .downcast_ref::<Object<Kind>>()
+        .ok_or_else(|| "failed to read image, invalid Kind reference".to_string())?
+        .clone();
+      let value = if index == 0 {
+        root.clone().into_value()
+      } else {
+        new_shell(root, kind, data)
+      };
+
+      guard.push(value.clone());
+      values[index] = Some(value);
+    }
+  }
+
+  // hashed collections are filled last and children first so keys hash their final contents
+  for (index, entry) in entries.iter().enumerate() {
+    if let &Entry::Object { ref data, .. } = entry {
+      match data {
+        &Data::Set(_) | &Data::Map(_) => (),
+        _ => restore_entry(&values, index, entry)?,
+      }
+    }
+  }
+  for (index, entry) in entries.iter().enumerate().rev() {
+    if let &Entry::Object { ref data, .. } = entry {
+      match data {
+        &Data::Set(_) | &Data::Map(_) => restore_entry(&values, index, entry)?,
+        _ => (),
+      }
+    }
+  }
+
+  Ok(())
+}
+
+#[inline]
+fn new_shell(root: &Gc<Object<Scope>>, kind: Gc<Object<Kind>>, data: &Data) -> Gc<dyn Value> {
+  let nil = nil_value(root).into_value();
+
+  match data {
+    &Data::Bool(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::Char(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::U8(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::U16(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::U32(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::U64(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::USize(value) => new_object(root, Object::new(kind, value as usize)).into_value(),
+    &Data::I8(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::I16(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::I32(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::I64(value) => new_object(root, Object::new(kind, value)).into_value(),
+    &Data::ISize(value) => new_object(root, Object::new(kind, value as isize)).into_value(),
+    &Data::F32(value) => new_object(root, Object::new(kind, F32(value))).into_value(),
+    &Data::F64(value) => new_object(root, Object::new(kind, F64(value))).into_value(),
+    &Data::String(ref value) => new_object(root, Object::new(kind, value.clone())).into_value(),
+    &Data::Symbol(ref value) => {
+      new_object(root, Object::new(kind, Symbol::new(value.clone()))).into_value()
+    }
+    &Data::Keyword(ref value) => {
+      new_object(root, Object::new(kind, Keyword::new(value.clone()))).into_value()
+    }
+    &Data::List(_) => new_object(root, Object::new(kind, List::new())).into_value(),
+    &Data::Vector(_) => new_object(root, Object::new(kind, Vector::new())).into_value(),
+    &Data::Set(_) => new_object(root, Object::new(kind, Set::new())).into_value(),
+    &Data::Map(_) => new_object(root, Object::new(kind, Map::new())).into_value(),
+    &Data::Atom(_) => new_object(root, Object::new(kind, Atom::new(nil))).into_value(),
+    &Data::Escape(_) => new_object(root, Object::new(kind, Escape::new(nil))).into_value(),
+    &Data::Tagged(_, _) => new_object(
+      root,
+      Object::new(kind, Tagged::new(new_symbol(root, ""), nil)),
+    )
+    .into_value(),
+    &Data::Scope(_, _) => new_object(
+      root,
+      Object::new(kind, Scope::new(HashMap::default(), None)),
+    )
+    .into_value(),
+    &Data::Function(_, _, _, _) => new_object(
+      root,
+      Object::new(
+        kind,
+        Function::new(None, root.clone(), new_vector(root), nil),
+      ),
+    )
+    .into_value(),
+  }
+}
+
+macro_rules! object_mut {
+  ($value:ident, $type:ty, $meta:ident) => {{
+    let object = $value
+      .downcast_mut::<Object<$type>>()
+      .ok_or_else(|| "failed to read image, value changed Kind".to_string())?;
+    if $meta.is_some() {
+      object.meta = $meta;
+    }
+    object
+  }};
+}
+
+#[inline]
+fn restore_entry(
+  values: &[Option<Gc<dyn Value>>],
+  index: usize,
+  entry: &Entry,
+) -> Result<(), String> {
+  let (meta, data) = match entry {
+    &Entry::Object { meta, ref data, .. } => (meta, data),
+    _ => return Ok(()),
+  };
+  let meta = match meta {
+    Some(meta) => Some(
+      get_value(values, meta)?
+        .downcast_ref::<Object<Map>>()
+        .ok_or_else(|| "failed to read image, meta is not a Map".to_string())?
+        .clone(),
+    ),
+    None => None,
+  };
+  let mut value = get_value(values, index as u32)?;
+
+  match data {
+    &Data::Bool(_) => {
+      object_mut!(value, bool, meta);
+    }
+    &Data::Char(_) => {
+      object_mut!(value, char, meta);
+    }
+    &Data::U8(_) => {
+      object_mut!(value, u8, meta);
+    }
+    &Data::U16(_) => {
+      object_mut!(value, u16, meta);
+    }
+    &Data::U32(_) => {
+      object_mut!(value, u32, meta);
+    }
+    &Data::U64(_) => {
+      object_mut!(value, u64, meta);
+    }
+    &Data::USize(_) => {
+      object_mut!(value, usize, meta);
+    }
+    &Data::I8(_) => {
+      object_mut!(value, i8, meta);
+    }
+    &Data::I16(_) => {
+      object_mut!(value, i16, meta);
+    }
+    &Data::I32(_) => {
+      object_mut!(value, i32, meta);
+    }
+    &Data::I64(_) => {
+      object_mut!(value, i64, meta);
+    }
+    &Data::ISize(_) => {
+      object_mut!(value, isize, meta);
+    }
+    &Data::F32(_) => {
+      object_mut!(value, F32, meta);
+    }
+    &Data::F64(_) => {
+      object_mut!(value, F64, meta);
+    }
+    &Data::String(_) => {
+      object_mut!(value, String, meta);
+    }
+    &Data::Symbol(_) => {
+      object_mut!(value, Symbol, meta);
+    }
+    &Data::Keyword(_) => {
+      object_mut!(value, Keyword, meta);
+    }
+    &Data::List(ref indices) => {
+      let list = object_mut!(value, List, meta);
+      for index in indices.iter() {
+        list.push_back(get_value(values, *index)?);
+      }
+    }
+    &Data::Vector(ref indices) => {
+      let vector = object_mut!(value, Vector, meta);
+      for index in indices.iter() {
+        vector.push(get_value(values, *index)?);
+      }
+    }
+    &Data::Set(ref indices) => {
+      let set = object_mut!(value, Set, meta);
+      for index in indices.iter() {
+        set.add(get_value(values, *index)?);
+      }
+    }
+    &Data::Map(ref indices) => {
+      let map = object_mut!(value, Map, meta);
+      for &(key, value) in indices.iter() {
+        map.set(get_value(values, key)?, get_value(values, value)?);
+      }
+    }
+    &Data::Atom(index) => {
+      let atom = object_mut!(value, Atom, meta);
+      *atom.value_mut().deref_mut() = get_value(values, index)?;
+    }
+    &Data::Escape(index) => {
+      object_mut!(value, Escape, meta).0 = get_value(values, index)?;
+    }
+    &Data::Tagged(tag, index) => {
+      let tag = get_typed::<Symbol>(values, tag)?;
+      let tagged = object_mut!(value, Tagged, meta).value_mut();
+      tagged.tag = tag;
+      tagged.value = get_value(values, index)?;
+    }
+    &Data::Scope(parent, ref bindings) => {
+      let parent = match parent {
+        Some(parent) => Some(get_typed::<Scope>(values, parent)?),
+        None => None,
+      };
+      let scope = object_mut!(value, Scope, meta);
+
+      // the root scope of the image is restored into the running root, keeping its parent
+      if index != 0 {
+        scope.parent = parent;
+      }
+      for &(ref name, index) in bindings.iter() {
+        scope.set(name, get_value(values, index)?);
+      }
+    }
+    &Data::Function(name, scope, params, body) => {
+      let name = match name {
+        Some(name) => Some(get_typed::<Symbol>(values, name)?),
+        None => None,
+      };
+      let scope = get_typed::<Scope>(values, scope)?;
+      let params = get_typed::<Vector>(values, params)?;
+      let body = get_value(values, body)?;
+      let function = object_mut!(value, Function, meta);
+
+      function.name = name;
+      function.scope = scope;
+      function.params = params;
+      function.body = FunctionKind::new_internal(body);
+    }
+  }
+
+  Ok(())
+}
+
+#[inline]
+fn get_value(values: &[Option<Gc<dyn Value>>], index: u32) -> Result<Gc<dyn Value>, String> {
+  values
+    .get(index as usize)
+    .and_then(Clone::clone)
+    .ok_or_else(|| format!("failed to read image, invalid reference {}", index))
+}
+
+#[inline]
+fn get_typed<T>(values: &[Option<Gc<dyn Value>>], index: u32) -> Result<Gc<Object<T>>, String>
+where
+  T: 'static + PartialEq + PartialOrd + ::core::hash::Hash + ::core::fmt::Debug + gc::Trace,
+{
+  get_value(values, index)?
+    .downcast_ref::<Object<T>>()
+    .map(Clone::clone)
+    .ok_or_else(|| {
+      format!(
+        "failed to read image, reference {} has the wrong Kind",
+        index
+      )
+    })
+}
+
+impl Entry {
+  #[inline]
+  fn write(&self, bytes: &mut Vec<u8>) {
+    match self {
+      &Entry::Nil => bytes.push(TAG_NIL),
+      &Entry::Kind(ref name) => {
+        bytes.push(TAG_KIND);
+        write_str(bytes, name);
+      }
+      &Entry::Root(ref name) => {
+        bytes.push(TAG_ROOT);
+        write_str(bytes, name);
+      }
+      &Entry::Stack => bytes.push(TAG_STACK),
+      &Entry::External(ref owner, ref name) => {
+        bytes.push(TAG_EXTERNAL);
+        write_str(bytes, owner);
+        write_str(bytes, name);
+      }
+      &Entry::Object {
+        kind,
+        meta,
+        ref data,
+      } => {
+        bytes.push(data.tag());
+        write_u32(bytes, kind);
+        write_u32(bytes, meta.map(|meta| meta + 1).unwrap_or(0));
+        data.write(bytes);
+      }
+    }
+  }
+
+  #[inline]
+  fn read(reader: &mut ImageReader) -> Result<Self, String> {
+    let tag = reader.u8()?;
+
+    Ok(match tag {
+      TAG_NIL => Entry::Nil,
+      TAG_KIND => Entry::Kind(reader.string()?),
+      TAG_ROOT => Entry::Root(reader.string()?),
+      TAG_STACK => Entry::Stack,
+      TAG_EXTERNAL => Entry::External(reader.string()?, reader.string()?),
+      tag => {
+        let kind = reader.u32()?;
+        let meta = match reader.u32()? {
+          0 => None,
+          meta => Some(meta - 1),
+        };
+
+        Entry::Object {
+          kind,
+          meta,
+          data: Data::read(tag, reader)?,
+        }
+      }
+    })
+  }
+}
+
+impl Data {
+  #[inline]
+  fn tag(&self) -> u8 {
+    match self {
+      &Data::Bool(_) => TAG_BOOL,
+      &Data::Char(_) => TAG_CHAR,
+      &Data::U8(_) => TAG_U8,
+      &Data::U16(_) => TAG_U16,
+      &Data::U32(_) => TAG_U32,
+      &Data::U64(_) => TAG_U64,
+      &Data::USize(_) => TAG_USIZE,
+      &Data::I8(_) => TAG_I8,
+      &Data::I16(_) => TAG_I16,
+      &Data::I32(_) => TAG_I32,
+      &Data::I64(_) => TAG_I64,
+      &Data::ISize(_) => TAG_ISIZE,
+      &Data::F32(_) => TAG_F32,
+      &Data::F64(_) => TAG_F64,
+      &Data::String(_) => TAG_STRING,
+      &Data::Symbol(_) => TAG_SYMBOL,
+      &Data::Keyword(_) => TAG_KEYWORD,
+      &Data::List(_) => TAG_LIST,
+      &Data::Vector(_) => TAG_VECTOR,
+      &Data::Set(_) => TAG_SET,
+      &Data::Map(_) => TAG_MAP,
+      &Data::Atom(_) => TAG_ATOM,
+      &Data::Escape(_) => TAG_ESCAPE,
+      &Data::Tagged(_, _) => TAG_TAGGED,
+      &Data::Scope(_, _) => TAG_SCOPE,
+      &Data::Function(_, _, _, _) => TAG_FUNCTION,
+    }
+  }
+
+  #[inline]
+  fn write(&self, bytes: &mut Vec<u8>) {
+    match self {
+      &Data::Bool(value) => bytes.push(value as u8),
+      &Data::Char(value) => write_u32(bytes, value as u32),
+      &Data::U8(value) => bytes.push(value),
+      &Data::U16(value) => bytes.extend_from_slice(&value.to_le_bytes()),
+      &Data::U32(value) => write_u32(bytes, value),
+      &Data::U64(value) | &Data::USize(value) => write_u64(bytes, value),
+      &Data::I8(value) => bytes.push(value as u8),
+      &Data::I16(value) => bytes.extend_from_slice(&value.to_le_bytes()),
+      &Data::I32(value) => write_u32(bytes, value as u32),
+      &Data::I64(value) | &Data::ISize(value) => write_u64(bytes, value as u64),
+      &Data::F32(value) => write_u32(bytes, value.to_bits()),
+      &Data::F64(value) => write_u64(bytes, value.to_bits()),
+      &Data::String(ref value) | &Data::Symbol(ref value) | &Data::Keyword(ref value) => {
+        write_str(bytes, value)
+      }
+      &Data::List(ref indices) | &Data::Vector(ref indices) | &Data::Set(ref indices) => {
+        write_u32(bytes, indices.len() as u32);
+        for index in indices.iter() {
+          write_u32(bytes, *index);
+        }
+      }
+      &Data::Map(ref indices) => {
+        write_u32(bytes, indices.len() as u32);
+        for &(key, value) in indices.iter() {
+          write_u32(bytes, key);
+          write_u32(bytes, value);
+        }
+      }
+      &Data::Atom(index) | &Data::Escape(index) => write_u32(bytes, index),
+      &Data::Tagged(tag, index) => {
+        write_u32(bytes, tag);
+        write_u32(bytes, index);
+      }
+      &Data::Scope(parent, ref bindings) => {
+        write_u32(bytes, parent.map(|parent| parent + 1).unwrap_or(0));
+        write_u32(bytes, bindings.len() as u32);
+        for &(ref name, index) in bindings.iter() {
+          write_str(bytes, name);
+          write_u32(bytes, index);
+        }
+      }
+      &Data::Function(name, scope, params, body) => {
+        write_u32(bytes, name.map(|name| name + 1).unwrap_or(0));
+        write_u32(bytes, scope);
+        write_u32(bytes, params);
+        write_u32(bytes, body);
+      }
+    }
+  }
+
+  #[inline]
+  fn read(tag: u8, reader: &mut ImageReader) -> Result<Self, String> {
+    Ok(match tag {
+      TAG_BOOL => Data::Bool(reader.u8()? != 0),
+      TAG_CHAR => Data::Char(
+        ::core::char::from_u32(reader.u32()?)
+          .ok_or_else(|| "failed to read image, invalid char".to_string())?,
+      ),
+      TAG_U8 => Data::U8(reader.u8()?),
+      TAG_U16 => Data::U16(u16::from_le_bytes([reader.u8()?, reader.u8()?])),
+      TAG_U32 => Data::U32(reader.u32()?),
+      TAG_U64 => Data::U64(reader.u64()?),
+      TAG_USIZE => Data::USize(reader.u64()?),
+      TAG_I8 => Data::I8(reader.u8()? as i8),
+      TAG_I16 => Data::I16(i16::from_le_bytes([reader.u8()?, reader.u8()?])),
+      TAG_I32 => Data::I32(reader.u32()? as i32),
+      TAG_I64 => Data::I64(reader.u64()? as i64),
+      TAG_ISIZE => Data::ISize(reader.u64()? as i64),
+      TAG_F32 => Data::F32(f32::from_bits(reader.u32()?)),
+      TAG_F64 => Data::F64(f64::from_bits(reader.u64()?)),
+      TAG_STRING => Data::String(reader.string()?),
+      TAG_SYMBOL => Data::Symbol(reader.string()?),
+      TAG_KEYWORD => Data::Keyword(reader.string()?),
+      TAG_LIST => Data::List(reader.indices()?),
+      TAG_VECTOR => Data::Vector(reader.indices()?),
+      TAG_SET => Data::Set(reader.indices()?),
+      TAG_MAP => {
+        let len = reader.u32()?;
+        let mut indices = Vec::new();
+        for _ in 0..len {
+          indices.push((reader.u32()?, reader.u32()?));
+        }
+        Data::Map(indices)
+      }
+      TAG_ATOM => Data::Atom(reader.u32()?),
+      TAG_ESCAPE => Data::Escape(reader.u32()?),
+      TAG_TAGGED => Data::Tagged(reader.u32()?, reader.u32()?),
+      TAG_SCOPE => {
+        let parent = reader.optional_u32()?;
+        let len = reader.u32()?;
+        let mut bindings = Vec::new();
+        for _ in 0..len {
+          bindings.push((reader.string()?, reader.u32()?));
+        }
+        Data::Scope(parent, bindings)
+      }
+      TAG_FUNCTION => Data::Function(
+        reader.optional_u32()?,
+        reader.u32()?,
+        reader.u32()?,
+        reader.u32()?,
+      ),
+      tag => return Err(format!("failed to read image, unknown tag {}", tag)),
+    })
+  }
+}
+
+#[inline]
+fn parse_image(bytes: &[u8]) -> Result<Vec<Entry>, String> {
+  let mut reader = ImageReader { bytes, index: 0 };
+
+  if reader.take(IMAGE_MAGIC.len())? != IMAGE_MAGIC {
+    return Err("failed to read image, not an image file".to_string());
+  }
+
+  let version = reader.u32()?;
+  if version != IMAGE_VERSION {
+    return Err(format!(
+      "failed to read image, unsupported version {} expected {}",
+      version, IMAGE_VERSION
+    ));
+  }
+
+  let len = reader.u32()?;
+  let mut entries = Vec::new();
+
+  for _ in 0..len {
+    entries.push(Entry::read(&mut reader)?);
+  }
+
+  match entries.first() {
+    Some(&Entry::Object {
+      data: Data::Scope(_, _),
+      ..
+    }) => Ok(entries),
+    _ => Err("failed to read image, image does not start with a Scope".to_string()),
+  }
+}
+
+struct ImageReader<'a> {
+  bytes: &'a [u8],
+  index: usize,
+}
+
+impl<'a> ImageReader<'a> {
+  #[inline]
+  fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+    if self.index + len <= self.bytes.len() {
+      let bytes = &self.bytes[self.index..(self.index + len)];
+      self.index += len;
+      Ok(bytes)
+    } else {
+      Err("failed to read image, unexpected end of image".to_string())
+    }
+  }
+
+  #[inline]
+  fn u8(&mut self) -> Result<u8, String> {
+    Ok(self.take(1)?[0])
+  }
+  #[inline]
+  fn u32(&mut self) -> Result<u32, String> {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(self.take(4)?);
+    Ok(u32::from_le_bytes(bytes))
+  }
+  #[inline]
+  fn u64(&mut self) -> Result<u64, String> {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(self.take(8)?);
+    Ok(u64::from_le_bytes(bytes))
+  }
+  #[inline]
+  fn optional_u32(&mut self) -> Result<Option<u32>, String> {
+    Ok(match self.u32()? {
+      0 => None,
+      value => Some(value - 1),
+    })
+  }
+  #[inline]
+  fn string(&mut self) -> Result<String, String> {
+    let len = self.u32()? as usize;
+    String::from_utf8(self.take(len)?.to_vec())
+      .map_err(|_| "failed to read image, invalid utf-8 string".to_string())
+  }
+  #[inline]
+  fn indices(&mut self) -> Result<Vec<u32>, String> {
+    let len = self.u32()?;
+    let mut indices = Vec::new();
+    for _ in 0..len {
+      indices.push(self.u32()?);
+    }
+    Ok(indices)
+  }
+}
+
+#[inline]
+fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+  bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+#[inline]
+fn write_u64(bytes: &mut Vec<u8>, value: u64) {
+  bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+#[inline]
+fn write_str(bytes: &mut Vec<u8>, value: &str) {
+  write_u32(bytes, value.len() as u32);
+  bytes.extend_from_slice(value.as_bytes());
+}
@@ -0,0 +1,128 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use gc::Gc;
+use serde_json::{self, Error, Value as JsonValue};
+
+use super::{
+  add_external_function, from_argument, new_bool, new_f64, new_i64, new_keyword, new_map_from,
+  new_string, new_vector_from, nil_value, throw_error, Map, Object, Scope, Value, Vector,
+};
+
+#[inline]
+pub fn init_json_scope(scope: &Gc<Object<Scope>>) {
+  add_external_function(
+    scope,
+    "lisp_runtime",
+    "json.parse",
+    vec!["string", "options"],
+    json_parse,
+  );
+  add_external_function(
+    scope,
+    "lisp_runtime",
+    "json.stringify",
+    vec!["value", "options"],
+    json_stringify,
+  );
+}
+
+#[inline]
+pub fn json_parse(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let keywords = json_option(scope, args.get(1), "keywords");
+
+  match from_argument::<String>(scope, args, 0).and_then(|string| {
+    parse_json(scope, &string, keywords).map_err(|error| format!("failed to parse JSON: {}", error))
+  }) {
+    Ok(value) => value,
+    Err(error) => throw_error(scope, new_string(scope, error).into_value()),
+  }
+}
+
+#[inline]
+pub fn json_stringify(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let value = args
+    .get(0)
+    .map(Clone::clone)
+    .unwrap_or_else(|| nil_value(scope).clone().into_value());
+  let pretty = json_option(scope, args.get(1), "pretty");
+
+  match stringify_json(&value, pretty) {
+    Ok(string) => new_string(scope, string).into_value(),
+    Err(error) => throw_error(
+      scope,
+      new_string(scope, format!("failed to stringify JSON: {}", error)).into_value(),
+    ),
+  }
+}
+
+#[inline]
+pub fn parse_json(
+  scope: &Gc<Object<Scope>>,
+  string: &str,
+  keywords: bool,
+) -> Result<Gc<dyn Value>, Error> {
+  serde_json::from_str::<JsonValue>(string).map(|json| json_to_value(scope, &json, keywords))
+}
+
+#[inline]
+pub fn stringify_json(value: &Gc<dyn Value>, pretty: bool) -> Result<String, Error> {
+  if pretty {
+    serde_json::to_string_pretty(value)
+  } else {
+    serde_json::to_string(value)
+  }
+}
+
+#[inline]
+pub fn json_to_value(scope: &Gc<Object<Scope>>, json: &JsonValue, keywords: bool) -> Gc<dyn Value> {
+  match json {
+    &JsonValue::Null => nil_value(scope).into_value(),
+    &JsonValue::Bool(value) => new_bool(scope, value).into_value(),
+    &JsonValue::Number(ref number) => match number.as_i64() {
+      Some(value) => new_i64(scope, value).into_value(),
+      None => new_f64(scope, number.as_f64().unwrap_or(::core::f64::NAN)).into_value(),
+    },
+    &JsonValue::String(ref value) => new_string(scope, value).into_value(),
+    &JsonValue::Array(ref array) => new_vector_from(
+      scope,
+      array
+        .iter()
+        .map(|value| json_to_value(scope, value, keywords))
+        .collect::<Vec<Gc<dyn Value>>>()
+        .into(),
+    )
+    .into_value(),
+    &JsonValue::Object(ref object) => {
+      let mut map = Map::new();
+
+      for (key, value) in object.iter() {
+        let key = if keywords {
+          new_keyword(scope, key).into_value()
+        } else {
+          new_string(scope, key).into_value()
+        };
+        map.set(key, json_to_value(scope, value, keywords));
+      }
+
+      new_map_from(scope, map).into_value()
+    }
+  }
+}
+
+#[inline]
+fn json_option(scope: &Gc<Object<Scope>>, options: Option<&Gc<dyn Value>>, name: &str) -> bool {
+  options
+    .and_then(|options| options.downcast_ref::<Object<Map>>())
+    .and_then(|options| {
+      options
+        .get(&new_keyword(scope, name).into_value())
+        .map(Clone::clone)
+    })
+    .and_then(|value| {
+      value
+        .downcast_ref::<Object<bool>>()
+        .map(|value| *value.value())
+    })
+    .unwrap_or(false)
+}
@@ -6,9 +6,37 @@ use gc::{Gc, Trace};
 
 use super::{
   add_external_function, new_object, new_string, new_usize, nil_value, scope_get_with_kind,
-  LispMap, Object, Scope, Value, Vector,
+  scope_set, LispMap, Object, Scope, Value, Vector,
 };
 
+/// Rust types exposed to lisp as a Kind, usually implemented with
+/// `#[derive(LispKind)]`.
+pub trait LispKind: 'static + Sized + PartialEq + PartialOrd + Hash + fmt::Debug + Trace {
+  fn kind_name() -> &'static str;
+
+  #[inline]
+  fn init_kind(scope: &Gc<Object<Scope>>) {
+    let kind = new_kind::<Self>(scope, Self::kind_name());
+    scope_set(scope, Self::kind_name(), kind.into_value());
+  }
+
+  #[inline]
+  fn lisp_kind(scope: &Gc<Object<Scope>>) -> Gc<Object<Kind>> {
+    scope_get_with_kind::<Kind>(scope, Self::kind_name())
+      .unwrap_or_else(|| panic!("failed to get {} Kind", Self::kind_name()))
+  }
+
+  #[inline]
+  fn into_object(self, scope: &Gc<Object<Scope>>) -> Gc<Object<Self>> {
+    new_object(scope, Object::new(Self::lisp_kind(scope), self))
+  }
+}
+
+/// Builtins of a LispKind, usually implemented with `#[lisp_methods]`.
+pub trait LispMethods {
+  fn init_methods(scope: &Gc<Object<Scope>>);
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd)]
 pub struct Kind {
   name: String,
@@ -84,10 +112,16 @@ impl Kind {
 
   #[inline]
   pub(crate) fn init_scope(scope: &Gc<Object<Scope>>) {
-    add_external_function(scope, "kind.of", vec!["value"], kind_of);
-    add_external_function(scope, "kind.name", vec!["value"], kind_name);
-    add_external_function(scope, "kind.size", vec!["value"], kind_size);
-    add_external_function(scope, "kind.align", vec!["value"], kind_align);
+    add_external_function(scope, "lisp_runtime", "kind.of", vec!["value"], kind_of);
+    add_external_function(scope, "lisp_runtime", "kind.name", vec!["value"], kind_name);
+    add_external_function(scope, "lisp_runtime", "kind.size", vec!["value"], kind_size);
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "kind.align",
+      vec!["value"],
+      kind_align,
+    );
   }
 }
 
@@ -3,7 +3,8 @@
 #![feature(unboxed_closures)]
 #![feature(fn_traits)]
 
-extern crate core;
+#[doc(hidden)]
+pub extern crate core;
 
 #[macro_use]
 extern crate alloc;
@@ -12,17 +13,24 @@ extern crate num_traits;
 extern crate parking_lot;
 extern crate serde;
 extern crate serde_derive;
+extern crate serde_json;
 
-extern crate lisp_gc as gc;
+extern crate lisp_derive;
+pub extern crate lisp_gc as gc;
 
 mod atom;
 mod boolean;
 mod context;
+mod convert;
+mod deserialize;
+mod edn;
 mod escape;
 mod eval;
 mod function;
 mod function_kind;
 mod gc_allocator;
+mod image;
+mod json;
 mod keyword;
 mod kind;
 mod lisp_map;
@@ -32,6 +40,7 @@ mod numbers;
 mod object;
 mod reader;
 mod scope;
+mod serialize;
 mod set;
 mod special_form;
 mod stack;
@@ -39,14 +48,21 @@ mod symbol;
 mod value;
 mod vector;
 
+pub use lisp_derive::{lisp_methods, LispKind};
+
 pub use self::atom::*;
 pub use self::boolean::*;
 pub use self::context::*;
+pub use self::convert::*;
+pub use self::deserialize::*;
+pub use self::edn::*;
 pub use self::escape::*;
 pub use self::eval::*;
 pub use self::function::*;
 pub use self::function_kind::*;
 pub use self::gc_allocator::*;
+pub use self::image::*;
+pub use self::json::*;
 pub use self::keyword::*;
 pub use self::kind::*;
 pub use self::lisp_map::*;
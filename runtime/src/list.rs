@@ -4,7 +4,6 @@ use core::fmt::{self, Write};
 use core::hash::{Hash, Hasher};
 use core::iter::FromIterator;
 use core::ops::{Deref, DerefMut};
-use core::ptr;
 
 use gc::{Gc, Trace};
 
@@ -19,7 +18,7 @@ pub struct List(LinkedList<Gc<dyn Value>>);
 impl Hash for List {
   #[inline]
   fn hash<H: Hasher>(&self, state: &mut H) {
-    ptr::hash(self, state)
+    self.0.hash(state)
   }
 }
 
@@ -144,18 +143,38 @@ impl List {
 
   #[inline]
   pub(crate) fn init_scope(scope: &Gc<Object<Scope>>) {
-    add_external_function(scope, "list.is_empty", vec!["list"], list_is_empty);
-    add_external_function(scope, "list.len", vec!["list"], list_len);
-    add_external_function(scope, "list.nth", vec!["list", "index"], list_nth);
-    add_external_function(scope, "list.get", vec!["list", "index"], list_nth);
     add_external_function(
       scope,
+      "lisp_runtime",
+      "list.is_empty",
+      vec!["list"],
+      list_is_empty,
+    );
+    add_external_function(scope, "lisp_runtime", "list.len", vec!["list"], list_len);
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "list.nth",
+      vec!["list", "index"],
+      list_nth,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "list.get",
+      vec!["list", "index"],
+      list_nth,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
       "list.push_front",
       vec!["list", "...args"],
       list_push_front,
     );
     add_external_function(
       scope,
+      "lisp_runtime",
       "list.push_back",
       vec!["list", "...args"],
       list_push_back,
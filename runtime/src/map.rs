@@ -2,15 +2,14 @@ use core::cmp::Ordering;
 use core::fmt::{self, Write};
 use core::hash::{Hash, Hasher};
 use core::ops::{Deref, DerefMut};
-use core::ptr;
 
 use gc::{Gc, Trace};
 use hashbrown::hash_map::{IntoIter, Iter, IterMut};
 use hashbrown::HashMap;
 
 use super::{
-  add_external_function, new_bool, new_kind, new_object, new_usize, nil_value, scope_get_with_kind,
-  scope_set, Kind, Object, Scope, Value, Vector,
+  add_external_function, hash_unordered, new_bool, new_kind, new_object, new_usize, nil_value,
+  scope_get_with_kind, scope_set, Kind, Object, Scope, Value, Vector,
 };
 
 #[derive(Clone, PartialEq, Eq)]
@@ -18,8 +17,12 @@ pub struct Map(pub HashMap<Gc<dyn Value>, Gc<dyn Value>>);
 
 impl PartialOrd for Map {
   #[inline]
-  fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
-    None
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    if self == other {
+      Some(Ordering::Equal)
+    } else {
+      None
+    }
   }
 }
 
@@ -45,7 +48,8 @@ impl From<HashMap<Gc<dyn Value>, Gc<dyn Value>>> for Map {
 impl Hash for Map {
   #[inline]
   fn hash<H: Hasher>(&self, state: &mut H) {
-    ptr::hash(self, state)
+    self.0.len().hash(state);
+    hash_unordered(self.0.iter()).hash(state)
   }
 }
 
@@ -139,12 +143,42 @@ impl Map {
 
   #[inline]
   pub(crate) fn init_scope(scope: &Gc<Object<Scope>>) {
-    add_external_function(scope, "map.is_empty", vec!["map"], map_is_empty);
-    add_external_function(scope, "map.len", vec!["map"], map_len);
-    add_external_function(scope, "map.get", vec!["map", "key"], map_get);
-    add_external_function(scope, "map.remove", vec!["map", "key"], map_remove);
-    add_external_function(scope, "map.has", vec!["map", "key"], map_has);
-    add_external_function(scope, "map.set", vec!["map", "key", "value"], map_set);
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "map.is_empty",
+      vec!["map"],
+      map_is_empty,
+    );
+    add_external_function(scope, "lisp_runtime", "map.len", vec!["map"], map_len);
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "map.get",
+      vec!["map", "key"],
+      map_get,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "map.remove",
+      vec!["map", "key"],
+      map_remove,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "map.has",
+      vec!["map", "key"],
+      map_has,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "map.set",
+      vec!["map", "key", "value"],
+      map_set,
+    );
   }
 }
 
@@ -45,7 +45,7 @@ impl Reader {
     self.col
   }
   #[inline]
-  fn consume(&mut self) -> &mut Self {
+  pub(crate) fn consume(&mut self) -> &mut Self {
     if self.peek().map(is_newline).unwrap_or(false) {
       self.line += 1;
       self.col = 0;
@@ -56,17 +56,17 @@ impl Reader {
     self
   }
   #[inline]
-  fn next(&mut self) -> Option<char> {
+  pub(crate) fn next(&mut self) -> Option<char> {
     let index = self.index;
     self.consume();
     self.chars.get(index).map(Clone::clone)
   }
   #[inline]
-  fn peek(&self) -> Option<char> {
+  pub(crate) fn peek(&self) -> Option<char> {
     self.peek_nth(0)
   }
   #[inline]
-  fn peek_nth(&self, index: usize) -> Option<char> {
+  pub(crate) fn peek_nth(&self, index: usize) -> Option<char> {
     self.chars.get(self.index + index).map(Clone::clone)
   }
 }
@@ -184,6 +184,7 @@ fn read_map(scope: &Gc<Object<Scope>>, reader: &mut Reader) -> Gc<Object<Map>> {
           reader.consume();
         } else {
           value = read_value(scope, reader);
+          break;
         }
       }
 
@@ -8,11 +8,11 @@ use gc::{Gc, Trace};
 use hashbrown::HashMap;
 use parking_lot::RwLock;
 
-use super::{new_object, Kind, Object, Value};
+use super::{new_object, new_string, Kind, Map, Object, Value};
 
 pub struct Scope {
-  map: RwLock<HashMap<String, Gc<dyn Value>>>,
-  parent: Option<Gc<Object<Scope>>>,
+  pub(crate) map: RwLock<HashMap<String, Gc<dyn Value>>>,
+  pub(crate) parent: Option<Gc<Object<Scope>>>,
 }
 
 impl Default for Scope {
@@ -159,6 +159,24 @@ pub fn scope_get<'a>(scope: &'a Gc<Object<Scope>>, ident: &str) -> Option<Gc<dyn
   scope_get_by_value(scope, ident)
 }
 
+/// Looks up `ident`, falling back to `namespace/name` where `namespace` is
+/// bound to a Map, like the exports of `(import "path" :as namespace)`.
+#[inline]
+pub fn scope_get_qualified(scope: &Gc<Object<Scope>>, ident: &str) -> Option<Gc<dyn Value>> {
+  scope_get(scope, ident).or_else(|| {
+    let index = ident.find('/')?;
+    let (namespace, name) = (&ident[..index], &ident[(index + 1)..]);
+
+    if namespace.is_empty() || name.is_empty() {
+      return None;
+    }
+    scope_get(scope, namespace)?
+      .downcast_ref::<Object<Map>>()?
+      .get(&new_string(scope, name).into_value())
+      .map(Clone::clone)
+  })
+}
+
 #[inline]
 pub fn scope_set<'a>(scope: &'a Gc<Object<Scope>>, ident: &str, value: Gc<dyn Value>) {
   scope.set(ident, value);
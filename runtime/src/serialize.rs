@@ -0,0 +1,82 @@
+use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use super::{Keyword, List, Map, Object, Set, Symbol, Value, Vector, F32, F64};
+
+macro_rules! serialize_primitive {
+  ($value:ident, $serializer:ident, $($type:ty => $func:ident as $cast:ty),*) => {
+    $(
+      if let Some(value) = $value.downcast_ref::<Object<$type>>() {
+        return $serializer.$func(*value.value() as $cast);
+      }
+    )*
+  };
+}
+
+impl Serialize for dyn Value {
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    if self.is::<Object<()>>() {
+      return serializer.serialize_unit();
+    }
+
+    serialize_primitive!(
+      self, serializer,
+      bool => serialize_bool as bool,
+      char => serialize_char as char,
+      u8 => serialize_u8 as u8,
+      u16 => serialize_u16 as u16,
+      u32 => serialize_u32 as u32,
+      u64 => serialize_u64 as u64,
+      usize => serialize_u64 as u64,
+      i8 => serialize_i8 as i8,
+      i16 => serialize_i16 as i16,
+      i32 => serialize_i32 as i32,
+      i64 => serialize_i64 as i64,
+      isize => serialize_i64 as i64
+    );
+
+    if let Some(value) = self.downcast_ref::<Object<F32>>() {
+      serializer.serialize_f32(value.value().0)
+    } else if let Some(value) = self.downcast_ref::<Object<F64>>() {
+      serializer.serialize_f64(value.value().0)
+    } else if let Some(value) = self.downcast_ref::<Object<String>>() {
+      serializer.serialize_str(value.value())
+    } else if let Some(value) = self.downcast_ref::<Object<Keyword>>() {
+      serializer.serialize_str(value.value())
+    } else if let Some(value) = self.downcast_ref::<Object<Symbol>>() {
+      serializer.serialize_str(value.value())
+    } else if let Some(value) = self.downcast_ref::<Object<List>>() {
+      let mut seq = serializer.serialize_seq(Some(value.len()))?;
+      for element in value.iter() {
+        seq.serialize_element(element)?;
+      }
+      seq.end()
+    } else if let Some(value) = self.downcast_ref::<Object<Vector>>() {
+      let mut seq = serializer.serialize_seq(Some(value.len()))?;
+      for element in value.iter() {
+        seq.serialize_element(element)?;
+      }
+      seq.end()
+    } else if let Some(value) = self.downcast_ref::<Object<Set>>() {
+      let mut seq = serializer.serialize_seq(Some(value.len()))?;
+      for element in value.iter() {
+        seq.serialize_element(element)?;
+      }
+      seq.end()
+    } else if let Some(value) = self.downcast_ref::<Object<Map>>() {
+      let mut map = serializer.serialize_map(Some(value.len()))?;
+      for (key, value) in value.iter() {
+        map.serialize_entry(key, value)?;
+      }
+      map.end()
+    } else {
+      Err(S::Error::custom(format!(
+        "failed to serialize value of Kind {}, only data values are serializable",
+        self.kind().name()
+      )))
+    }
+  }
+}
@@ -2,15 +2,14 @@ use core::cmp::Ordering;
 use core::fmt::{self, Write};
 use core::hash::{Hash, Hasher};
 use core::ops::{Deref, DerefMut};
-use core::ptr;
 
 use gc::{Gc, Trace};
 use hashbrown::hash_set::{IntoIter, Iter};
 use hashbrown::HashSet;
 
 use super::{
-  add_external_function, new_bool, new_kind, new_object, new_usize, nil_value, scope_get_with_kind,
-  scope_set, Kind, Map, Object, Scope, Value, Vector,
+  add_external_function, hash_unordered, new_bool, new_kind, new_object, new_usize, nil_value,
+  scope_get_with_kind, scope_set, Kind, Map, Object, Scope, Value, Vector,
 };
 
 #[derive(Clone, PartialEq, Eq)]
@@ -18,8 +17,12 @@ pub struct Set(HashSet<Gc<dyn Value>>);
 
 impl PartialOrd for Set {
   #[inline]
-  fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
-    None
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    if self == other {
+      Some(Ordering::Equal)
+    } else {
+      None
+    }
   }
 }
 
@@ -37,7 +40,8 @@ impl Trace for Set {
 impl Hash for Set {
   #[inline]
   fn hash<H: Hasher>(&self, state: &mut H) {
-    ptr::hash(self, state)
+    self.0.len().hash(state);
+    hash_unordered(self.0.iter()).hash(state)
   }
 }
 
@@ -130,12 +134,42 @@ impl Set {
 
   #[inline]
   pub(crate) fn init_scope(scope: &Gc<Object<Scope>>) {
-    add_external_function(scope, "set.is_empty", vec!["set"], set_is_empty);
-    add_external_function(scope, "set.len", vec!["set"], set_len);
-    add_external_function(scope, "set.get", vec!["set", "key"], set_get);
-    add_external_function(scope, "set.remove", vec!["set", "key"], set_remove);
-    add_external_function(scope, "set.has", vec!["set", "key"], set_has);
-    add_external_function(scope, "set.add", vec!["set", "value"], set_add)
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "set.is_empty",
+      vec!["set"],
+      set_is_empty,
+    );
+    add_external_function(scope, "lisp_runtime", "set.len", vec!["set"], set_len);
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "set.get",
+      vec!["set", "key"],
+      set_get,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "set.remove",
+      vec!["set", "key"],
+      set_remove,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "set.has",
+      vec!["set", "key"],
+      set_has,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "set.add",
+      vec!["set", "value"],
+      set_add,
+    )
   }
 }
 
@@ -40,6 +40,8 @@ pub struct Stack {
   pub(crate) scope: LinkedList<Gc<Object<Scope>>>,
   pub(crate) callable: LinkedList<Gc<Object<Function>>>,
   pub(crate) state: LinkedList<EvalState>,
+  pub(crate) nested: LinkedList<(usize, usize, usize)>,
+  pub(crate) error: Option<Gc<dyn Value>>,
 }
 
 impl fmt::Debug for Stack {
@@ -61,6 +63,9 @@ impl Trace for Stack {
     for v in self.callable.iter_mut() {
       v.trace(marked);
     }
+    if let Some(v) = self.error.as_mut() {
+      v.trace(marked);
+    }
   }
 }
 
@@ -93,6 +98,8 @@ impl Stack {
       scope: LinkedList::new(),
       callable: LinkedList::new(),
       state: LinkedList::new(),
+      nested: LinkedList::new(),
+      error: None,
     }
   }
 
@@ -109,6 +116,7 @@ impl Stack {
           );
         }
         Some(EvalState::PopScope) => {
+          self.scope.pop_front().expect("no scope was in the stack");
           let callable = self
             .callable
             .pop_front()
@@ -123,6 +131,40 @@ impl Stack {
     }
   }
 
+  /// Ends the innermost nested evaluation with an uncaught `error`, the
+  /// evaluation returns nil and the external function that started it throws
+  /// `error` into the outer states once it returns. Returns false when there
+  /// is no outer evaluation to throw into.
+  #[inline]
+  pub(crate) fn throw_nested(
+    &mut self,
+    scope: Gc<Object<Scope>>,
+    nil: Gc<dyn Value>,
+    error: Gc<dyn Value>,
+  ) -> bool {
+    let (values, scopes, callables) = match self.nested.front() {
+      Some(&lengths) if self.nested.len() > 1 => lengths,
+      _ => return false,
+    };
+
+    while self.value.len() > values {
+      self.value.pop_front();
+    }
+    while self.scope.len() > scopes {
+      self.scope.pop_front();
+    }
+    while self.callable.len() > callables {
+      self.callable.pop_front();
+    }
+    self.push_scope_and_value(scope, nil);
+    self.state.clear();
+
+    if self.error.is_none() {
+      self.error = Some(error);
+    }
+    true
+  }
+
   #[inline]
   pub(crate) fn pop_scope_and_value(&mut self) -> Option<Gc<dyn Value>> {
     self.scope.pop_front();
@@ -166,6 +208,14 @@ pub fn stack_kind(scope: &Gc<Object<Scope>>) -> Gc<Object<Kind>> {
   scope_get_with_kind::<Kind>(scope, "Stack").expect("failed to get Stack Kind")
 }
 
+/// Returns true if a nested evaluation ended with an error that has not been
+/// thrown into the outer evaluation yet, external functions should stop and
+/// return so it can be thrown.
+#[inline]
+pub fn is_error_pending(scope: &Gc<Object<Scope>>) -> bool {
+  get_stack(scope).error.is_some()
+}
+
 pub fn get_stack(scope: &Gc<Object<Scope>>) -> Gc<Object<Stack>> {
   scope_get(scope, "__stack")
     .unwrap()
@@ -3,7 +3,7 @@ use core::cmp::Ordering;
 use core::fmt;
 use core::hash::{Hash, Hasher};
 
-use gc::Gc;
+use gc::{Gc, Trace};
 
 use super::{
   add_external_function, false_value, new_bool, nil_value, Kind, Map, Object, Scope, Vector,
@@ -20,6 +20,21 @@ pub trait Value: Any {
   fn mark(&mut self, marked: bool);
 }
 
+impl Trace for dyn Value {
+  #[inline(always)]
+  fn is_marked(&self) -> bool {
+    Value::is_marked(self)
+  }
+  #[inline(always)]
+  fn trace(&mut self, marked: bool) {
+    Value::trace(self, marked)
+  }
+  #[inline(always)]
+  fn mark(&mut self, marked: bool) {
+    Value::mark(self, marked)
+  }
+}
+
 impl dyn Value {
   #[inline]
   pub fn is<T: Value>(&self) -> bool {
@@ -54,13 +69,13 @@ impl dyn Value {
 
   #[inline]
   pub(crate) fn init_scope(scope: &Gc<Object<Scope>>) {
-    add_external_function(scope, "=", vec!["a", "b"], value_eq);
-    add_external_function(scope, "!=", vec!["a", "b"], value_ne);
-    add_external_function(scope, ">", vec!["a", "b"], value_gt);
-    add_external_function(scope, ">=", vec!["a", "b"], value_ge);
-    add_external_function(scope, "<", vec!["a", "b"], value_lt);
-    add_external_function(scope, "<=", vec!["a", "b"], value_le);
-    add_external_function(scope, "!", vec!["a", "b"], value_not);
+    add_external_function(scope, "lisp_runtime", "=", vec!["a", "b"], value_eq);
+    add_external_function(scope, "lisp_runtime", "!=", vec!["a", "b"], value_ne);
+    add_external_function(scope, "lisp_runtime", ">", vec!["a", "b"], value_gt);
+    add_external_function(scope, "lisp_runtime", ">=", vec!["a", "b"], value_ge);
+    add_external_function(scope, "lisp_runtime", "<", vec!["a", "b"], value_lt);
+    add_external_function(scope, "lisp_runtime", "<=", vec!["a", "b"], value_le);
+    add_external_function(scope, "lisp_runtime", "!", vec!["a", "b"], value_not);
   }
 }
 
@@ -194,3 +209,41 @@ impl fmt::Debug for dyn Value {
     self.debug(f)
   }
 }
+
+/// Combines the hashes of `values` so that their iteration order does not
+/// change the result, used by the unordered collections.
+#[inline]
+pub(crate) fn hash_unordered<I, T>(values: I) -> u64
+where
+  I: IntoIterator<Item = T>,
+  T: Hash,
+{
+  values.into_iter().fold(0, |hash, value| {
+    let mut hasher = FnvHasher::default();
+    value.hash(&mut hasher);
+    hash.wrapping_add(hasher.finish())
+  })
+}
+
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+  #[inline(always)]
+  fn default() -> Self {
+    FnvHasher(0xcbf2_9ce4_8422_2325)
+  }
+}
+
+impl Hasher for FnvHasher {
+  #[inline(always)]
+  fn finish(&self) -> u64 {
+    self.0
+  }
+
+  #[inline]
+  fn write(&mut self, bytes: &[u8]) {
+    for byte in bytes {
+      self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+    }
+  }
+}
@@ -3,7 +3,6 @@ use core::fmt;
 use core::hash::{Hash, Hasher};
 use core::iter::FromIterator;
 use core::ops::{Deref, DerefMut};
-use core::ptr;
 use core::slice::{Iter, IterMut};
 
 use gc::{Gc, Trace};
@@ -41,7 +40,7 @@ impl fmt::Debug for Vector {
 impl Hash for Vector {
   #[inline]
   fn hash<H: Hasher>(&self, state: &mut H) {
-    ptr::hash(self, state)
+    self.0.hash(state)
   }
 }
 
@@ -170,24 +169,51 @@ impl Vector {
 
   #[inline]
   pub(crate) fn init_scope(scope: &Gc<Object<Scope>>) {
-    add_external_function(scope, "vector.is_empty", vec!["vector"], vector_is_empty);
-    add_external_function(scope, "vector.len", vec!["vector"], vector_len);
-    add_external_function(scope, "vector.nth", vec!["vector", "index"], vector_nth);
-    add_external_function(scope, "vector.get", vec!["vector", "index"], vector_nth);
     add_external_function(
       scope,
+      "lisp_runtime",
+      "vector.is_empty",
+      vec!["vector"],
+      vector_is_empty,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "vector.len",
+      vec!["vector"],
+      vector_len,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "vector.nth",
+      vec!["vector", "index"],
+      vector_nth,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
+      "vector.get",
+      vec!["vector", "index"],
+      vector_nth,
+    );
+    add_external_function(
+      scope,
+      "lisp_runtime",
       "vector.push_front",
       vec!["vector", "...args"],
       vector_push_front,
     );
     add_external_function(
       scope,
+      "lisp_runtime",
       "vector.push_back",
       vec!["vector", "...args"],
       vector_push_back,
     );
     add_external_function(
       scope,
+      "lisp_runtime",
       "vector.insert",
       vec!["vector", "index", "value"],
       vector_insert,
@@ -0,0 +1,81 @@
+extern crate lisp_gc;
+extern crate lisp_runtime;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use lisp_gc::{Gc, Trace};
+use lisp_runtime::{
+  add_external_closure, new_context, new_external_closure, new_keyword, new_symbol, new_vector,
+  run_in_scope, write_image, FromValue, Object, Scope, Vector,
+};
+
+fn counter_scope() -> (Gc<Object<Scope>>, Gc<Object<Vector>>, Rc<Cell<usize>>) {
+  let scope = new_context();
+  let calls = Rc::new(Cell::new(0));
+  let mut names = new_vector(&scope);
+  names.push(new_keyword(&scope, "a").into_value());
+  names.push(new_keyword(&scope, "b").into_value());
+
+  let host_calls = calls.clone();
+  add_external_closure(
+    &scope,
+    "names",
+    vec!["index"],
+    names.clone(),
+    move |names: &Gc<Object<Vector>>, scope, args| {
+      host_calls.set(host_calls.get() + 1);
+      let index = usize::from_value(scope, &args.get(0).expect("index is required")).unwrap();
+      names.get(index).expect("index out of range").clone()
+    },
+  );
+
+  (scope, names, calls)
+}
+
+#[test]
+fn test_closure_captures_host_state() {
+  let (scope, _, calls) = counter_scope();
+
+  let value = run_in_scope(&scope, "(kind.name (names 1))");
+  assert_eq!(String::from_value(&scope, &value), Ok("Keyword".to_owned()));
+  run_in_scope(&scope, "(names 0)");
+  assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn test_closure_traces_captured_values() {
+  let (scope, names, _) = counter_scope();
+
+  let mut function = run_in_scope(&scope, "names");
+  Trace::trace(&mut function, true);
+  assert!(names.is_marked());
+
+  run_in_scope(&scope, "(gc_allocator.collect)");
+  assert_eq!(
+    run_in_scope(&scope, "(names 1)"),
+    new_keyword(&scope, "b").into_value()
+  );
+}
+
+#[test]
+fn test_closures_are_not_pinned() {
+  let (scope, _, _) = counter_scope();
+  let error = write_image(&scope).expect_err("wrote an image with a closure");
+  assert!(error.contains("names is not registered"), "{}", error);
+
+  let closure = new_external_closure(
+    &scope,
+    Some(new_symbol(&scope, "names")),
+    new_vector(&scope),
+    new_vector(&scope),
+    |_: &Gc<Object<Vector>>, scope, _args| new_vector(scope).into_value(),
+  );
+
+  // a closure nothing refers to is not reachable from the context
+  let mut root = scope.clone();
+  root.trace(true);
+  let reachable = closure.as_ref().is_marked();
+  root.trace(false);
+  assert!(!reachable);
+}
@@ -0,0 +1,113 @@
+extern crate hashbrown;
+extern crate lisp_gc;
+extern crate lisp_runtime;
+
+use std::collections::HashMap as StdHashMap;
+
+use hashbrown::HashMap;
+use lisp_gc::Gc;
+use lisp_runtime::{
+  add_typed_function, new_context, new_string, run_in_scope, FromValue, IntoValue, Object, Scope,
+};
+
+fn caught(scope: &Gc<Object<Scope>>, expr: &str) -> String {
+  let value = run_in_scope(
+    scope,
+    format!("(try {} (fn [error] (map.get error :value)))", expr),
+  );
+  String::from_value(scope, &value).expect("expected a caught error string")
+}
+
+fn range(start: i64, end: i64) -> Result<Vec<i64>, String> {
+  if start <= end {
+    Ok((start..end).collect())
+  } else {
+    Err(format!("invalid range {}..{}", start, end))
+  }
+}
+
+#[test]
+fn test_round_trip_conversions() {
+  let scope = new_context();
+
+  let mut map = HashMap::new();
+  map.insert("a".to_owned(), vec![Some(1_i64), None]);
+  let value = map.clone().into_value(&scope);
+  assert_eq!(
+    HashMap::<String, Vec<Option<i64>>>::from_value(&scope, &value),
+    Ok(map)
+  );
+
+  let mut std_map = StdHashMap::new();
+  std_map.insert("b".to_owned(), 2_u32);
+  let value = std_map.clone().into_value(&scope);
+  assert_eq!(
+    StdHashMap::<String, u32>::from_value(&scope, &value),
+    Ok(std_map)
+  );
+
+  let tuple = (1_u8, 'c', "text".to_owned(), 0.5_f64, true);
+  let value = tuple.clone().into_value(&scope);
+  assert_eq!(
+    <(u8, char, String, f64, bool)>::from_value(&scope, &value),
+    Ok(tuple)
+  );
+
+  let value = new_string(&scope, "text").into_value();
+  assert_eq!(
+    i64::from_value(&scope, &value),
+    Err("expected I64, found String".to_owned())
+  );
+  let value = 300_isize.into_value(&scope);
+  assert!(u8::from_value(&scope, &value).is_err());
+  assert_eq!(u16::from_value(&scope, &value), Ok(300));
+}
+
+#[test]
+fn test_typed_function() {
+  let scope = new_context();
+  add_typed_function(&scope, "host", "range", vec!["start", "end"], range);
+
+  let value = run_in_scope(&scope, "(range 1 4)");
+  assert_eq!(Vec::<i64>::from_value(&scope, &value), Ok(vec![1, 2, 3]));
+}
+
+#[test]
+fn test_typed_function_errors_are_catchable() {
+  let scope = new_context();
+  add_typed_function(&scope, "host", "range", vec!["start", "end"], range);
+
+  assert_eq!(caught(&scope, "(range 4 1)"), "invalid range 4..1");
+  assert_eq!(
+    caught(&scope, "(range \"a\" 1)"),
+    "invalid argument 0: expected I64, found String"
+  );
+  assert_eq!(
+    caught(&scope, "(range 1 2 3)"),
+    "expected 2 arguments, found 3"
+  );
+  assert_eq!(
+    caught(&scope, "(u8.add 1_u8 \"b\")"),
+    "invalid argument 1: expected U8, found String"
+  );
+}
+
+#[test]
+fn test_builtin_errors_are_catchable() {
+  let scope = new_context();
+
+  assert!(caught(&scope, "(json.parse \"[1,\")").starts_with("failed to parse JSON"));
+  assert!(caught(&scope, "(edn.read \"(1\")").starts_with("failed to read EDN"));
+  assert_eq!(
+    caught(&scope, "(json.parse 1)"),
+    "invalid argument 0: expected String, found ISize"
+  );
+}
+
+#[test]
+fn test_try_returns_the_block_value() {
+  let scope = new_context();
+
+  let value = run_in_scope(&scope, "(try (isize.add 1 2) (fn [error] 0))");
+  assert_eq!(isize::from_value(&scope, &value), Ok(3));
+}
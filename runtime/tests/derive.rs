@@ -0,0 +1,92 @@
+extern crate lisp_gc;
+extern crate lisp_runtime;
+
+use lisp_gc::Gc;
+use lisp_runtime::{
+  lisp_methods, new_context, run_in_scope, FromValue, LispKind, LispMethods, Object, Scope, Value,
+};
+
+#[derive(Debug, LispKind)]
+struct Counter {
+  count: i64,
+  label: Gc<dyn Value>,
+}
+
+#[lisp_methods]
+impl Counter {
+  pub fn new(scope: &Gc<Object<Scope>>, label: Gc<dyn Value>) -> Gc<Object<Counter>> {
+    Counter { count: 0, label }.into_object(scope)
+  }
+
+  pub fn inc(&mut self, by: i64) -> Result<i64, String> {
+    if by < 0 {
+      Err(format!("can not increment by {}", by))
+    } else {
+      self.count += by;
+      Ok(self.count)
+    }
+  }
+
+  #[lisp(name = "label")]
+  pub fn get_label(&self) -> Gc<dyn Value> {
+    self.label.clone()
+  }
+
+  #[lisp(skip)]
+  pub fn count(&self) -> i64 {
+    self.count
+  }
+}
+
+fn counter_scope() -> Gc<Object<Scope>> {
+  let scope = new_context();
+  Counter::init_kind(&scope);
+  Counter::init_methods(&scope);
+  scope
+}
+
+#[test]
+fn test_kind_and_methods() {
+  let scope = counter_scope();
+  let value = run_in_scope(
+    &scope,
+    "(def c (counter.new :clicks)) (counter.inc c 2) (counter.inc c 3)",
+  );
+  assert_eq!(i64::from_value(&scope, &value), Ok(5));
+
+  let counter = run_in_scope(&scope, "c");
+  assert_eq!(counter.kind(), Counter::lisp_kind(&scope));
+  assert_eq!(counter.kind().name(), "Counter");
+  let counter = Gc::<Object<Counter>>::from_value(&scope, &counter).unwrap();
+  assert_eq!(counter.count(), 5);
+
+  let label = run_in_scope(&scope, "(kind.name (counter.label c))");
+  assert_eq!(String::from_value(&scope, &label), Ok("Keyword".to_owned()));
+  assert_eq!(
+    run_in_scope(&scope, "counter.count"),
+    run_in_scope(&scope, "nil")
+  );
+}
+
+#[test]
+fn test_method_errors_are_catchable() {
+  let scope = counter_scope();
+  let catch = |expr: &str| {
+    let value = run_in_scope(
+      &scope,
+      format!("(try {} (fn [error] (map.get error :value)))", expr),
+    );
+    String::from_value(&scope, &value).unwrap()
+  };
+
+  run_in_scope(&scope, "(def c (counter.new nil))");
+  assert_eq!(catch("(counter.inc c -1)"), "can not increment by -1");
+  assert_eq!(
+    catch("(counter.inc 1 1)"),
+    "invalid argument 0: expected Counter, found ISize"
+  );
+  assert_eq!(
+    catch("(counter.inc c 1 2)"),
+    "expected 2 arguments, found 3"
+  );
+}
@@ -0,0 +1,243 @@
+extern crate lisp_runtime;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use lisp_runtime::{
+  new_context, new_string, read_edn, run_in_scope, scope_set, write_edn, FromValue, IntoValue,
+};
+
+fn round_trip(input: &str) {
+  let scope = new_context();
+  let value =
+    read_edn(&scope, input).unwrap_or_else(|error| panic!("failed to read {}: {}", input, error));
+  let string =
+    write_edn(&value).unwrap_or_else(|error| panic!("failed to write {}: {}", input, error));
+  let actual = read_edn(&scope, &string)
+    .unwrap_or_else(|error| panic!("failed to read back {}: {}", string, error));
+
+  assert_eq!(
+    value, actual,
+    "round trip of {} through {} changed the value",
+    input, string
+  );
+}
+
+#[test]
+fn test_round_trip_scalars() {
+  for input in &[
+    "nil",
+    "true",
+    "false",
+    "42",
+    "-7",
+    "+3N",
+    "0.5",
+    "-1.5e10",
+    "2M",
+    "##Inf",
+    "##-Inf",
+    "\\a",
+    "\\space",
+    "\\newline",
+    "\\u0000",
+    "\"\"",
+    "\"tab\\t quote\\\" slash\\\\ newline\\n \\u00e9\"",
+    ":keyword",
+    ":ns/keyword",
+    "symbol",
+    "ns/symbol",
+  ] {
+    round_trip(input);
+  }
+}
+
+#[test]
+fn test_number_kinds_write_plain_integers() {
+  let scope = new_context();
+
+  for &(input, output) in &[
+    ("#u8 255", "255"),
+    ("#u16 65535", "65535"),
+    ("#u32 4294967295", "4294967295"),
+    ("#u64 18446744073709551615", "18446744073709551615"),
+    ("#usize 1", "1"),
+    ("#i8 -128", "-128"),
+    ("#i16 -32768", "-32768"),
+    ("#i32 -2147483648", "-2147483648"),
+    ("#i64 -9223372036854775808", "-9223372036854775808"),
+    ("#f32 1.5", "#f32 1.5"),
+  ] {
+    let value = read_edn(&scope, input).unwrap();
+    let string = write_edn(&value).unwrap();
+
+    assert_eq!(string, output, "{}", input);
+    assert_eq!(
+      write_edn(&read_edn(&scope, &string).unwrap()).unwrap(),
+      output,
+      "{}",
+      input
+    );
+  }
+}
+
+#[test]
+fn test_round_trip_lisp_values() {
+  let scope = new_context();
+
+  // json.parse makes i64 integers, which are written as plain EDN integers
+  let value = run_in_scope(
+    &scope,
+    "(edn.write (json.parse \"[1, -2, 2.5, true, null]\"))",
+  );
+  assert_eq!(
+    String::from_value(&scope, &value),
+    Ok("[1 -2 2.5 true nil]".to_owned())
+  );
+
+  for &(source, expected) in &[
+    (
+      "{:a [1 \"two\" 'c'] :b {:c nil}}",
+      "{:a [1 \"two\" \\c] :b {:c nil}}",
+    ),
+    (
+      "[(isize.sub 0 5) (json.parse \"-9223372036854775808\") (json.parse \"1.5\")]",
+      "[-5 -9223372036854775808 1.5]",
+    ),
+    ("(json.parse \"[[1, 2], {}, null]\")", "[[1 2] {} nil]"),
+  ] {
+    let value = run_in_scope(&scope, source);
+    let string = write_edn(&value).unwrap();
+
+    scope_set(&scope, "written", new_string(&scope, &string).into_value());
+    let actual = run_in_scope(&scope, "(edn.read written)");
+
+    assert_eq!(
+      actual,
+      read_edn(&scope, expected).unwrap(),
+      "round trip of {} through {}",
+      source,
+      string
+    );
+  }
+}
+
+#[test]
+fn test_round_trip_collections() {
+  for input in &[
+    "()",
+    "[]",
+    "{}",
+    "#{}",
+    "(1 (2 [3 #{4}]))",
+    "[1, 2, 3]",
+    "{:a 1, \"b\" [2 3], [4] {:c #{:d}}}",
+    "#{1 \"two\" :three [4]}",
+  ] {
+    round_trip(input);
+  }
+}
+
+#[test]
+fn test_round_trip_tagged() {
+  for input in &[
+    "#inst \"1985-04-12T23:20:50.52Z\"",
+    "#inst \"2020-01-01\"",
+    "#uuid \"f81d4fae-7dec-11d0-a765-00a0c91e6bf6\"",
+    "#my/tag {:a [1 2]}",
+  ] {
+    round_trip(input);
+  }
+}
+
+#[test]
+fn test_comments_and_discard() {
+  let scope = new_context();
+  let value = read_edn(&scope, "; comment\n[1 #_ 2 3 #_ #_ 4 5] ; trailing").unwrap();
+
+  assert_eq!(write_edn(&value).unwrap(), "[1 3]");
+}
+
+#[test]
+fn test_read_errors() {
+  let scope = new_context();
+
+  for input in &[
+    "[1 2",
+    "{:a}",
+    "\"unterminated",
+    ")",
+    "#inst \"yesterday\"",
+    "#uuid \"not-a-uuid\"",
+    "#u8 256",
+    "1 2",
+    "{:a 1} }",
+    "[1] ; comment\n[2]",
+  ] {
+    assert!(
+      read_edn(&scope, input).is_err(),
+      "expected {} to fail",
+      input
+    );
+  }
+
+  let error = read_edn(&scope, "[1\n  {:a}]").err().unwrap();
+  assert!(error.contains("line 2"), "{}", error);
+}
+
+#[test]
+fn test_trailing_whitespace_and_discard() {
+  let scope = new_context();
+  let value = read_edn(&scope, "  [1 2] , #_ 3 ; done\n").unwrap();
+
+  assert_eq!(write_edn(&value).unwrap(), "[1 2]");
+}
+
+#[test]
+fn test_register_tag() {
+  let scope = new_context();
+
+  run_in_scope(
+    &scope,
+    "(edn.register_tag :my/point (fn [point] {:point point}))",
+  );
+  let value = run_in_scope(&scope, "(edn.read \"#my/point [1 2]\")");
+
+  assert_eq!(
+    value,
+    run_in_scope(&scope, "(edn.read \"{:point [1 2]}\")"),
+    "the tag handler was not called for {:?}",
+    value
+  );
+}
+
+#[test]
+fn test_try_catches_tag_handler_errors() {
+  let scope = new_context();
+
+  run_in_scope(
+    &scope,
+    "(edn.register_tag :my/bad (fn [value] (throw \"bad tag\")))",
+  );
+  let value = run_in_scope(
+    &scope,
+    "(try (edn.read \"#my/bad 1\") (fn [error] (map.get error :value)))",
+  );
+
+  assert_eq!(String::from_value(&scope, &value), Ok("bad tag".to_owned()));
+}
+
+#[test]
+fn test_map_and_set_hash_by_entries() {
+  let scope = new_context();
+  let hash = |source: &str| {
+    let mut hasher = DefaultHasher::new();
+    read_edn(&scope, source).unwrap().hash(&mut hasher);
+    hasher.finish()
+  };
+
+  assert_eq!(hash("{:a 1 :b 2}"), hash("{:b 2 :a 1}"));
+  assert_ne!(hash("{:a 1 :b 2}"), hash("{:a 2 :b 1}"));
+  assert_eq!(hash("#{1 2 3}"), hash("#{3 2 1}"));
+  assert_ne!(hash("#{1 2 3}"), hash("#{1 2 4}"));
+}
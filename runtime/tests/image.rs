@@ -0,0 +1,124 @@
+extern crate lisp_gc;
+extern crate lisp_runtime;
+
+use lisp_gc::Gc;
+use lisp_runtime::{
+  add_external_function, add_typed_function, new_base_context, new_context, new_external_function,
+  new_isize, new_string, new_symbol, new_vector, read_image, run_in_scope, scope_set, write_image,
+  FromValue, Object, Scope, Value, Vector,
+};
+
+fn answer(scope: &Gc<Object<Scope>>, _args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  new_isize(scope, 42).into_value()
+}
+
+fn other_answer(scope: &Gc<Object<Scope>>, _args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  new_isize(scope, 0).into_value()
+}
+
+#[test]
+fn test_restore_definitions() {
+  let scope = new_context();
+
+  run_in_scope(
+    &scope,
+    "(def-fn add-one [x] (isize.add x 1))
+     (def data {:a [1 2], :b \"text\"})
+     (def counter (atom.new 1))",
+  );
+
+  let bytes = write_image(&scope).expect("failed to write image");
+  let restored = new_base_context();
+
+  read_image(&restored, &bytes).expect("failed to read image");
+
+  assert_eq!(
+    run_in_scope(&restored, "(add-one 41)"),
+    new_isize(&restored, 42).into_value()
+  );
+  assert_eq!(
+    run_in_scope(&restored, "(atom.get counter)"),
+    new_isize(&restored, 1).into_value()
+  );
+  assert_eq!(
+    run_in_scope(&restored, "(edn.write (map.get data :a))"),
+    run_in_scope(&scope, "(edn.write (map.get data :a))")
+  );
+  assert_eq!(
+    run_in_scope(&restored, "(edn.write (map.get data :b))"),
+    run_in_scope(&scope, "(edn.write (map.get data :b))")
+  );
+}
+
+#[test]
+fn test_restore_bootstrap_macros() {
+  let scope = new_context();
+  let bytes = write_image(&scope).expect("failed to write image");
+  let restored = new_base_context();
+
+  read_image(&restored, &bytes).expect("failed to read image");
+
+  assert_eq!(
+    run_in_scope(&restored, "(def-fn twice [x] (isize.mul x 2)) (twice 21)"),
+    new_isize(&restored, 42).into_value()
+  );
+  assert_eq!(
+    run_in_scope(&restored, "(json.stringify [1 2])"),
+    new_string(&restored, "[1,2]").into_value()
+  );
+}
+
+#[test]
+fn test_invalid_image() {
+  let scope = new_base_context();
+
+  assert!(read_image(&scope, b"not an image").is_err());
+
+  let mut bytes = write_image(&new_context()).expect("failed to write image");
+  bytes.truncate(bytes.len() / 2);
+  assert!(read_image(&scope, &bytes).is_err());
+}
+
+#[test]
+fn test_relink_external_functions_by_owner() {
+  let scope = new_context();
+  add_external_function(&scope, "host", "host.answer", vec![], answer);
+  add_typed_function(&scope, "host", "host.double", vec!["x"], |x: i64| x * 2);
+  add_typed_function(&scope, "host", "host.negate", vec!["x"], |x: i64| -x);
+  let bytes = write_image(&scope).expect("failed to write image");
+
+  let restored = new_base_context();
+  add_external_function(&restored, "host", "host.answer", vec![], answer);
+  add_typed_function(&restored, "host", "host.double", vec!["x"], |x: i64| x * 2);
+  add_typed_function(&restored, "host", "host.negate", vec!["x"], |x: i64| -x);
+  read_image(&restored, &bytes).expect("failed to read image");
+  assert_eq!(
+    run_in_scope(&restored, "(host.answer)"),
+    new_isize(&restored, 42).into_value()
+  );
+  let value = run_in_scope(
+    &restored,
+    "[(host.double (json.parse \"2\")) (host.negate (json.parse \"2\"))]",
+  );
+  assert_eq!(Vec::<i64>::from_value(&restored, &value), Ok(vec![4, -2]));
+
+  let mismatched = new_base_context();
+  add_external_function(&mismatched, "other", "host.answer", vec![], other_answer);
+  let error = read_image(&mismatched, &bytes).expect_err("relinked a different owner");
+  assert!(error.contains("host.answer"), "{}", error);
+}
+
+#[test]
+fn test_unregistered_external_functions_are_not_written() {
+  let scope = new_context();
+  let function = new_external_function(
+    &scope,
+    Some(new_symbol(&scope, "host.answer")),
+    new_vector(&scope),
+    answer,
+  );
+  scope_set(&scope, "host.answer", function.into_value());
+
+  let error = write_image(&scope).expect_err("wrote an unregistered function");
+  assert!(error.contains("not registered"), "{}", error);
+}
@@ -0,0 +1,69 @@
+extern crate lisp_runtime;
+extern crate serde_json;
+
+use std::fs::{read_dir, read_to_string};
+use std::path::PathBuf;
+
+use lisp_runtime::{new_context, parse_json, stringify_json};
+
+fn corpus() -> Vec<(PathBuf, String)> {
+  let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/json");
+  let mut files = read_dir(&dir)
+    .expect("failed to read JSON corpus directory")
+    .map(|entry| entry.expect("failed to read corpus entry").path())
+    .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+    .map(|path| {
+      let content = read_to_string(&path).expect("failed to read corpus file");
+      (path, content)
+    })
+    .collect::<Vec<_>>();
+  files.sort();
+  assert!(!files.is_empty(), "JSON corpus is empty");
+  files
+}
+
+fn round_trip(keywords: bool, pretty: bool) {
+  let scope = new_context();
+
+  for (path, content) in corpus() {
+    let expected = serde_json::from_str::<serde_json::Value>(&content).unwrap();
+    let value = parse_json(&scope, &content, keywords)
+      .unwrap_or_else(|error| panic!("failed to parse {:?}: {}", path, error));
+    let string = stringify_json(&value, pretty)
+      .unwrap_or_else(|error| panic!("failed to stringify {:?}: {}", path, error));
+    let actual = serde_json::from_str::<serde_json::Value>(&string).unwrap();
+
+    assert_eq!(
+      expected, actual,
+      "round trip of {:?} changed the document",
+      path
+    );
+  }
+}
+
+#[test]
+fn test_round_trip_string_keys() {
+  round_trip(false, false);
+}
+
+#[test]
+fn test_round_trip_keyword_keys() {
+  round_trip(true, false);
+}
+
+#[test]
+fn test_round_trip_pretty() {
+  round_trip(false, true);
+}
+
+#[test]
+fn test_parse_error_line_column() {
+  let scope = new_context();
+  let error = parse_json(&scope, "{\n  \"a\": 1,\n  \"b\": }", false)
+    .err()
+    .expect("expected invalid JSON to fail");
+
+  assert_eq!(error.line(), 3);
+  assert_eq!(error.column(), 8);
+  assert!(error.to_string().contains("line 3 column 8"));
+}
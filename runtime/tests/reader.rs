@@ -0,0 +1,22 @@
+extern crate lisp_runtime;
+
+use lisp_runtime::{new_context, read, run_in_scope, Map, Object};
+
+#[test]
+fn test_read_map_keeps_every_entry() {
+  let scope = new_context();
+  let value = read(&scope, "{:a 1 :b 2, :c 3}");
+  let map = value
+    .downcast_ref::<Object<Map>>()
+    .expect("failed to read a map");
+
+  assert_eq!(map.value().len(), 3);
+  assert_eq!(
+    format!("{:?}", run_in_scope(&scope, "(map.get {:a 1 :b 2} :a)")),
+    "1"
+  );
+  assert_eq!(
+    format!("{:?}", run_in_scope(&scope, "(map.get {:a 1 :b 2} :b)")),
+    "2"
+  );
+}
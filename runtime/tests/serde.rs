@@ -0,0 +1,125 @@
+extern crate lisp_gc;
+extern crate lisp_runtime;
+extern crate serde_json;
+
+use lisp_gc::Gc;
+use lisp_runtime::{
+  deserialize_value, new_bool, new_char, new_context, new_f32, new_f64, new_i16, new_i32, new_i64,
+  new_i8, new_isize, new_keyword, new_list, new_map, new_set, new_string, new_symbol, new_u16,
+  new_u32, new_u64, new_u8, new_usize, new_vector, nil_value, Object, Scope, Value,
+};
+
+fn to_json(value: &Gc<dyn Value>) -> serde_json::Value {
+  serde_json::to_value(&**value).expect("failed to serialize value")
+}
+
+fn round_trip(scope: &Gc<Object<Scope>>, value: Gc<dyn Value>, expected: serde_json::Value) {
+  let json = to_json(&value);
+  assert_eq!(json, expected, "{:?} serialized to {}", value, json);
+
+  let string = serde_json::to_string(&json).unwrap();
+  let restored = deserialize_value(scope, &mut serde_json::Deserializer::from_str(&string))
+    .unwrap_or_else(|error| panic!("failed to deserialize {}: {}", string, error));
+  assert_eq!(
+    to_json(&restored),
+    expected,
+    "{} changed on round trip",
+    string
+  );
+}
+
+#[test]
+fn test_round_trip_scalars() {
+  let scope = new_context();
+
+  round_trip(
+    &scope,
+    nil_value(&scope).into_value(),
+    serde_json::Value::Null,
+  );
+  round_trip(&scope, new_bool(&scope, true).into_value(), true.into());
+  round_trip(&scope, new_char(&scope, 'c').into_value(), "c".into());
+  round_trip(
+    &scope,
+    new_string(&scope, "text").into_value(),
+    "text".into(),
+  );
+  round_trip(
+    &scope,
+    new_keyword(&scope, "key").into_value(),
+    "key".into(),
+  );
+  round_trip(&scope, new_symbol(&scope, "sym").into_value(), "sym".into());
+}
+
+#[test]
+fn test_round_trip_numbers() {
+  let scope = new_context();
+
+  round_trip(&scope, new_i8(&scope, -8).into_value(), (-8).into());
+  round_trip(&scope, new_i16(&scope, -16).into_value(), (-16).into());
+  round_trip(&scope, new_i32(&scope, -32).into_value(), (-32).into());
+  round_trip(&scope, new_i64(&scope, -64).into_value(), (-64).into());
+  round_trip(&scope, new_isize(&scope, -1).into_value(), (-1).into());
+  round_trip(&scope, new_u8(&scope, 8).into_value(), 8.into());
+  round_trip(&scope, new_u16(&scope, 16).into_value(), 16.into());
+  round_trip(&scope, new_u32(&scope, 32).into_value(), 32.into());
+  round_trip(
+    &scope,
+    new_u64(&scope, u64::MAX).into_value(),
+    u64::MAX.into(),
+  );
+  round_trip(&scope, new_usize(&scope, 1).into_value(), 1.into());
+  round_trip(&scope, new_f32(&scope, 1.5).into_value(), 1.5.into());
+  round_trip(&scope, new_f64(&scope, -0.25).into_value(), (-0.25).into());
+}
+
+#[test]
+fn test_sixteen_bit_kind_names() {
+  let scope = new_context();
+
+  assert_eq!(new_i16(&scope, 1).kind().name(), "I16");
+  assert_eq!(new_u16(&scope, 1).kind().name(), "U16");
+}
+
+#[test]
+fn test_round_trip_collections() {
+  let scope = new_context();
+
+  let mut list = new_list(&scope);
+  list.push_back(new_i64(&scope, 1).into_value());
+  list.push_back(new_string(&scope, "two").into_value());
+  round_trip(&scope, list.into_value(), serde_json::json!([1, "two"]));
+
+  let mut vector = new_vector(&scope);
+  vector.push(new_bool(&scope, false).into_value());
+  vector.push(new_vector(&scope).into_value());
+  round_trip(&scope, vector.into_value(), serde_json::json!([false, []]));
+
+  let mut set = new_set(&scope);
+  set.insert(new_keyword(&scope, "only").into_value());
+  round_trip(&scope, set.into_value(), serde_json::json!(["only"]));
+
+  let mut map = new_map(&scope);
+  map.set(
+    new_string(&scope, "a").into_value(),
+    new_f64(&scope, 1.5).into_value(),
+  );
+  map.set(
+    new_string(&scope, "b").into_value(),
+    nil_value(&scope).into_value(),
+  );
+  round_trip(
+    &scope,
+    map.into_value(),
+    serde_json::json!({"a": 1.5, "b": null}),
+  );
+}
+
+#[test]
+fn test_serialize_rejects_functions() {
+  let scope = new_context();
+  let function = lisp_runtime::run_in_scope(&scope, "(fn [] nil)");
+
+  assert!(serde_json::to_value(&*function).is_err());
+}
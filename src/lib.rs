@@ -2,12 +2,19 @@ extern crate libloading;
 extern crate rustyline;
 
 pub extern crate lisp_gc as gc;
+pub extern crate lisp_plugin as plugin;
 pub extern crate lisp_runtime as runtime;
 
 mod lisp;
 mod loader;
 mod module;
+mod reload;
+mod repl;
+mod semver;
 
 pub use self::lisp::*;
 pub use self::loader::*;
 pub use self::module::*;
+pub use self::reload::*;
+pub use self::repl::*;
+pub use self::semver::*;
@@ -1,71 +1,224 @@
-use std::{fmt::Write, io, path::Path};
+use std::{fmt::Write, fs, io, io::Write as _, path::Path, process, thread, time::Duration};
 
 use rustyline::error::ReadlineError;
 use rustyline::{Editor, Result};
 
 use gc::Gc;
 use runtime::{
-  add_external_function, new_context, new_string, nil_value, Object, Scope, Value, Vector,
+  add_external_function, add_external_macro, catch_uncaught, from_argument, new_base_context,
+  new_context, new_string, new_vector, nil_value, read_image, scope_get_with_kind, scope_set,
+  throw_error, write_image, IntoValue, LispKind, LispMethods, Map, Object, Scope, Value, Vector,
 };
 
-use super::{loader, new_module, DyLib};
+use super::{
+  dylib_reload, export, history_path, import, loader, module_add_loader, module_new, module_reload,
+  module_reload_changed, new_module, reexport, reload_changed, top_level_forms, DyLib,
+  ModuleSources, ReplHelper,
+};
 
 const NAME: &'static str = env!("CARGO_PKG_NAME");
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+const WATCH_INTERVAL_MS: u64 = 250;
 
 pub fn new() -> Gc<Object<Scope>> {
   let scope = new_context();
+  init_scope(&scope);
+  scope
+}
 
-  DyLib::init_kind(&scope);
-  DyLib::init_scope(&scope);
+#[inline]
+pub fn new_from_image(bytes: &[u8]) -> io::Result<Gc<Object<Scope>>> {
+  let scope = new_base_context();
+  init_scope(&scope);
+  read_image(&scope, bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+  Ok(scope)
+}
 
-  add_external_function(&scope, "println", vec!["...args"], println);
+#[inline]
+pub fn load_image(path: &Path) -> io::Result<Gc<Object<Scope>>> {
+  new_from_image(&fs::read(path)?)
+}
 
-  scope
+#[inline]
+pub fn save_image(scope: &Gc<Object<Scope>>, path: &Path) -> io::Result<()> {
+  let bytes =
+    write_image(scope).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+  fs::write(path, bytes)
 }
 
 #[inline]
-pub fn run_path(scope: &Gc<Object<Scope>>, filename_path: &Path) -> io::Result<()> {
+fn init_scope(scope: &Gc<Object<Scope>>) {
+  DyLib::init_kind(scope);
+  DyLib::init_methods(scope);
+  ModuleSources::init_kind(scope);
+
+  add_external_function(scope, "lisp", "println", vec!["...args"], println);
+  add_external_function(scope, "lisp", "exit", vec!["...code"], exit);
+  // macros expand in the calling scope, so modules share the root bindings of
+  // import and export and -e and the repl can import too
+  add_external_macro(scope, "lisp", "import", vec!["...imports"], import);
+  add_external_macro(scope, "lisp", "export", vec!["...exports"], export);
+  add_external_macro(scope, "lisp", "reexport", vec!["...imports"], reexport);
+  add_external_macro(scope, "lisp", "module.reload", vec!["path"], module_reload);
+  add_external_macro(scope, "lisp", "dylib.reload", vec!["path"], dylib_reload);
+  add_external_function(
+    scope,
+    "lisp",
+    "module.add-loader",
+    vec!["loader", "...position"],
+    module_add_loader,
+  );
+  // the underscore spelling is kept for existing code
+  add_external_function(
+    scope,
+    "lisp",
+    "module.add_loader",
+    vec!["loader", "...position"],
+    module_add_loader,
+  );
+  add_external_function(scope, "lisp", "module.new", vec!["parent"], module_new);
+  add_external_function(
+    scope,
+    "lisp",
+    "module.reload_changed",
+    vec![],
+    module_reload_changed,
+  );
+
   let mut module = new_module(scope, None);
   module.set(
     new_string(scope, "dirname").into_value(),
     new_string(scope, ".").into_value(),
   );
-  loader::load(
+  scope_set(scope, "module", module.into_value());
+}
+
+/// Binds `argv` to a vector of `args`, the script followed by its arguments.
+#[inline]
+pub fn set_argv<I>(scope: &Gc<Object<Scope>>, args: I)
+where
+  I: IntoIterator,
+  I::Item: AsRef<str>,
+{
+  let mut argv = new_vector(scope);
+
+  for arg in args {
+    argv.push(new_string(scope, arg.as_ref()).into_value());
+  }
+  scope_set(scope, "argv", argv.into_value());
+}
+
+#[inline]
+pub fn run_path(scope: &Gc<Object<Scope>>, filename_path: &Path) -> io::Result<()> {
+  let module = scope_get_with_kind::<Map>(scope, "module").expect("module is not defined");
+  let filename = new_string(
     scope,
-    module,
-    new_string(
-      scope,
-      filename_path
-        .to_str()
-        .expect("failed to move Path to string"),
-    ),
-  )
-  .expect(&format!("failed to load module {:?}", filename_path));
-  Ok(())
+    filename_path
+      .to_str()
+      .expect("failed to move Path to string"),
+  );
+
+  // errors the module does not catch are returned like other io errors
+  match catch_uncaught(scope, || loader::load(scope, module, filename)) {
+    Ok(Some(_)) => Ok(()),
+    Ok(None) => Err(io::Error::new(
+      io::ErrorKind::NotFound,
+      format!("failed to load module {:?}", filename_path),
+    )),
+    Err(error) => Err(io::Error::new(io::ErrorKind::Other, format!("{:?}", error))),
+  }
+}
+
+/// Reloads the modules whose files change, never returns.
+#[inline]
+pub fn watch(scope: &Gc<Object<Scope>>) -> io::Result<()> {
+  loop {
+    thread::sleep(Duration::from_millis(WATCH_INTERVAL_MS));
+    reload_changed_modules(scope);
+  }
+}
+
+#[inline]
+fn reload_changed_modules(scope: &Gc<Object<Scope>>) {
+  let module = scope_get_with_kind::<Map>(scope, "module").expect("module is not defined");
+
+  match reload_changed(scope, &module) {
+    Ok(reloaded) => {
+      for path in reloaded {
+        eprintln!("reloaded {}", path);
+      }
+    }
+    Err(error) => eprintln!("error: {}", error),
+  }
 }
 
 #[inline(always)]
 pub fn repl(scope: &Gc<Object<Scope>>) -> io::Result<()> {
-  match repl_internal(scope) {
+  repl_with(scope, false)
+}
+
+/// Like `repl` but reloads the modules whose files changed before each line
+/// is evaluated.
+#[inline(always)]
+pub fn repl_watch(scope: &Gc<Object<Scope>>) -> io::Result<()> {
+  repl_with(scope, true)
+}
+
+#[inline]
+fn repl_with(scope: &Gc<Object<Scope>>, watch: bool) -> io::Result<()> {
+  match repl_internal(scope, watch) {
     Ok(_) => io::Result::Ok(()),
     Err(e) => io::Result::Err(io::Error::new(io::ErrorKind::Other, e)),
   }
 }
 
 #[inline]
-fn repl_internal(scope: &Gc<Object<Scope>>) -> Result<()> {
-  let mut rl = Editor::<()>::new()?;
+fn repl_internal(scope: &Gc<Object<Scope>>, watch: bool) -> Result<()> {
+  let mut rl = Editor::<ReplHelper>::new()?;
+  let history = history_path();
+
+  rl.set_helper(Some(ReplHelper));
+  if let Some(ref history) = history {
+    // there is no history before the first session
+    let _ = rl.load_history(history);
+  }
+
   println!("Welcome to {} v{}", NAME, VERSION);
+  let mut input = String::new();
   loop {
-    let readline = rl.readline("> ");
+    let readline = rl.readline(if input.is_empty() { "> " } else { ".. " });
     match readline {
       Ok(line) => {
-        rl.add_history_entry(line.as_str());
-        let value = runtime::run_in_scope(scope, &line);
-        if value != nil_value(scope).into_value() {
-          println!("{:?}", value);
+        input.push_str(&line);
+        input.push('\n');
+
+        let forms = match top_level_forms(&input) {
+          Some(forms) => forms,
+          None => continue,
+        };
+        rl.add_history_entry(input.trim_end());
+        // saved after each entry since an uncaught error ends the process
+        if let Some(ref history) = history {
+          if let Err(error) = rl.save_history(history) {
+            eprintln!(
+              "warning: failed to save history to {:?}: {}",
+              history, error
+            );
+          }
+        }
+        if watch {
+          reload_changed_modules(scope);
         }
+        for form in forms {
+          let value = runtime::run_in_scope(scope, form);
+          if value != nil_value(scope).into_value() {
+            println!("{:?}", value);
+          }
+        }
+        input.clear();
+      }
+      Err(ReadlineError::Interrupted) if !input.is_empty() => {
+        input.clear();
       }
       Err(ReadlineError::Interrupted) => {
         println!("CTRL-C");
@@ -80,6 +233,7 @@ fn repl_internal(scope: &Gc<Object<Scope>>) -> Result<()> {
       }
     }
   }
+
   Ok(())
 }
 
@@ -100,3 +254,15 @@ fn println(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value
   println!("{}", string);
   nil_value(scope).clone().into_value()
 }
+
+/// `(exit)` or `(exit code)` ends the process, 0 by default.
+#[inline]
+fn exit(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let code = match from_argument::<Option<i32>>(scope, args, 0) {
+    Ok(code) => code.unwrap_or(0),
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+
+  let _ = io::stdout().flush();
+  process::exit(code)
+}
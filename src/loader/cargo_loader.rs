@@ -0,0 +1,218 @@
+use std::env::{current_exe, var_os};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+use gc::Gc;
+use runtime::{
+  from_argument, new_string, nil_value, throw_error, IntoValue, Map, Object, Scope, Value, Vector,
+};
+
+use super::{dylib_loader, native_library_filename, NATIVE_BUILDER_OUT};
+
+pub const NATIVE_BUILDER_ENV: &'static str = "LISP_BUILDER";
+pub const CARGO_MANIFEST: &'static str = "Cargo.toml";
+
+const IGNORED_DIRS: [&'static str; 3] = [".git", "target", NATIVE_BUILDER_OUT];
+
+#[inline]
+pub fn cargo_loader_lisp_fn(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let module = match from_argument::<Gc<Object<Map>>>(scope, args, 0) {
+    Ok(module) => module,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let filename = match from_argument::<String>(scope, args, 1) {
+    Ok(filename) => filename,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+
+  match cargo_loader(scope, &module, &filename) {
+    Ok(Some(module)) => module.into_value(),
+    Ok(None) => nil_value(scope).clone().into_value(),
+    Err(error) => throw_error(scope, error.into_value(scope)),
+  }
+}
+
+/// Loads a local `Cargo.toml`, building the package's cdylib into the
+/// `lisp-builder-out` directory next to it when the library is older than
+/// the package's files, then loading the library with `dylib_loader`.
+#[inline]
+pub fn cargo_loader(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  filename: &String,
+) -> Result<Option<Gc<Object<Map>>>, String> {
+  let is_local =
+    filename.starts_with(".") || filename.starts_with("/") || filename.starts_with("\\");
+
+  if !is_local
+    || Path::new(filename)
+      .file_name()
+      .and_then(|name| name.to_str())
+      != Some(CARGO_MANIFEST)
+  {
+    return Ok(None);
+  }
+
+  let parent_dirname = parent_module
+    .get(&new_string(scope, "dirname").into_value())
+    .and_then(|dirname| dirname.downcast_ref::<Object<String>>())
+    .map(|dirname| dirname.value().clone())
+    .unwrap_or_else(|| ".".to_owned());
+  let manifest = fs::canonicalize(Path::new(&parent_dirname).join(filename))
+    .map_err(|error| format!("failed to find {:?}: {}", filename, error))?;
+  let library = native_package_library(&manifest)?;
+
+  // an imported library is only rebuilt by dylib.reload
+  let is_loaded = parent_module
+    .get(&new_string(scope, "cache").into_value())
+    .and_then(|cache| cache.downcast_ref::<Object<Map>>())
+    .map(|cache| cache.has(&new_string(scope, library.to_str().unwrap()).into_value()))
+    .unwrap_or(false);
+
+  if !is_loaded {
+    build_native_package(&native_builder(), &manifest)?;
+  }
+  dylib_loader(scope, parent_module, &library.to_str().unwrap().to_owned())
+}
+
+/// Runs `builder` for `manifest` unless the library it builds is newer than
+/// every file in the package, returns the library's path.
+#[inline]
+pub fn build_native_package(builder: &Path, manifest: &Path) -> Result<PathBuf, String> {
+  let library = native_package_library(manifest)?;
+  let dirname = manifest.parent().unwrap_or_else(|| Path::new("."));
+
+  let is_fresh = match (modified(&library), newest_modified(dirname)) {
+    (Some(built), Some(changed)) => built >= changed,
+    _ => false,
+  };
+  if is_fresh {
+    return Ok(library);
+  }
+
+  let out_dir = library.parent().unwrap();
+  fs::create_dir_all(out_dir)
+    .map_err(|error| format!("failed to create {:?}: {}", out_dir, error))?;
+
+  let output = Command::new(builder)
+    .arg("--input")
+    .arg(manifest)
+    .arg("--dest")
+    .arg(out_dir)
+    .output()
+    .map_err(|error| {
+      format!(
+        "failed to run {:?}, set {} to the lisp-builder executable: {}",
+        builder, NATIVE_BUILDER_ENV, error
+      )
+    })?;
+
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut error = format!("failed to build {:?}, {}", manifest, output.status);
+
+    if !stderr.trim().is_empty() {
+      error.push('\n');
+      error.push_str(stderr.trim_end());
+    }
+    return Err(error);
+  }
+  if !library.is_file() {
+    return Err(format!(
+      "failed to build {:?}, the builder did not create {:?}",
+      manifest, library
+    ));
+  }
+
+  Ok(library)
+}
+
+/// The builder run by `cargo_loader`, `LISP_BUILDER` when set, otherwise
+/// `lisp-builder` next to the current executable or on the `PATH`.
+#[inline]
+pub fn native_builder() -> PathBuf {
+  if let Some(builder) = var_os(NATIVE_BUILDER_ENV) {
+    return PathBuf::from(builder);
+  }
+  let filename = format!("lisp-builder{}", ::std::env::consts::EXE_SUFFIX);
+
+  current_exe()
+    .ok()
+    .and_then(|exe| exe.parent().map(|dir| dir.join(&filename)))
+    .filter(|path| path.is_file())
+    .unwrap_or_else(|| PathBuf::from(filename))
+}
+
+/// Where the builder puts the cdylib of `manifest`, named after its `[lib]`
+/// name or else its package name.
+#[inline]
+pub fn native_package_library(manifest: &Path) -> Result<PathBuf, String> {
+  let content = fs::read_to_string(manifest)
+    .map_err(|error| format!("failed to read {:?}: {}", manifest, error))?;
+  let name = manifest_name(&content, "lib")
+    .or_else(|| manifest_name(&content, "package"))
+    .ok_or_else(|| format!("failed to find the package name in {:?}", manifest))?;
+
+  Ok(
+    manifest
+      .parent()
+      .unwrap_or_else(|| Path::new("."))
+      .join(NATIVE_BUILDER_OUT)
+      .join(native_library_filename(&name.replace('-', "_"))),
+  )
+}
+
+#[inline]
+fn manifest_name(content: &str, table: &str) -> Option<String> {
+  let header = format!("[{}]", table);
+  let mut in_table = false;
+
+  for line in content.lines().map(str::trim) {
+    if line.starts_with('[') {
+      in_table = line == header;
+    } else if in_table {
+      let mut parts = line.splitn(2, '=');
+
+      if parts.next().map(str::trim) == Some("name") {
+        return parts
+          .next()
+          .map(|value| value.trim().trim_matches('"').to_owned());
+      }
+    }
+  }
+  None
+}
+
+#[inline]
+fn modified(path: &Path) -> Option<SystemTime> {
+  fs::metadata(path)
+    .and_then(|metadata| metadata.modified())
+    .ok()
+}
+
+#[inline]
+fn newest_modified(dir: &Path) -> Option<SystemTime> {
+  let mut newest = None;
+
+  for entry in fs::read_dir(dir).ok()?.filter_map(Result::ok) {
+    let path = entry.path();
+    let is_ignored = entry
+      .file_name()
+      .to_str()
+      .map(|name| IGNORED_DIRS.contains(&name))
+      .unwrap_or(false);
+
+    let changed = if path.is_dir() {
+      if is_ignored {
+        continue;
+      }
+      newest_modified(&path)
+    } else {
+      modified(&path)
+    };
+    newest = newest.max(changed);
+  }
+  newest
+}
@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use gc::Gc;
+use runtime::{
+  from_argument, new_map, new_string, nil_value, parse_json, read_edn, throw_error, IntoValue, Map,
+  Object, Scope, Value, Vector,
+};
+
+use super::super::new_module;
+use super::module_source;
+
+/// The export a data file's document is bound to, `(import name "path")`
+/// with a name a module does not export binds its default export.
+pub const DEFAULT_EXPORT: &'static str = "default";
+
+#[inline]
+pub fn json_loader_lisp_fn(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  data_loader_lisp_fn(scope, args, json_loader)
+}
+
+#[inline]
+pub fn edn_loader_lisp_fn(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  data_loader_lisp_fn(scope, args, edn_loader)
+}
+
+/// Loads a local `.json` file, objects keep their keys as strings.
+#[inline]
+pub fn json_loader(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  filename: &String,
+) -> Result<Option<Gc<Object<Map>>>, String> {
+  data_loader(scope, parent_module, filename, "json", |scope, content| {
+    parse_json(scope, content, false).map_err(|error| error.to_string())
+  })
+}
+
+/// Loads a local `.edn` file, the first form is the document.
+#[inline]
+pub fn edn_loader(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  filename: &String,
+) -> Result<Option<Gc<Object<Map>>>, String> {
+  data_loader(scope, parent_module, filename, "edn", read_edn)
+}
+
+#[inline]
+fn data_loader_lisp_fn<F>(
+  scope: &Gc<Object<Scope>>,
+  args: &Gc<Object<Vector>>,
+  loader: F,
+) -> Gc<dyn Value>
+where
+  F: Fn(&Gc<Object<Scope>>, &Gc<Object<Map>>, &String) -> Result<Option<Gc<Object<Map>>>, String>,
+{
+  let module = match from_argument::<Gc<Object<Map>>>(scope, args, 0) {
+    Ok(module) => module,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let filename = match from_argument::<String>(scope, args, 1) {
+    Ok(filename) => filename,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+
+  match loader(scope, &module, &filename) {
+    Ok(Some(module)) => module.into_value(),
+    Ok(None) => nil_value(scope).clone().into_value(),
+    Err(error) => throw_error(scope, error.into_value(scope)),
+  }
+}
+
+#[inline]
+fn data_loader<F>(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  filename: &String,
+  extension: &str,
+  parse: F,
+) -> Result<Option<Gc<Object<Map>>>, String>
+where
+  F: Fn(&Gc<Object<Scope>>, &str) -> Result<Gc<dyn Value>, String>,
+{
+  let is_local =
+    filename.starts_with(".") || filename.starts_with("/") || filename.starts_with("\\");
+
+  if !is_local || Path::new(filename).extension().and_then(|ext| ext.to_str()) != Some(extension) {
+    return Ok(None);
+  }
+
+  let parent_dirname = parent_module
+    .get(&new_string(scope, "dirname").into_value())
+    .and_then(|dirname| dirname.downcast_ref::<Object<String>>())
+    .map(|dirname| dirname.value().clone())
+    .unwrap_or_else(|| ".".to_owned());
+  let source = module_source(scope, parent_module);
+  let path = source
+    .canonicalize(&Path::new(&parent_dirname).join(filename))
+    .map_err(|error| format!("failed to find {:?}: {}", filename, error))?;
+  let path_value = new_string(scope, path.to_str().unwrap()).into_value();
+
+  let mut cache = parent_module
+    .get(&new_string(scope, "cache").into_value())
+    .and_then(|cache| cache.downcast_ref::<Object<Map>>())
+    .map(Clone::clone)
+    .unwrap_or_else(|| new_map(scope));
+
+  if let Some(module) = cache
+    .get(&path_value)
+    .and_then(|module| module.downcast_ref::<Object<Map>>())
+  {
+    return Ok(Some(module.clone()));
+  }
+
+  let content = source
+    .read_to_string(&path)
+    .map_err(|error| format!("failed to read {:?}: {}", path, error))?;
+  let document =
+    parse(scope, &content).map_err(|error| format!("failed to parse {:?}: {}", path, error))?;
+
+  let mut module = new_module(scope, Some(parent_module.clone()));
+  let mut exports = new_map(scope);
+  exports.set(new_string(scope, DEFAULT_EXPORT).into_value(), document);
+
+  module.set(
+    new_string(scope, "exports").into_value(),
+    exports.into_value(),
+  );
+  module.set(
+    new_string(scope, "filename").into_value(),
+    path_value.clone(),
+  );
+  module.set(
+    new_string(scope, "dirname").into_value(),
+    new_string(
+      scope,
+      path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .to_str()
+        .unwrap(),
+    )
+    .into_value(),
+  );
+  cache.set(path_value, module.clone().into_value());
+
+  Ok(Some(module))
+}
@@ -1,45 +1,60 @@
-use std::cmp::Ordering;
+use std::ffi::CStr;
 use std::fmt;
-use std::hash::{Hash, Hasher};
-use std::ops::Deref;
-use std::ptr;
+use std::os::raw::c_char;
+use std::slice;
 
-use gc::{Gc, Trace};
+use gc::Gc;
 use libloading::{Error, Library, Symbol};
+use plugin::{LispFunction, LispPluginInit, LISP_PLUGIN_ABI_VERSION, LISP_PLUGIN_INIT};
 use runtime::{
-  add_external_function, new_kind, new_object, scope_get_with_kind, scope_set, Keyword, Kind,
-  Object, Scope, Value, Vector,
+  lisp_methods, new_external_closure, new_keyword, new_map, new_string, new_symbol,
+  new_vector_from, throw_error, Function, IntoValue, Keyword, Kind, LispKind, Object, Scope, Value,
+  Vector,
 };
 
-pub type DyLibFunction =
-  unsafe extern "C" fn(&Gc<Object<Scope>>, &Gc<Object<Vector>>) -> Gc<dyn Value>;
+use super::{call_plugin_function, LISP_HOST};
 
+#[derive(LispKind)]
+#[lisp(crate = "runtime")]
 pub struct DyLib {
+  name: String,
+  exports: Vec<DyLibExport>,
   library: Library,
 }
 
-impl Trace for DyLib {}
+#[derive(Clone)]
+pub struct DyLibExport {
+  name: String,
+  params: Vec<String>,
+  doc: Option<String>,
+  function: LispFunction,
+}
 
-impl PartialEq for DyLib {
+impl DyLibExport {
   #[inline]
-  fn eq(&self, other: &Self) -> bool {
-    ptr::eq(self, other)
+  pub fn name(&self) -> &str {
+    &self.name
   }
-}
-
-impl Eq for DyLib {}
-
-impl PartialOrd for DyLib {
   #[inline]
-  fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
-    None
+  pub fn params(&self) -> &[String] {
+    &self.params
+  }
+  #[inline]
+  pub fn doc(&self) -> Option<&str> {
+    self.doc.as_ref().map(String::as_str)
+  }
+  #[inline]
+  pub fn function(&self) -> LispFunction {
+    self.function
   }
 }
 
-impl Hash for DyLib {
-  #[inline]
-  fn hash<H: Hasher>(&self, state: &mut H) {
-    ptr::hash(self, state);
+#[inline]
+unsafe fn c_string(ptr: *const c_char) -> Option<String> {
+  if ptr.is_null() {
+    None
+  } else {
+    Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
   }
 }
 
@@ -50,84 +65,151 @@ impl fmt::Debug for DyLib {
   }
 }
 
+#[lisp_methods(crate = "runtime")]
 impl DyLib {
   #[inline]
-  pub unsafe fn new<T>(path: T) -> Self
+  #[lisp(skip)]
+  pub unsafe fn new<T>(path: T) -> Result<Self, String>
   where
     T: ToString,
   {
-    DyLib {
-      library: Library::new(path.to_string())
-        .expect(&format!("failed to find dylib {}", path.to_string())),
+    let path = path.to_string();
+    let library =
+      Library::new(&path).map_err(|error| format!("failed to load {}: {}", path, error))?;
+    let plugin = {
+      let init = library
+        .get::<LispPluginInit>(LISP_PLUGIN_INIT.as_bytes())
+        .map_err(|_| format!("{} does not export {}", path, LISP_PLUGIN_INIT))?;
+      init(&LISP_HOST)
+    };
+
+    if plugin.is_null() {
+      return Err(format!(
+        "{} does not support plugin ABI version {}",
+        path, LISP_PLUGIN_ABI_VERSION
+      ));
     }
+    if (*plugin).abi_version != LISP_PLUGIN_ABI_VERSION {
+      return Err(format!(
+        "{} was built for plugin ABI version {}, expected {}",
+        path,
+        (*plugin).abi_version,
+        LISP_PLUGIN_ABI_VERSION
+      ));
+    }
+
+    let name = c_string((*plugin).name).unwrap_or_default();
+    let functions = if (*plugin).functions.is_null() {
+      &[]
+    } else {
+      slice::from_raw_parts((*plugin).functions, (*plugin).functions_len)
+    };
+    let mut exports = Vec::with_capacity(functions.len());
+
+    for function in functions {
+      let export_name = c_string(function.name)
+        .ok_or_else(|| format!("{} exports a function without a name", path))?;
+
+      exports.push(DyLibExport {
+        name: export_name,
+        params: c_string(function.params)
+          .map(|params| params.split_whitespace().map(String::from).collect())
+          .unwrap_or_default(),
+        doc: c_string(function.doc),
+        function: function.function,
+      });
+    }
+
+    Ok(DyLib {
+      name,
+      exports,
+      library,
+    })
   }
 
   #[inline]
+  #[lisp(skip)]
   pub unsafe fn get<'a, T>(&'a self, symbol: &str) -> Result<Symbol<'a, T>, Error> {
     self.library.get::<T>(symbol.as_bytes())
   }
 
   #[inline]
-  pub unsafe fn call(
-    &self,
-    name: &str,
-    scope: &Gc<Object<Scope>>,
-    args: &Gc<Object<Vector>>,
-  ) -> Result<Gc<dyn Value>, Error> {
-    let func = self.get::<DyLibFunction>(name)?;
-    Ok(func(scope, args))
+  pub fn name(&self) -> String {
+    self.name.clone()
   }
 
   #[inline]
-  pub(crate) fn init_kind(scope: &Gc<Object<Scope>>) {
-    let dylib_kind = new_kind::<DyLib>(scope, "DyLib");
-    scope_set(scope, "DyLib", dylib_kind.into_value());
+  #[lisp(skip)]
+  pub fn exports(&self) -> &[DyLibExport] {
+    &self.exports
   }
 
+  /// Calls the function exported as `name` in the plugin's manifest, other
+  /// symbols of the library can not be called since their signatures are
+  /// unknown.
   #[inline]
-  pub(crate) fn init_scope(scope: &Gc<Object<Scope>>) {
-    add_external_function(
-      scope,
-      "dylib.call",
-      vec!["dylib", "name", "...args"],
-      dylib_call,
-    );
-  }
-}
-
-#[inline]
-pub fn dylib_call(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
-  let dylib = args
-    .get(0)
-    .expect("DyLib is nil")
-    .downcast_ref::<Object<DyLib>>()
-    .expect("Failed to downcast dylib to DyLib")
-    .clone();
-  let name = args
-    .get(1)
-    .expect("name is nil")
-    .downcast_ref::<Object<Keyword>>()
-    .expect("Failed to downcast name to Keyword")
-    .clone();
-
-  unsafe {
-    dylib
-      .call(name.deref(), scope, args)
-      .expect(&format!("Failed to call dylib function {}", name.deref()))
+  pub fn call(
+    &self,
+    name: Gc<Object<Keyword>>,
+    scope: &Gc<Object<Scope>>,
+    args: &Gc<Object<Vector>>,
+  ) -> Result<Gc<dyn Value>, String> {
+    let name = name.value().as_str();
+    let export = self
+      .exports
+      .iter()
+      .find(|export| export.name() == name)
+      .ok_or_else(|| format!("{} does not export a function named {}", self.name, name))?;
+
+    call_plugin_function(scope, export.function(), &args[2..])
   }
 }
 
 #[inline]
 pub fn dylib_kind(scope: &Gc<Object<Scope>>) -> Gc<Object<Kind>> {
-  scope_get_with_kind::<Kind>(scope, "DyLib").expect("failed to get DyLib Kind")
+  DyLib::lisp_kind(scope)
 }
 #[inline]
-pub unsafe fn new_dylib<T>(scope: &Gc<Object<Scope>>, path: T) -> Gc<Object<DyLib>>
+pub unsafe fn new_dylib<T>(scope: &Gc<Object<Scope>>, path: T) -> Result<Gc<Object<DyLib>>, String>
 where
   T: ToString,
 {
-  new_object(
+  DyLib::new(path).map(|dylib| dylib.into_object(scope))
+}
+
+/// Wraps an exported plugin function in a `Function`, the function keeps
+/// `dylib` alive so the library is not unloaded while it is reachable.
+#[inline]
+pub fn new_dylib_function(
+  scope: &Gc<Object<Scope>>,
+  dylib: &Gc<Object<DyLib>>,
+  export: &DyLibExport,
+) -> Gc<Object<Function>> {
+  let params = export
+    .params()
+    .iter()
+    .map(|param| new_symbol(scope, param).into_value())
+    .collect::<Vec<Gc<dyn Value>>>();
+  let function = export.function();
+
+  let mut value = new_external_closure(
     scope,
-    Object::new(dylib_kind(scope).clone(), DyLib::new(path)),
-  )
+    Some(new_symbol(scope, export.name())),
+    new_vector_from(scope, params.into()),
+    dylib.clone(),
+    move |_dylib, scope, args| match call_plugin_function(scope, function, &args[..]) {
+      Ok(value) => value,
+      Err(error) => throw_error(scope, error.into_value(scope)),
+    },
+  );
+
+  if let Some(doc) = export.doc() {
+    let mut meta = new_map(scope);
+    meta.set(
+      new_keyword(scope, "doc").into_value(),
+      new_string(scope, doc).into_value(),
+    );
+    value.set_meta(meta);
+  }
+  value
 }
@@ -1,31 +1,38 @@
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::env::{split_paths, var_os};
 use std::fs::canonicalize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use gc::Gc;
-use runtime::{new_map, new_string, nil_value, Map, Object, Scope, Value, Vector};
+use runtime::{
+  from_argument, new_map, new_string, new_vector, nil_value, throw_error, IntoValue, Map, Object,
+  Scope, Value, Vector,
+};
 
-use super::super::{new_dylib, new_module};
+pub const NATIVE_PREFIX: &'static str = "native:";
+pub const NATIVE_PATH_ENV: &'static str = "LISP_NATIVE_PATH";
+pub const NATIVE_BUILDER_OUT: &'static str = "lisp-builder-out";
+
+const NATIVE_EXTENSIONS: [&'static str; 3] = [".so", ".dylib", ".dll"];
+
+use super::super::{new_dylib, new_dylib_function, new_module, DyLib};
 
 #[inline]
 pub fn dylib_loader_lisp_fn(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
-  let module_value = args
-    .get(0)
-    .expect("module not passed to dylib_loader")
-    .clone();
-  let module = module_value
-    .downcast_ref::<Object<Map>>()
-    .expect("Failed to downcast module to Map");
-  let filename_value = args
-    .get(1)
-    .expect("filename not passed to dylib_loader")
-    .clone();
-  let filename = filename_value
-    .downcast_ref::<Object<String>>()
-    .expect("Failed to downcast filename to String");
-
-  dylib_loader(scope, module, filename.value())
-    .map(|module| module.into_value())
-    .unwrap_or_else(|| nil_value(scope).clone().into_value())
+  let module = match from_argument::<Gc<Object<Map>>>(scope, args, 0) {
+    Ok(module) => module,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let filename = match from_argument::<String>(scope, args, 1) {
+    Ok(filename) => filename,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+
+  match dylib_loader(scope, &module, &filename) {
+    Ok(Some(module)) => module.into_value(),
+    Ok(None) => nil_value(scope).clone().into_value(),
+    Err(error) => throw_error(scope, error.into_value(scope)),
+  }
 }
 
 #[inline]
@@ -33,69 +40,181 @@ pub fn dylib_loader(
   scope: &Gc<Object<Scope>>,
   parent_module: &Gc<Object<Map>>,
   filename: &String,
-) -> Option<Gc<Object<Map>>> {
-  if filename.ends_with(".so") {
-    let parent_dirname_string = new_string(scope, "dirname").into_value();
-    let parent_dirname_value = parent_module
-      .get(&parent_dirname_string)
-      .expect("parent dirname is nil");
-    let parent_dirname = parent_dirname_value
-      .downcast_ref::<Object<String>>()
-      .expect("failed to downcast dirname to String");
-    let filename_path = Path::new(filename);
-    let parent_dirname_path = Path::new(parent_dirname.value());
-    let path =
-      canonicalize(parent_dirname_path.join(filename_path)).expect("failed to find local path");
-    let path_value = new_string(scope, path.clone().to_str().unwrap()).into_value();
-
-    let mut cache = parent_module
-      .get(&new_string(scope, "cache").into_value())
-      .and_then(|cache| cache.downcast_ref::<Object<Map>>())
-      .map(Clone::clone)
-      .unwrap_or_else(|| new_map(scope));
-
-    if cache.has(&path_value) {
-      Some(
-        cache
-          .get(&path_value)
-          .and_then(|cache| cache.downcast_ref::<Object<Map>>())
-          .map(Clone::clone)
-          .expect("failed to get module from cache"),
+) -> Result<Option<Gc<Object<Map>>>, String> {
+  let path = match resolve_native_library(scope, parent_module, filename)? {
+    Some(path) => path,
+    None => return Ok(None),
+  };
+  let path_value = new_string(scope, path.clone().to_str().unwrap()).into_value();
+
+  let mut cache = parent_module
+    .get(&new_string(scope, "cache").into_value())
+    .and_then(|cache| cache.downcast_ref::<Object<Map>>())
+    .map(Clone::clone)
+    .unwrap_or_else(|| new_map(scope));
+
+  if cache.has(&path_value) {
+    Ok(Some(
+      cache
+        .get(&path_value)
+        .and_then(|cache| cache.downcast_ref::<Object<Map>>())
+        .map(Clone::clone)
+        .expect("failed to get module from cache"),
+    ))
+  } else {
+    let dylib = unsafe { new_dylib(scope, path.to_str().unwrap())? };
+    let mut module = new_module(scope, Some(parent_module.clone()));
+
+    cache.set(path_value.clone(), module.clone().into_value());
+
+    module.set(
+      new_string(scope, "filename").into_value(),
+      path_value.clone(),
+    );
+    module.set(
+      new_string(scope, "dirname").into_value(),
+      new_string(
+        scope,
+        path
+          .parent()
+          .unwrap_or(Path::new(""))
+          .to_str()
+          .unwrap_or(""),
       )
-    } else {
-      let mut module = new_module(scope, Some(parent_module.clone()));
-
-      cache.set(path_value.clone(), module.clone().into_value());
-
-      module.set(
-        new_string(scope, "filename").into_value(),
-        path_value.clone(),
-      );
-      module.set(
-        new_string(scope, "dirname").into_value(),
-        new_string(
-          scope,
-          path
-            .parent()
-            .unwrap_or(Path::new(""))
-            .to_str()
-            .unwrap_or(""),
-        )
-        .into_value(),
-      );
-      let exports_value = module
-        .get_mut(&new_string(scope, "exports").into_value())
-        .unwrap();
-      let exports = exports_value.downcast_mut::<Object<Map>>().unwrap();
-
-      exports.set(
-        new_string(scope, path.file_stem().unwrap().to_str().unwrap()).into_value(),
-        unsafe { new_dylib(scope, path.to_str().unwrap()).into_value() },
-      );
-
-      Some(module)
+      .into_value(),
+    );
+    set_dylib_exports(scope, &module, &dylib, &path);
+
+    Ok(Some(module))
+  }
+}
+
+/// Sets the exports of `module` to the functions of `dylib` and the library
+/// itself, named after the file stem of `path`.
+#[inline]
+pub fn set_dylib_exports(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+  dylib: &Gc<Object<DyLib>>,
+  path: &Path,
+) {
+  let mut exports = module
+    .get(&new_string(scope, "exports").into_value())
+    .and_then(|exports| exports.downcast_ref::<Object<Map>>())
+    .map(Clone::clone)
+    .expect("exports not defined on module");
+
+  for export in dylib.exports() {
+    exports.set(
+      new_string(scope, export.name()).into_value(),
+      new_dylib_function(scope, dylib, export).into_value(),
+    );
+  }
+  exports.set(
+    new_string(scope, path.file_stem().unwrap().to_str().unwrap()).into_value(),
+    dylib.clone().into_value(),
+  );
+}
+
+/// Returns the platform file name of the native library `name`, `libname.so`
+/// on Linux, `libname.dylib` on macOS and `name.dll` on Windows.
+#[inline]
+pub fn native_library_filename(name: &str) -> String {
+  format!("{}{}{}", DLL_PREFIX, name, DLL_SUFFIX)
+}
+
+/// Creates the default native search path from `LISP_NATIVE_PATH` followed by
+/// the builder output directory.
+#[inline]
+pub fn new_native_path(scope: &Gc<Object<Scope>>) -> Gc<Object<Vector>> {
+  let mut native_path = new_vector(scope);
+
+  if let Some(paths) = var_os(NATIVE_PATH_ENV) {
+    for path in split_paths(&paths) {
+      if let Some(path) = path.to_str() {
+        native_path.push(new_string(scope, path).into_value());
+      }
     }
+  }
+  native_path.push(new_string(scope, NATIVE_BUILDER_OUT).into_value());
+
+  native_path
+}
+
+/// Returns the directories searched for `native:` imports from `module`, its
+/// dirname, the builder output directory next to it, then the module's
+/// `native_path`.
+#[inline]
+pub fn native_search_path(scope: &Gc<Object<Scope>>, module: &Gc<Object<Map>>) -> Vec<PathBuf> {
+  let dirname = module_dirname(scope, module);
+  let mut search_path = vec![dirname.clone(), dirname.join(NATIVE_BUILDER_OUT)];
+
+  if let Some(native_path) = module
+    .get(&new_string(scope, "native_path").into_value())
+    .and_then(|native_path| native_path.downcast_ref::<Object<Vector>>())
+  {
+    for path in native_path.iter() {
+      if let Some(path) = path.downcast_ref::<Object<String>>() {
+        search_path.push(PathBuf::from(path.value()));
+      }
+    }
+  }
+
+  search_path
+}
+
+/// Resolves `native:name` specifiers across the native search path, and
+/// paths to shared libraries relative to the module.
+#[inline]
+pub fn resolve_native_library(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+  filename: &str,
+) -> Result<Option<PathBuf>, String> {
+  let (name, candidates) = if filename.starts_with(NATIVE_PREFIX) {
+    let name = &filename[NATIVE_PREFIX.len()..];
+    let library = native_library_filename(name);
+
+    (
+      name,
+      native_search_path(scope, module)
+        .into_iter()
+        .map(|path| path.join(&library))
+        .collect::<Vec<PathBuf>>(),
+    )
+  } else if NATIVE_EXTENSIONS
+    .iter()
+    .any(|extension| filename.ends_with(extension))
+  {
+    (filename, vec![module_dirname(scope, module).join(filename)])
   } else {
-    None
+    return Ok(None);
+  };
+
+  for path in candidates.iter() {
+    if path.is_file() {
+      return canonicalize(path)
+        .map(Some)
+        .map_err(|error| format!("failed to resolve {}: {}", path.display(), error));
+    }
   }
+
+  Err(format!(
+    "failed to find native library {}, tried:\n{}",
+    name,
+    candidates
+      .iter()
+      .map(|path| format!("  {}", path.display()))
+      .collect::<Vec<String>>()
+      .join("\n")
+  ))
+}
+
+#[inline]
+fn module_dirname(scope: &Gc<Object<Scope>>, module: &Gc<Object<Map>>) -> PathBuf {
+  module
+    .get(&new_string(scope, "dirname").into_value())
+    .and_then(|dirname| dirname.downcast_ref::<Object<String>>())
+    .map(|dirname| PathBuf::from(dirname.value()))
+    .expect("parent dirname is nil")
 }
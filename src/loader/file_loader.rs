@@ -1,28 +1,30 @@
-use std::fs::{canonicalize, read_to_string};
 use std::path::Path;
 
 use gc::Gc;
 use runtime::{
-  add_external_macro, get_scope_root, new_map, new_scope, new_string, nil_value, run_in_scope,
-  scope_set, Map, Object, Scope, Stack, Value, Vector,
+  from_argument, get_scope_root, is_error_pending, new_map, new_scope, new_string, new_u64,
+  nil_value, run_in_scope, scope_set, throw_error, IntoValue, Map, Object, Scope, Value, Vector,
 };
 
-use super::super::{export, import, new_module};
+use super::super::{module_loading, new_module};
+use super::module_source;
 
 #[inline]
 pub fn file_loader_lisp_fn(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
-  let module_value = args.get(0).expect("module not passed to file_loader");
-  let module = module_value
-    .downcast_ref::<Object<Map>>()
-    .expect("Failed to downcast module to Map");
-  let filename_value = args.get(1).expect("filename not passed to file_loader");
-  let filename = filename_value
-    .downcast_ref::<Object<String>>()
-    .expect("Failed to downcast filename to String");
-
-  file_loader(scope, module, filename.value())
-    .map(|module| module.into_value())
-    .unwrap_or_else(|| nil_value(scope).clone().into_value())
+  let module = match from_argument::<Gc<Object<Map>>>(scope, args, 0) {
+    Ok(module) => module,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let filename = match from_argument::<String>(scope, args, 1) {
+    Ok(filename) => filename,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+
+  match file_loader(scope, &module, &filename) {
+    Ok(Some(module)) => module.into_value(),
+    Ok(None) => nil_value(scope).clone().into_value(),
+    Err(error) => throw_error(scope, error.into_value(scope)),
+  }
 }
 
 #[inline]
@@ -30,7 +32,7 @@ pub fn file_loader(
   scope: &Gc<Object<Scope>>,
   parent_module: &Gc<Object<Map>>,
   filename: &String,
-) -> Option<Gc<Object<Map>>> {
+) -> Result<Option<Gc<Object<Map>>>, String> {
   if filename.starts_with(".") || filename.starts_with("/") || filename.starts_with("\\") {
     let parent_dirname_string = new_string(scope, "dirname").into_value();
     let parent_dirname = parent_module
@@ -48,8 +50,9 @@ pub fn file_loader(
     }
 
     let parent_dirname_path = Path::new(parent_dirname.value());
-    let path =
-      canonicalize(parent_dirname_path.join(filename_path)).expect("failed to find local path");
+    let path = module_source(scope, parent_module)
+      .canonicalize(&parent_dirname_path.join(filename_path))
+      .map_err(|error| format!("failed to find module {:?}: {}", filename, error))?;
     let path_value = new_string(scope, path.clone().to_str().unwrap()).into_value();
 
     let mut cache = parent_module
@@ -59,70 +62,77 @@ pub fn file_loader(
       .unwrap_or_else(|| new_map(scope));
 
     if cache.has(&path_value) {
-      Some(
+      Ok(Some(
         cache
           .get(&path_value)
           .and_then(|cache| cache.downcast_ref::<Object<Map>>())
           .map(Clone::clone)
           .expect("failed to get module from cache"),
-      )
+      ))
     } else {
-      let mut module = new_module(scope, Some(parent_module.clone()));
-      let module_scope = new_scope(get_scope_root(scope));
-
-      Stack::init_scope(&module_scope);
-
+      let module = new_module(scope, Some(parent_module.clone()));
       cache.set(path_value.clone(), module.clone().into_value());
-
-      module.set(
-        new_string(scope, "filename").into_value(),
-        path_value.clone(),
-      );
-      module.set(
-        new_string(scope, "dirname").into_value(),
-        new_string(
-          scope,
-          path
-            .parent()
-            .unwrap_or(Path::new(""))
-            .to_str()
-            .unwrap_or(""),
-        )
-        .into_value(),
-      );
-
-      scope_set(&module_scope, "module", module.clone().into_value());
-      scope_set(&module_scope, "__filename", path_value.clone());
-      scope_set(
-        &module_scope,
-        "__dirname",
-        new_string(
-          scope,
-          path
-            .parent()
-            .unwrap_or_else(|| Path::new(""))
-            .to_str()
-            .unwrap(),
-        )
-        .into_value(),
-      );
-
-      add_external_macro(
-        &module_scope,
-        "import",
-        vec!["...imports", "module_path"],
-        import,
-      );
-      add_external_macro(&module_scope, "export", vec!["...exports"], export);
-
-      run_in_scope(
-        &module_scope,
-        read_to_string(path.clone()).expect("failed to load local path"),
-      );
-
-      Some(module)
+      let result = run_module(scope, module.clone(), &path);
+
+      // a module that failed to run is not cached, importing it again runs it
+      // again
+      if result.is_err() || is_error_pending(scope) {
+        cache.remove(&path_value);
+      }
+      result.map(|_| Some(module))
     }
   } else {
-    None
+    Ok(None)
   }
 }
+
+/// Runs the file at `path` in a fresh scope with `module` bound as its
+/// module, `path` has to be canonical. Fails when the file can not be read,
+/// errors the module throws are left pending.
+#[inline]
+pub fn run_module(
+  scope: &Gc<Object<Scope>>,
+  mut module: Gc<Object<Map>>,
+  path: &Path,
+) -> Result<(), String> {
+  let path_value = new_string(scope, path.to_str().unwrap()).into_value();
+  let dirname_value = new_string(
+    scope,
+    path
+      .parent()
+      .unwrap_or_else(|| Path::new(""))
+      .to_str()
+      .unwrap(),
+  )
+  .into_value();
+  let module_scope = new_scope(get_scope_root(scope));
+  let source = module_source(scope, &module);
+  let contents = source
+    .read_to_string(path)
+    .map_err(|error| format!("failed to read module {:?}: {}", path, error))?;
+
+  module.set(
+    new_string(scope, "filename").into_value(),
+    path_value.clone(),
+  );
+  module.set(
+    new_string(scope, "dirname").into_value(),
+    dirname_value.clone(),
+  );
+  module.set(
+    new_string(scope, "mtime").into_value(),
+    new_u64(scope, source.mtime(path).unwrap_or(0)).into_value(),
+  );
+
+  scope_set(&module_scope, "module", module.clone().into_value());
+  scope_set(&module_scope, "__filename", path_value.clone());
+  scope_set(&module_scope, "__dirname", dirname_value);
+
+  let mut loading = module_loading(scope, &module).expect("loading is not defined on module");
+  loading.push(path_value.clone());
+
+  run_in_scope(&module_scope, contents);
+
+  loading.retain(|loading_path| loading_path != &path_value);
+  Ok(())
+}
@@ -1,14 +1,50 @@
+use std::ops::Deref;
+
 use gc::Gc;
-use runtime::{call_function, new_string, new_vector, Function, Map, Object, Scope, Vector};
+use runtime::{
+  call_function, from_argument, is_error_pending, new_string, new_vector, nil_value,
+  scope_get_with_kind, throw_error, Function, IntoValue, Keyword, Map, Object, Scope, Symbol,
+  Value, Vector,
+};
+
+use super::new_module;
 
+mod cargo_loader;
+mod data_loader;
 mod dylib;
 mod dylib_loader;
 mod file_loader;
+mod package_loader;
+mod plugin;
+mod source;
+#[cfg(feature = "bundled-std")]
+mod std_loader;
 
+pub use self::cargo_loader::*;
+pub use self::data_loader::*;
 pub use self::dylib::*;
 pub use self::dylib_loader::*;
 pub use self::file_loader::*;
+pub use self::package_loader::*;
+pub use self::plugin::*;
+pub use self::source::*;
+#[cfg(feature = "bundled-std")]
+pub use self::std_loader::*;
+
+/// Where `add_loader` puts a loader, `Before` and `After` name an existing
+/// loader like `file_loader`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoaderPosition {
+  First,
+  Last,
+  Before(String),
+  After(String),
+}
 
+/// Loaders are called in order with `[module filename]`, the importing module
+/// and the string passed to `import`, and return a module map or nil to let
+/// the next loader try. A loader that throws stops the search, the error is
+/// thrown from `import`.
 #[inline]
 pub fn load(
   scope: &Gc<Object<Scope>>,
@@ -32,6 +68,10 @@ pub fn load(
     loader_args.push(filename.clone().into_value());
 
     let result_value = call_function(scope, loader.clone(), loader_args);
+
+    if is_error_pending(scope) {
+      return None;
+    }
     let result = result_value.downcast_ref::<Object<Map>>();
 
     if result.is_some() {
@@ -41,3 +81,113 @@ pub fn load(
 
   None
 }
+
+/// Adds `loader` to the loaders shared by `module` and every module loaded
+/// from it.
+#[inline]
+pub fn add_loader(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+  loader: Gc<Object<Function>>,
+  position: LoaderPosition,
+) -> Result<(), String> {
+  let mut loaders = module
+    .get(&new_string(scope, "loaders").into_value())
+    .expect("Loaders is not defined in the current module")
+    .downcast_ref::<Object<Vector>>()
+    .expect("Failed to downcast loaders to Vector")
+    .clone();
+  let index = match position {
+    LoaderPosition::First => 0,
+    LoaderPosition::Last => loaders.len(),
+    LoaderPosition::Before(ref name) | LoaderPosition::After(ref name) => {
+      let index = loaders
+        .iter()
+        .position(|loader| loader_name(loader).as_ref() == Some(name))
+        .ok_or_else(|| format!("no loader named {}", name))?;
+
+      if let LoaderPosition::After(_) = position {
+        index + 1
+      } else {
+        index
+      }
+    }
+  };
+
+  loaders.insert(index, loader.into_value());
+  Ok(())
+}
+
+/// `(module.add-loader f)` runs `f` before the other loaders,
+/// `(module.add-loader f :before :file_loader)` or `:after` places it next to
+/// a named loader, given as a keyword, string or quoted symbol.
+/// `module.add_loader` is the same function.
+#[inline]
+pub fn module_add_loader(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let loader = match from_argument::<Gc<Object<Function>>>(scope, args, 0) {
+    Ok(loader) => loader,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let position = match loader_position(args) {
+    Ok(position) => position,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let module = scope_get_with_kind::<Map>(scope, "module").expect("module is not defined");
+
+  match add_loader(scope, &module, loader, position) {
+    Ok(()) => nil_value(scope).clone().into_value(),
+    Err(error) => throw_error(scope, error.into_value(scope)),
+  }
+}
+
+/// `(module.new parent)` returns an empty module sharing the cache and
+/// loaders of `parent`, for loaders written in lisp.
+#[inline]
+pub fn module_new(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  match from_argument::<Gc<Object<Map>>>(scope, args, 0) {
+    Ok(parent) => new_module(scope, Some(parent)).into_value(),
+    Err(error) => throw_error(scope, error.into_value(scope)),
+  }
+}
+
+#[inline]
+fn loader_position(args: &Gc<Object<Vector>>) -> Result<LoaderPosition, String> {
+  let option = match args.get(1) {
+    Some(option) => option
+      .downcast_ref::<Object<Keyword>>()
+      .ok_or_else(|| format!("expected :before or :after, found {:?}", option))?,
+    None => return Ok(LoaderPosition::First),
+  };
+  let name = args
+    .get(2)
+    .and_then(|name| {
+      name
+        .downcast_ref::<Object<Symbol>>()
+        .map(|symbol| symbol.value().deref().clone())
+        .or_else(|| {
+          name
+            .downcast_ref::<Object<Keyword>>()
+            .map(|keyword| keyword.value().deref().clone())
+        })
+        .or_else(|| {
+          name
+            .downcast_ref::<Object<String>>()
+            .map(|string| string.value().clone())
+        })
+    })
+    .ok_or_else(|| "expected a loader name".to_owned())?;
+
+  match option.value().deref().as_str() {
+    "before" => Ok(LoaderPosition::Before(name)),
+    "after" => Ok(LoaderPosition::After(name)),
+    other => Err(format!("unknown loader position :{}", other)),
+  }
+}
+
+#[inline]
+fn loader_name(loader: &Gc<dyn Value>) -> Option<String> {
+  loader
+    .downcast_ref::<Object<Function>>()
+    .and_then(|loader| loader.value().name())
+    .map(|name| name.value().deref().clone())
+}
@@ -0,0 +1,284 @@
+use std::env::{split_paths, var_os};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use gc::Gc;
+use runtime::{
+  from_argument, new_keyword, new_string, nil_value, throw_error, IntoValue, Keyword, Map, Object,
+  Scope, Value, Vector,
+};
+
+use super::super::{Version, VersionReq};
+use super::{file_loader, module_source, NATIVE_PREFIX};
+
+pub const PACKAGE_MANIFEST: &'static str = "package.lisp";
+pub const PACKAGE_PATH_ENV: &'static str = "LISP_PATH";
+pub const PACKAGE_INSTALL_DIR: &'static str = "lisp_packages";
+
+#[inline]
+pub fn package_loader_lisp_fn(
+  scope: &Gc<Object<Scope>>,
+  args: &Gc<Object<Vector>>,
+) -> Gc<dyn Value> {
+  let module = match from_argument::<Gc<Object<Map>>>(scope, args, 0) {
+    Ok(module) => module,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let filename = match from_argument::<String>(scope, args, 1) {
+    Ok(filename) => filename,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+
+  match package_loader(scope, &module, &filename) {
+    Ok(Some(module)) => module.into_value(),
+    Ok(None) => nil_value(scope).clone().into_value(),
+    Err(error) => throw_error(scope, error.into_value(scope)),
+  }
+}
+
+#[inline]
+pub fn package_loader(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  filename: &String,
+) -> Result<Option<Gc<Object<Map>>>, String> {
+  match resolve_package_module(scope, parent_module, filename)? {
+    Some(path) => file_loader(scope, parent_module, &path.to_str().unwrap().to_owned()),
+    None => Ok(None),
+  }
+}
+
+#[inline]
+pub fn is_package_specifier(filename: &str) -> bool {
+  !(filename.is_empty()
+    || filename.starts_with(".")
+    || filename.starts_with("/")
+    || filename.starts_with("\\")
+    || filename.starts_with(NATIVE_PREFIX))
+}
+
+/// Resolves `name` or `name/path` to `src/lib.lisp` or `src/path.lisp` of
+/// the package `name`, found in the `:deps` of each `package.lisp` above the
+/// module's directory, by `:path` or installed in `lisp_packages`, or as a
+/// directory in `LISP_PATH`.
+#[inline]
+pub fn resolve_package_module(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  filename: &str,
+) -> Result<Option<PathBuf>, String> {
+  if !is_package_specifier(filename) {
+    return Ok(None);
+  }
+
+  let (name, module_path) = match filename.find('/') {
+    Some(index) => (&filename[..index], &filename[(index + 1)..]),
+    None => (filename, ""),
+  };
+  let mut consulted = Vec::new();
+
+  let package_dir = match find_package_dir(scope, parent_module, name, &mut consulted)? {
+    Some(package_dir) => package_dir,
+    None => {
+      return Err(format!(
+        "failed to resolve package {} for {:?}, consulted:\n  {}",
+        name,
+        filename,
+        if consulted.is_empty() {
+          format!("no {} or {}", PACKAGE_MANIFEST, PACKAGE_PATH_ENV)
+        } else {
+          consulted.join("\n  ")
+        }
+      ))
+    }
+  };
+
+  let mut path = package_dir.join("src");
+  if module_path.is_empty() {
+    path.push("lib.lisp");
+  } else {
+    path.push(module_path);
+    if path.extension().is_none() {
+      path.set_extension("lisp");
+    }
+  }
+
+  if module_source(scope, parent_module).is_file(&path) {
+    Ok(Some(path))
+  } else {
+    Err(format!(
+      "package {} at {:?} has no module {:?}",
+      name, package_dir, path
+    ))
+  }
+}
+
+#[inline]
+fn find_package_dir(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  name: &str,
+  consulted: &mut Vec<String>,
+) -> Result<Option<PathBuf>, String> {
+  let dirname = parent_module
+    .get(&new_string(scope, "dirname").into_value())
+    .and_then(|dirname| dirname.downcast_ref::<Object<String>>())
+    .map(|dirname| dirname.value().clone())
+    .unwrap_or_else(|| ".".to_owned());
+  let source = module_source(scope, parent_module);
+  let dirname = source
+    .canonicalize(Path::new(&dirname))
+    .unwrap_or_else(|_| PathBuf::from(dirname));
+
+  for dir in dirname.ancestors() {
+    let manifest = dir.join(PACKAGE_MANIFEST);
+
+    if !source.is_file(&manifest) {
+      continue;
+    }
+    consulted.push(manifest.to_str().unwrap().to_owned());
+
+    let package = read_package_manifest(scope, parent_module, &manifest)?;
+
+    if map_get_named(scope, &package, "name")
+      .map(|package_name| value_name(&package_name).as_deref() == Some(name))
+      .unwrap_or(false)
+    {
+      return Ok(Some(dir.to_path_buf()));
+    }
+
+    let dep = map_get_named(scope, &package, "deps")
+      .and_then(|deps| deps.downcast_ref::<Object<Map>>().map(Clone::clone))
+      .and_then(|deps| map_get_named(scope, &deps, name))
+      .and_then(|dep| dep.downcast_ref::<Object<Map>>().map(Clone::clone));
+    let path = dep
+      .as_ref()
+      .and_then(|dep| map_get_named(scope, dep, "path"))
+      .and_then(|path| value_name(&path));
+    let requirement = dep
+      .as_ref()
+      .and_then(|dep| map_get_named(scope, dep, "version"))
+      .and_then(|version| value_name(&version));
+    let installed_dir = dir.join(PACKAGE_INSTALL_DIR).join(name);
+
+    let package_dir = if dep.is_some() && path.is_none() && source.is_dir(&installed_dir) {
+      installed_dir
+    } else if let Some(path) = path {
+      let package_dir = dir.join(path);
+
+      match source.canonicalize(&package_dir) {
+        Ok(ref package_dir) if source.is_dir(package_dir) => package_dir.clone(),
+        _ => {
+          return Err(format!(
+            "package {} in {:?} has :path {:?} which is not a directory",
+            name, manifest, package_dir
+          ))
+        }
+      }
+    } else {
+      continue;
+    };
+
+    if let Some(requirement) = requirement {
+      check_package_version(
+        scope,
+        parent_module,
+        name,
+        &manifest,
+        &requirement,
+        &package_dir,
+      )?;
+    }
+    return Ok(Some(package_dir));
+  }
+
+  if let Some(lisp_path) = var_os(PACKAGE_PATH_ENV) {
+    for dir in split_paths(&lisp_path) {
+      let package_dir = dir.join(name);
+      consulted.push(format!(
+        "{} {}",
+        PACKAGE_PATH_ENV,
+        package_dir.to_str().unwrap_or("")
+      ));
+
+      if source.is_dir(&package_dir) {
+        return Ok(Some(package_dir));
+      }
+    }
+  }
+
+  Ok(None)
+}
+
+/// Checks the `:version` of the package in `package_dir` against the
+/// `requirement` that `manifest` has for it.
+#[inline]
+fn check_package_version(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  name: &str,
+  manifest: &Path,
+  requirement: &str,
+  package_dir: &Path,
+) -> Result<(), String> {
+  let requirement = VersionReq::parse(requirement)
+    .map_err(|error| format!("package {} in {:?} has {}", name, manifest, error))?;
+  let package_manifest = package_dir.join(PACKAGE_MANIFEST);
+  let version = read_package_manifest(scope, parent_module, &package_manifest)
+    .ok()
+    .and_then(|package| map_get_named(scope, &package, "version"))
+    .and_then(|version| value_name(&version))
+    .ok_or_else(|| format!("{:?} has no :version", package_manifest))?;
+  let version =
+    Version::parse(&version).map_err(|error| format!("{:?} has {}", package_manifest, error))?;
+
+  if requirement.matches(&version) {
+    Ok(())
+  } else {
+    Err(format!(
+      "package {} in {:?} requires version {} but {:?} is {}",
+      name, manifest, requirement, package_dir, version
+    ))
+  }
+}
+
+#[inline]
+fn read_package_manifest(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  manifest: &Path,
+) -> Result<Gc<Object<Map>>, String> {
+  file_loader(scope, parent_module, &manifest.to_str().unwrap().to_owned())?
+    .and_then(|module| {
+      module
+        .get(&new_string(scope, "exports").into_value())
+        .and_then(|exports| exports.downcast_ref::<Object<Map>>())
+        .and_then(|exports| exports.get(&new_string(scope, "package").into_value()))
+        .and_then(|package| package.downcast_ref::<Object<Map>>())
+        .map(Clone::clone)
+    })
+    .ok_or_else(|| format!("{:?} does not export a package map", manifest))
+}
+
+#[inline]
+fn map_get_named(
+  scope: &Gc<Object<Scope>>,
+  map: &Gc<Object<Map>>,
+  name: &str,
+) -> Option<Gc<dyn Value>> {
+  map
+    .get(&new_keyword(scope, name).into_value())
+    .or_else(|| map.get(&new_string(scope, name).into_value()))
+    .map(Clone::clone)
+}
+
+#[inline]
+fn value_name(value: &Gc<dyn Value>) -> Option<String> {
+  if let Some(string) = value.downcast_ref::<Object<String>>() {
+    Some(string.value().clone())
+  } else {
+    value
+      .downcast_ref::<Object<Keyword>>()
+      .map(|keyword| keyword.value().deref().clone())
+  }
+}
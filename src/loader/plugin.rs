@@ -0,0 +1,320 @@
+use std::mem::size_of;
+use std::os::raw::c_char;
+use std::slice;
+
+use gc::Gc;
+use plugin::{
+  LispContext, LispFunction, LispHost, LispType, LispValue, LISP_NULL, LISP_PLUGIN_ABI_VERSION,
+  LISP_TYPE_BOOL, LISP_TYPE_FLOAT, LISP_TYPE_FUNCTION, LISP_TYPE_INT, LISP_TYPE_INVALID,
+  LISP_TYPE_KEYWORD, LISP_TYPE_LIST, LISP_TYPE_MAP, LISP_TYPE_NIL, LISP_TYPE_OTHER,
+  LISP_TYPE_STRING, LISP_TYPE_SYMBOL, LISP_TYPE_VECTOR,
+};
+use runtime::{
+  new_keyword, new_scope, new_vector, nil_value, scope_set, FromValue, IntoValue, Keyword, Object,
+  Scope, Symbol, Value, Vector,
+};
+
+const PLUGIN_VALUES: &'static str = "__plugin_values";
+
+pub static LISP_HOST: LispHost = LispHost {
+  abi_version: LISP_PLUGIN_ABI_VERSION,
+  size: size_of::<LispHost>() as u32,
+  type_of: host_type_of,
+  nil: host_nil,
+  new_bool: host_new_bool,
+  new_int: host_new_int,
+  new_float: host_new_float,
+  new_string: host_new_string,
+  new_keyword: host_new_keyword,
+  new_vector: host_new_vector,
+  get_bool: host_get_bool,
+  get_int: host_get_int,
+  get_float: host_get_float,
+  get_string: host_get_string,
+  vector_len: host_vector_len,
+  vector_get: host_vector_get,
+  throw_error: host_throw_error,
+};
+
+struct PluginContext {
+  scope: Gc<Object<Scope>>,
+  // bound in `scope` as `__plugin_values`
+  values: Gc<Object<Vector>>,
+  error: Option<String>,
+}
+
+impl PluginContext {
+  #[inline]
+  fn push(&mut self, value: Gc<dyn Value>) -> LispValue {
+    self.values.push(value);
+    self.values.len() as LispValue
+  }
+  #[inline]
+  fn get(&self, value: LispValue) -> Option<&Gc<dyn Value>> {
+    if value == LISP_NULL {
+      None
+    } else {
+      self.values.get(value as usize - 1)
+    }
+  }
+}
+
+/// Calls a plugin function through the C ABI, `args` are passed as handles
+/// that stay valid until the function returns. The handles' values are bound
+/// in a scope of the call, a collection while the function runs keeps them.
+#[inline]
+pub fn call_plugin_function(
+  scope: &Gc<Object<Scope>>,
+  function: LispFunction,
+  args: &[Gc<dyn Value>],
+) -> Result<Gc<dyn Value>, String> {
+  let call_scope = new_scope(scope);
+  let mut values = new_vector(&call_scope);
+
+  values.extend(args.iter().cloned());
+  scope_set(&call_scope, PLUGIN_VALUES, values.clone().into_value());
+
+  let mut context = PluginContext {
+    scope: call_scope,
+    values,
+    error: None,
+  };
+  let handles = (1..=args.len() as LispValue).collect::<Vec<LispValue>>();
+
+  let result = unsafe {
+    function(
+      &LISP_HOST,
+      &mut context as *mut PluginContext as *mut LispContext,
+      handles.as_ptr(),
+      handles.len(),
+    )
+  };
+
+  if let Some(error) = context.error.take() {
+    Err(error)
+  } else {
+    context
+      .get(result)
+      .map(Clone::clone)
+      .ok_or_else(|| format!("plugin function returned invalid value {}", result))
+  }
+}
+
+#[inline]
+unsafe fn plugin_context<'a>(context: *mut LispContext) -> &'a mut PluginContext {
+  &mut *(context as *mut PluginContext)
+}
+
+#[inline]
+unsafe fn plugin_str(ptr: *const c_char, len: usize) -> String {
+  if len == 0 {
+    String::new()
+  } else {
+    String::from_utf8_lossy(slice::from_raw_parts(ptr as *const u8, len)).into_owned()
+  }
+}
+
+unsafe extern "C" fn host_type_of(context: *mut LispContext, value: LispValue) -> LispType {
+  match plugin_context(context).get(value) {
+    Some(value) => match value.kind().name().as_str() {
+      "Nil" => LISP_TYPE_NIL,
+      "Bool" => LISP_TYPE_BOOL,
+      "U8" | "U16" | "U32" | "U64" | "USize" | "I8" | "I16" | "I32" | "I64" | "ISize" => {
+        LISP_TYPE_INT
+      }
+      "F32" | "F64" => LISP_TYPE_FLOAT,
+      "String" => LISP_TYPE_STRING,
+      "Keyword" => LISP_TYPE_KEYWORD,
+      "Symbol" => LISP_TYPE_SYMBOL,
+      "Vector" => LISP_TYPE_VECTOR,
+      "List" => LISP_TYPE_LIST,
+      "Map" => LISP_TYPE_MAP,
+      "Function" | "Macro" => LISP_TYPE_FUNCTION,
+      _ => LISP_TYPE_OTHER,
+    },
+    None => LISP_TYPE_INVALID,
+  }
+}
+
+unsafe extern "C" fn host_nil(context: *mut LispContext) -> LispValue {
+  let context = plugin_context(context);
+  let value = nil_value(&context.scope).clone().into_value();
+  context.push(value)
+}
+
+unsafe extern "C" fn host_new_bool(context: *mut LispContext, value: bool) -> LispValue {
+  let context = plugin_context(context);
+  let value = value.into_value(&context.scope);
+  context.push(value)
+}
+
+unsafe extern "C" fn host_new_int(context: *mut LispContext, value: i64) -> LispValue {
+  let context = plugin_context(context);
+  let value = value.into_value(&context.scope);
+  context.push(value)
+}
+
+unsafe extern "C" fn host_new_float(context: *mut LispContext, value: f64) -> LispValue {
+  let context = plugin_context(context);
+  let value = value.into_value(&context.scope);
+  context.push(value)
+}
+
+unsafe extern "C" fn host_new_string(
+  context: *mut LispContext,
+  ptr: *const c_char,
+  len: usize,
+) -> LispValue {
+  let context = plugin_context(context);
+  let value = plugin_str(ptr, len).into_value(&context.scope);
+  context.push(value)
+}
+
+unsafe extern "C" fn host_new_keyword(
+  context: *mut LispContext,
+  ptr: *const c_char,
+  len: usize,
+) -> LispValue {
+  let context = plugin_context(context);
+  let value = new_keyword(&context.scope, plugin_str(ptr, len)).into_value();
+  context.push(value)
+}
+
+unsafe extern "C" fn host_new_vector(
+  context: *mut LispContext,
+  values: *const LispValue,
+  len: usize,
+) -> LispValue {
+  let context = plugin_context(context);
+  let handles = if len == 0 {
+    &[]
+  } else {
+    slice::from_raw_parts(values, len)
+  };
+  let mut vector = Vec::with_capacity(len);
+
+  for handle in handles {
+    match context.get(*handle) {
+      Some(value) => vector.push(value.clone()),
+      None => return LISP_NULL,
+    }
+  }
+
+  let value = vector.into_value(&context.scope);
+  context.push(value)
+}
+
+#[inline]
+unsafe fn host_get<T>(context: *mut LispContext, value: LispValue, out: *mut T) -> bool
+where
+  T: FromValue,
+{
+  let context = plugin_context(context);
+
+  match context
+    .get(value)
+    .and_then(|value| T::from_value(&context.scope, value).ok())
+  {
+    Some(value) => {
+      *out = value;
+      true
+    }
+    None => false,
+  }
+}
+
+unsafe extern "C" fn host_get_bool(
+  context: *mut LispContext,
+  value: LispValue,
+  out: *mut bool,
+) -> bool {
+  host_get(context, value, out)
+}
+
+unsafe extern "C" fn host_get_int(
+  context: *mut LispContext,
+  value: LispValue,
+  out: *mut i64,
+) -> bool {
+  host_get(context, value, out)
+}
+
+unsafe extern "C" fn host_get_float(
+  context: *mut LispContext,
+  value: LispValue,
+  out: *mut f64,
+) -> bool {
+  host_get(context, value, out)
+}
+
+unsafe extern "C" fn host_get_string(
+  context: *mut LispContext,
+  value: LispValue,
+  ptr: *mut *const c_char,
+  len: *mut usize,
+) -> bool {
+  let string: Option<&str> = plugin_context(context).get(value).and_then(|value| {
+    if let Some(string) = value.downcast_ref::<Object<String>>() {
+      Some(string.value().as_str())
+    } else if let Some(keyword) = value.downcast_ref::<Object<Keyword>>() {
+      Some(keyword.value().as_str())
+    } else if let Some(symbol) = value.downcast_ref::<Object<Symbol>>() {
+      Some(symbol.value().as_str())
+    } else {
+      None
+    }
+  });
+
+  match string {
+    Some(string) => {
+      *ptr = string.as_ptr() as *const c_char;
+      *len = string.len();
+      true
+    }
+    None => false,
+  }
+}
+
+unsafe extern "C" fn host_vector_len(
+  context: *mut LispContext,
+  value: LispValue,
+  out: *mut usize,
+) -> bool {
+  match plugin_context(context)
+    .get(value)
+    .and_then(|value| value.downcast_ref::<Object<Vector>>())
+  {
+    Some(vector) => {
+      *out = vector.len();
+      true
+    }
+    None => false,
+  }
+}
+
+unsafe extern "C" fn host_vector_get(
+  context: *mut LispContext,
+  value: LispValue,
+  index: usize,
+) -> LispValue {
+  let context = plugin_context(context);
+  let item = context
+    .get(value)
+    .and_then(|value| value.downcast_ref::<Object<Vector>>())
+    .and_then(|vector| vector.get(index))
+    .map(Clone::clone);
+
+  match item {
+    Some(item) => context.push(item),
+    None => LISP_NULL,
+  }
+}
+
+unsafe extern "C" fn host_throw_error(
+  context: *mut LispContext,
+  ptr: *const c_char,
+  len: usize,
+) -> LispValue {
+  plugin_context(context).error = Some(plugin_str(ptr, len));
+  LISP_NULL
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::UNIX_EPOCH;
+
+use gc::Gc;
+use runtime::{new_string, scope_get_with_kind, Kind, LispKind, Map, Object, Scope};
+
+/// Where loaders resolve and read lisp source files.
+pub trait ModuleSource {
+  /// Returns the absolute path of an existing file or directory.
+  fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+  fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+  fn is_file(&self, path: &Path) -> bool;
+
+  fn is_dir(&self, path: &Path) -> bool;
+
+  /// A stamp that changes when the file at `path` changes.
+  fn mtime(&self, path: &Path) -> Option<u64>;
+}
+
+/// Reads modules from the operating system's filesystem.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsModuleSource;
+
+impl ModuleSource for OsModuleSource {
+  #[inline]
+  fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+    fs::canonicalize(path)
+  }
+
+  #[inline]
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    fs::read_to_string(path)
+  }
+
+  #[inline]
+  fn is_file(&self, path: &Path) -> bool {
+    path.is_file()
+  }
+
+  #[inline]
+  fn is_dir(&self, path: &Path) -> bool {
+    path.is_dir()
+  }
+
+  #[inline]
+  fn mtime(&self, path: &Path) -> Option<u64> {
+    fs::metadata(path)
+      .and_then(|metadata| metadata.modified())
+      .ok()
+      .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+      .map(|duration| duration.as_nanos() as u64)
+  }
+}
+
+/// Modules held in memory by absolute path, directories exist when a file is
+/// inside them. Relative paths are resolved from `/`.
+#[derive(Debug, Default)]
+pub struct MemoryModuleSource {
+  files: RwLock<HashMap<PathBuf, (String, u64)>>,
+  version: AtomicU64,
+}
+
+impl MemoryModuleSource {
+  #[inline]
+  pub fn new() -> Self {
+    MemoryModuleSource::default()
+  }
+
+  #[inline]
+  pub fn with_file<P, S>(self, path: P, source: S) -> Self
+  where
+    P: AsRef<Path>,
+    S: ToString,
+  {
+    self.insert(path, source);
+    self
+  }
+
+  /// Adds or replaces the file at `path`.
+  #[inline]
+  pub fn insert<P, S>(&self, path: P, source: S)
+  where
+    P: AsRef<Path>,
+    S: ToString,
+  {
+    let version = self.version.fetch_add(1, Ordering::SeqCst) + 1;
+    self
+      .files
+      .write()
+      .unwrap()
+      .insert(normalize_path(path.as_ref()), (source.to_string(), version));
+  }
+
+  #[inline]
+  pub fn remove<P>(&self, path: P) -> Option<String>
+  where
+    P: AsRef<Path>,
+  {
+    self
+      .files
+      .write()
+      .unwrap()
+      .remove(&normalize_path(path.as_ref()))
+      .map(|(source, _)| source)
+  }
+}
+
+impl ModuleSource for MemoryModuleSource {
+  #[inline]
+  fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+    let path = normalize_path(path);
+
+    if self.is_file(&path) || self.is_dir(&path) {
+      Ok(path)
+    } else {
+      Err(not_found(&path))
+    }
+  }
+
+  #[inline]
+  fn read_to_string(&self, path: &Path) -> io::Result<String> {
+    let path = normalize_path(path);
+
+    self
+      .files
+      .read()
+      .unwrap()
+      .get(&path)
+      .map(|&(ref source, _)| source.clone())
+      .ok_or_else(|| not_found(&path))
+  }
+
+  #[inline]
+  fn is_file(&self, path: &Path) -> bool {
+    self
+      .files
+      .read()
+      .unwrap()
+      .contains_key(&normalize_path(path))
+  }
+
+  #[inline]
+  fn is_dir(&self, path: &Path) -> bool {
+    let path = normalize_path(path);
+
+    self
+      .files
+      .read()
+      .unwrap()
+      .keys()
+      .any(|file| file != &path && file.starts_with(&path))
+  }
+
+  #[inline]
+  fn mtime(&self, path: &Path) -> Option<u64> {
+    self
+      .files
+      .read()
+      .unwrap()
+      .get(&normalize_path(path))
+      .map(|&(_, version)| version)
+  }
+}
+
+/// The `ModuleSource` of a module, shared with every module loaded from it.
+#[derive(LispKind)]
+#[lisp(crate = "runtime")]
+pub struct ModuleSources {
+  source: Arc<dyn ModuleSource>,
+}
+
+impl fmt::Debug for ModuleSources {
+  #[inline]
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("ModuleSource")
+  }
+}
+
+#[inline]
+pub fn module_sources_kind(scope: &Gc<Object<Scope>>) -> Gc<Object<Kind>> {
+  ModuleSources::lisp_kind(scope)
+}
+
+#[inline]
+pub fn new_module_sources(
+  scope: &Gc<Object<Scope>>,
+  source: Arc<dyn ModuleSource>,
+) -> Gc<Object<ModuleSources>> {
+  ModuleSources { source }.into_object(scope)
+}
+
+/// The source `module` loads from, the filesystem unless one was set.
+#[inline]
+pub fn module_source(scope: &Gc<Object<Scope>>, module: &Gc<Object<Map>>) -> Arc<dyn ModuleSource> {
+  module
+    .get(&new_string(scope, "source").into_value())
+    .and_then(|source| source.downcast_ref::<Object<ModuleSources>>())
+    .map(|sources| sources.source.clone())
+    .unwrap_or_else(|| Arc::new(OsModuleSource))
+}
+
+/// Makes the modules loaded from now on in `scope` read from `source`,
+/// modules that were already loaded keep their source.
+#[inline]
+pub fn set_module_source(scope: &Gc<Object<Scope>>, source: Arc<dyn ModuleSource>) {
+  let mut module = scope_get_with_kind::<Map>(scope, "module").expect("module is not defined");
+  module.set(
+    new_string(scope, "source").into_value(),
+    new_module_sources(scope, source).into_value(),
+  );
+}
+
+#[inline]
+fn normalize_path(path: &Path) -> PathBuf {
+  let mut normalized = PathBuf::from("/");
+
+  for component in path.components() {
+    match component {
+      Component::ParentDir => {
+        normalized.pop();
+      }
+      Component::Normal(part) => normalized.push(part),
+      Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+    }
+  }
+  normalized
+}
+
+#[inline]
+fn not_found(path: &Path) -> io::Error {
+  io::Error::new(
+    io::ErrorKind::NotFound,
+    format!("{} is not in the module source", path.display()),
+  )
+}
@@ -0,0 +1,150 @@
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+use gc::Gc;
+use runtime::{
+  from_argument, is_error_pending, new_map, new_string, nil_value, throw_error, IntoValue, Map,
+  Object, Scope, Value, Vector,
+};
+
+use super::super::new_module;
+use super::{new_module_sources, run_module, MemoryModuleSource, ModuleSource};
+
+pub const STD_PREFIX: &'static str = "std/";
+/// Where the bundled std is mounted in its module source.
+pub const STD_ROOT: &'static str = "/std";
+
+const STD_FILES: [(&'static str, &'static str); 7] = [
+  (
+    "package.lisp",
+    include_str!("../../packages/std/package.lisp"),
+  ),
+  (
+    "core/package.lisp",
+    include_str!("../../packages/std/core/package.lisp"),
+  ),
+  (
+    "core/src/option.lisp",
+    include_str!("../../packages/std/core/src/option.lisp"),
+  ),
+  (
+    "fs/package.lisp",
+    include_str!("../../packages/std/fs/package.lisp"),
+  ),
+  (
+    "fs/src/lib.lisp",
+    include_str!("../../packages/std/fs/src/lib.lisp"),
+  ),
+  (
+    "num/package.lisp",
+    include_str!("../../packages/std/num/package.lisp"),
+  ),
+  (
+    "num/src/lib.lisp",
+    include_str!("../../packages/std/num/src/lib.lisp"),
+  ),
+];
+
+#[inline]
+pub fn std_loader_lisp_fn(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let module = match from_argument::<Gc<Object<Map>>>(scope, args, 0) {
+    Ok(module) => module,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let filename = match from_argument::<String>(scope, args, 1) {
+    Ok(filename) => filename,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+
+  match std_loader(scope, &module, &filename) {
+    Ok(Some(module)) => module.into_value(),
+    Ok(None) => nil_value(scope).clone().into_value(),
+    Err(error) => throw_error(scope, error.into_value(scope)),
+  }
+}
+
+/// Loads `std/name` or `std/name/path` from the std packages bundled into
+/// the binary, `src/lib.lisp` or `src/path.lisp` of the package `name`.
+#[inline]
+pub fn std_loader(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  filename: &String,
+) -> Result<Option<Gc<Object<Map>>>, String> {
+  let path = match std_module_path(filename) {
+    Some(path) => path,
+    None => return Ok(None),
+  };
+  let source = std_source();
+
+  if !source.is_file(&path) {
+    return Err(format!("no module {:?} in the bundled std", filename));
+  }
+
+  let path_value = new_string(scope, path.to_str().unwrap()).into_value();
+  let mut cache = parent_module
+    .get(&new_string(scope, "cache").into_value())
+    .and_then(|cache| cache.downcast_ref::<Object<Map>>())
+    .map(Clone::clone)
+    .unwrap_or_else(|| new_map(scope));
+
+  if let Some(module) = cache
+    .get(&path_value)
+    .and_then(|module| module.downcast_ref::<Object<Map>>())
+  {
+    return Ok(Some(module.clone()));
+  }
+
+  let mut module = new_module(scope, Some(parent_module.clone()));
+  module.set(
+    new_string(scope, "source").into_value(),
+    new_module_sources(scope, source).into_value(),
+  );
+  cache.set(path_value.clone(), module.clone().into_value());
+  let result = run_module(scope, module.clone(), &path);
+
+  if result.is_err() || is_error_pending(scope) {
+    cache.remove(&path_value);
+  }
+  result.map(|_| Some(module))
+}
+
+/// The bundled std files, mounted at `STD_ROOT`.
+#[inline]
+pub fn std_source() -> Arc<MemoryModuleSource> {
+  static SOURCE: OnceLock<Arc<MemoryModuleSource>> = OnceLock::new();
+
+  SOURCE
+    .get_or_init(|| {
+      let source = MemoryModuleSource::new();
+      for &(path, content) in STD_FILES.iter() {
+        source.insert(PathBuf::from(STD_ROOT).join(path), content);
+      }
+      Arc::new(source)
+    })
+    .clone()
+}
+
+#[inline]
+fn std_module_path(filename: &str) -> Option<PathBuf> {
+  let rest = filename.strip_prefix(STD_PREFIX)?;
+  let (name, module_path) = match rest.find('/') {
+    Some(index) => (&rest[..index], &rest[(index + 1)..]),
+    None => (rest, ""),
+  };
+
+  if name.is_empty() {
+    return None;
+  }
+
+  let mut path = PathBuf::from(STD_ROOT).join(name).join("src");
+  if module_path.is_empty() {
+    path.push("lib.lisp");
+  } else {
+    path.push(module_path);
+    if path.extension().is_none() {
+      path.set_extension("lisp");
+    }
+  }
+  Some(path)
+}
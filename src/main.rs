@@ -1,35 +1,157 @@
 extern crate clap;
 extern crate lisp;
 
-use clap::{Arg, Command};
-use std::{fs::canonicalize, io};
+use clap::{Arg, ArgMatches, Command};
+use std::{
+  any::Any,
+  fs::canonicalize,
+  io::{self, Read},
+  panic::{self, AssertUnwindSafe},
+  path::Path,
+  process,
+};
 
 const NAME: &'static str = env!("CARGO_PKG_NAME");
 const DESCRIPTION: &'static str = env!("CARGO_PKG_DESCRIPTION");
 const AUTHORS: &'static str = env!("CARGO_PKG_AUTHORS");
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-fn main() -> io::Result<()> {
+const UNCAUGHT_ERROR: &'static str = "Uncaught Error: ";
+const STDIN_INPUT: &'static str = "-";
+
+fn main() {
   let matches = Command::new(NAME)
     .version(VERSION)
     .author(AUTHORS)
     .about(DESCRIPTION)
+    .trailing_var_arg(true)
+    .allow_hyphen_values(true)
     .arg(
       Arg::new("input")
         .alias("input")
         .index(1)
         .required(false)
-        .help("Sets the input file to use"),
+        .help("Sets the input file to use, - reads the program from stdin"),
+    )
+    .arg(
+      Arg::new("args")
+        .index(2)
+        .multiple_values(true)
+        .help("Arguments passed to the program in argv"),
+    )
+    .arg(
+      Arg::new("eval")
+        .short('e')
+        .long("eval")
+        .takes_value(true)
+        .help(
+          "Evaluates the expressions instead of an input file, the input is passed in argv and \
+           not run",
+        ),
+    )
+    .arg(
+      Arg::new("image")
+        .long("image")
+        .takes_value(true)
+        .help("Restores the context from an image instead of bootstrapping it"),
+    )
+    .arg(
+      Arg::new("save-image")
+        .long("save-image")
+        .takes_value(true)
+        .help("Writes the context to an image after running the input file"),
+    )
+    .arg(
+      Arg::new("watch")
+        .long("watch")
+        .help("Reloads imported modules when their files change"),
     )
     .get_matches();
 
-  let scope = lisp::new();
-  if let Some(input_file) = matches.value_of("input") {
-    lisp::run_path(
+  // uncaught lisp errors unwind as panics, report them without the panic
+  // location and exit with 1
+  let default_hook = panic::take_hook();
+  panic::set_hook(Box::new(move |info| {
+    match panic_message(info.payload()).and_then(|message| message.strip_prefix(UNCAUGHT_ERROR)) {
+      Some(error) => eprintln!("error: {}", error),
+      None => default_hook(info),
+    }
+  }));
+
+  let code = match panic::catch_unwind(AssertUnwindSafe(|| run(&matches))) {
+    Ok(Ok(())) => 0,
+    Ok(Err(error)) => {
+      eprintln!("error: {}", error);
+      1
+    }
+    Err(_) => 1,
+  };
+  process::exit(code);
+}
+
+fn run(matches: &ArgMatches) -> io::Result<()> {
+  let scope = if let Some(image_file) = matches.value_of("image") {
+    lisp::load_image(Path::new(image_file))?
+  } else {
+    lisp::new()
+  };
+  let args = matches.values_of("args").into_iter().flatten();
+
+  if let Some(source) = matches.value_of("eval") {
+    lisp::set_argv(
       &scope,
-      &canonicalize(input_file).expect("Failed to canonicalize input file"),
-    )
+      Some("-e")
+        .into_iter()
+        .chain(matches.value_of("input"))
+        .chain(args),
+    );
+    lisp::runtime::run_in_scope(&scope, source);
+
+    if matches.is_present("watch") {
+      return lisp::watch(&scope);
+    }
+  } else if let Some(input_file) = matches.value_of("input") {
+    lisp::set_argv(&scope, Some(input_file).into_iter().chain(args));
+
+    if input_file == STDIN_INPUT {
+      let mut source = String::new();
+      io::stdin().read_to_string(&mut source)?;
+      lisp::runtime::run_in_scope(&scope, source);
+    } else {
+      let path = canonicalize(input_file).map_err(|error| {
+        io::Error::new(
+          error.kind(),
+          format!("failed to read {}: {}", input_file, error),
+        )
+      })?;
+      lisp::run_path(&scope, &path)?;
+    }
+
+    if matches.is_present("watch") {
+      return lisp::watch(&scope);
+    }
+  } else if matches.is_present("watch") {
+    lisp::set_argv(&scope, args);
+    return lisp::repl_watch(&scope);
+  } else if !matches.is_present("save-image") {
+    lisp::set_argv(&scope, args);
+    return lisp::repl(&scope);
+  }
+
+  if let Some(image_file) = matches.value_of("save-image") {
+    lisp::save_image(&scope, Path::new(image_file))
   } else {
-    lisp::repl(&scope)
+    Ok(())
   }
 }
+
+fn panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+  payload
+    .downcast_ref::<String>()
+    .map(String::as_str)
+    .or_else(|| {
+      payload
+        .downcast_ref::<&'static str>()
+        .map(|message| *message)
+    })
+}
@@ -2,11 +2,18 @@ use std::{collections::LinkedList, ops::Deref};
 
 use gc::Gc;
 use runtime::{
-  get_scope_root, new_external_function, new_list_from, new_map, new_string, new_symbol,
-  new_vector, nil_value, scope_get, scope_parent, Map, Object, Scope, Symbol, Value, Vector,
+  call_function, get_scope_root, new_external_closure, new_external_function, new_list_from,
+  new_map, new_string, new_symbol, new_vector, nil_value, scope_get, scope_parent, throw_error,
+  Function, IntoValue, Keyword, Map, Object, Scope, Symbol, Value, Vector,
 };
 
-use super::{dylib_loader_lisp_fn, file_loader_lisp_fn, load};
+#[cfg(feature = "bundled-std")]
+use super::std_loader_lisp_fn;
+use super::{
+  add_importer, cargo_loader_lisp_fn, dylib_loader_lisp_fn, edn_loader_lisp_fn,
+  file_loader_lisp_fn, json_loader_lisp_fn, load, new_native_path, package_loader_lisp_fn,
+  DEFAULT_EXPORT,
+};
 
 #[inline]
 pub fn new_module(
@@ -66,8 +73,8 @@ pub fn new_module(
         let mut loaders = new_vector(scope);
 
         let mut params = new_vector(scope);
-        params.push(new_symbol(scope, "filename").into_value());
         params.push(new_symbol(scope, "module").into_value());
+        params.push(new_symbol(scope, "filename").into_value());
 
         // Order matters here
         loaders.push(
@@ -79,70 +86,368 @@ pub fn new_module(
           )
           .into_value(),
         );
+        loaders.push(
+          new_external_function(
+            scope,
+            Some(new_symbol(scope, "cargo_loader")),
+            params.clone(),
+            cargo_loader_lisp_fn,
+          )
+          .into_value(),
+        );
+        #[cfg(feature = "bundled-std")]
+        loaders.push(
+          new_external_function(
+            scope,
+            Some(new_symbol(scope, "std_loader")),
+            params.clone(),
+            std_loader_lisp_fn,
+          )
+          .into_value(),
+        );
+        loaders.push(
+          new_external_function(
+            scope,
+            Some(new_symbol(scope, "json_loader")),
+            params.clone(),
+            json_loader_lisp_fn,
+          )
+          .into_value(),
+        );
+        loaders.push(
+          new_external_function(
+            scope,
+            Some(new_symbol(scope, "edn_loader")),
+            params.clone(),
+            edn_loader_lisp_fn,
+          )
+          .into_value(),
+        );
         loaders.push(
           new_external_function(
             scope,
             Some(new_symbol(scope, "file_loader")),
-            params,
+            params.clone(),
             file_loader_lisp_fn,
           )
           .into_value(),
         );
+        loaders.push(
+          new_external_function(
+            scope,
+            Some(new_symbol(scope, "package_loader")),
+            params,
+            package_loader_lisp_fn,
+          )
+          .into_value(),
+        );
 
         loaders
       })
       .into_value(),
   );
+  let loading_string = new_string(scope, "loading").into_value();
+  module.set(
+    loading_string.clone(),
+    parent
+      .as_ref()
+      .and_then(|parent| parent.get(&loading_string))
+      .and_then(|loading| loading.downcast_ref::<Object<Vector>>())
+      .map(Clone::clone)
+      .unwrap_or_else(|| new_vector(scope))
+      .into_value(),
+  );
+  let source_string = new_string(scope, "source").into_value();
+  if let Some(source) = parent
+    .as_ref()
+    .and_then(|parent| parent.get(&source_string))
+    .map(Clone::clone)
+  {
+    module.set(source_string, source);
+  }
+  let native_path_string = new_string(scope, "native_path").into_value();
+  module.set(
+    native_path_string.clone(),
+    parent
+      .as_ref()
+      .and_then(|parent| parent.get(&native_path_string))
+      .and_then(|native_path| native_path.downcast_ref::<Object<Vector>>())
+      .map(Clone::clone)
+      .unwrap_or_else(|| new_native_path(scope))
+      .into_value(),
+  );
   module
 }
 
+enum ImportNames {
+  Only(Vec<Gc<Object<Symbol>>>),
+  All,
+}
+
+struct ImportSpec {
+  filename: Gc<Object<String>>,
+  alias: Option<Gc<Object<Symbol>>>,
+  names: Option<ImportNames>,
+  rename: Vec<(String, Gc<Object<Symbol>>)>,
+  /// Only `(import name "path")` binds the default export to a name the
+  /// module does not export.
+  default: bool,
+}
+
 #[inline]
-pub fn import(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+fn parse_import(args: &Gc<Object<Vector>>) -> Result<ImportSpec, String> {
+  let mut args = args.iter();
+  let first = args
+    .next()
+    .ok_or_else(|| "module path is required".to_owned())?;
+
+  if let Some(filename) = first.downcast_ref::<Object<String>>() {
+    let mut spec = ImportSpec {
+      filename: filename.clone(),
+      alias: None,
+      names: None,
+      rename: Vec::new(),
+      default: false,
+    };
+
+    while let Some(option) = args.next() {
+      let option = option
+        .downcast_ref::<Object<Keyword>>()
+        .ok_or_else(|| format!("expected an import option, found {:?}", option))?;
+
+      match option.value().deref().as_str() {
+        "as" => {
+          spec.alias = Some(import_symbol(args.next())?);
+        }
+        "all" => {
+          spec.names = Some(ImportNames::All);
+        }
+        "only" => {
+          let names = args
+            .next()
+            .and_then(|names| names.downcast_ref::<Object<Vector>>())
+            .ok_or_else(|| "expected a vector of names after :only".to_owned())?;
+          spec.names = Some(ImportNames::Only(
+            names
+              .iter()
+              .map(|name| import_symbol(Some(name)))
+              .collect::<Result<Vec<_>, String>>()?,
+          ));
+        }
+        "rename" => {
+          let rename = args
+            .next()
+            .and_then(|rename| rename.downcast_ref::<Object<Map>>())
+            .ok_or_else(|| "expected a map of names after :rename".to_owned())?;
+
+          for (from, to) in rename.iter() {
+            spec.rename.push((
+              import_symbol(Some(from))?.value().deref().clone(),
+              import_symbol(Some(to))?,
+            ));
+          }
+        }
+        other => return Err(format!("unknown import option :{}", other)),
+      }
+    }
+
+    Ok(spec)
+  } else {
+    let mut names = vec![import_symbol(Some(first))?];
+    let mut filename = None;
+
+    for arg in args {
+      if let Some(string) = arg.downcast_ref::<Object<String>>() {
+        filename = Some(string.clone());
+      } else {
+        names.push(import_symbol(Some(arg))?);
+      }
+    }
+
+    Ok(ImportSpec {
+      filename: filename.ok_or_else(|| "module path is required".to_owned())?,
+      alias: None,
+      default: names.len() == 1,
+      names: Some(ImportNames::Only(names)),
+      rename: Vec::new(),
+    })
+  }
+}
+
+#[inline]
+fn import_symbol(value: Option<&Gc<dyn Value>>) -> Result<Gc<Object<Symbol>>, String> {
+  match value {
+    Some(value) => value
+      .downcast_ref::<Object<Symbol>>()
+      .map(Clone::clone)
+      .ok_or_else(|| format!("expected a name, found {:?}", value)),
+    None => Err("expected a name".to_owned()),
+  }
+}
+
+struct ImportBinding {
+  name: String,
+  export: Option<String>,
+  value: Gc<dyn Value>,
+}
+
+struct ImportBindings {
+  filename: Gc<Object<String>>,
+  parent_module: Gc<Object<Map>>,
+  module: Gc<Object<Map>>,
+  bindings: Vec<ImportBinding>,
+}
+
+impl ImportBindings {
+  /// Lets a reload of the imported module update the bindings made in
+  /// `target`, a scope or the exports of the importing module.
+  #[inline]
+  fn add_importers(&self, scope: &Gc<Object<Scope>>, target: Gc<dyn Value>) {
+    for binding in self.bindings.iter() {
+      add_importer(
+        scope,
+        &self.module,
+        &self.parent_module,
+        target.clone(),
+        &binding.name,
+        binding.export.as_deref(),
+      );
+    }
+  }
+}
+
+/// Loads the module described by `args` and returns the names it binds, with
+/// their values, in the order they were requested.
+#[inline]
+fn import_bindings(
+  scope: &Gc<Object<Scope>>,
+  args: &Gc<Object<Vector>>,
+) -> Result<ImportBindings, String> {
+  let spec = parse_import(args)?;
   let parent_module = scope_get(scope, "module")
     .expect("module is not defined in the current Scope")
     .downcast_ref::<Object<Map>>()
     .expect("Failed to downcast current module to Scope")
     .clone();
-  let mut mut_args = args.value().clone();
-  let filename = mut_args
-    .pop_back()
-    .expect("filename is required")
-    .downcast_ref::<Object<String>>()
-    .expect("filed to downcast filename to String")
-    .clone();
 
   let root_scope = get_scope_root(scope);
-  let mut module = load(root_scope, parent_module, filename.clone())
-    .expect(&format!("No Loader found for {}", filename.value()));
-  let exports_value = module
-    .get_mut(&new_string(scope, "exports").into_value())
-    .expect("exports not defined in module");
-  let exports = exports_value
-    .downcast_mut::<Object<Map>>()
-    .expect("Failed to downcast exports to Map");
+  let module = load(root_scope, parent_module.clone(), spec.filename.clone())
+    .ok_or_else(|| format!("No Loader found for {}", spec.filename.value()))?;
+  let exports = module
+    .get(&new_string(scope, "exports").into_value())
+    .expect("exports not defined in module")
+    .downcast_ref::<Object<Map>>()
+    .expect("Failed to downcast exports to Map")
+    .clone();
+
+  let mut bindings = Vec::new();
+
+  if let Some(alias) = spec.alias {
+    bindings.push(ImportBinding {
+      name: alias.value().deref().clone(),
+      export: None,
+      value: exports.clone().into_value(),
+    });
+  }
+
+  let mut names = match spec.names {
+    Some(ImportNames::Only(names)) => names
+      .iter()
+      .map(|name| name.value().deref().clone())
+      .collect::<Vec<String>>(),
+    Some(ImportNames::All) => exports
+      .iter()
+      .filter_map(|(name, _)| name.downcast_ref::<Object<String>>())
+      .map(|name| name.value().clone())
+      .collect::<Vec<String>>(),
+    None => Vec::new(),
+  };
+  for &(ref from, _) in spec.rename.iter() {
+    if !names.contains(from) {
+      names.push(from.clone());
+    }
+  }
+
+  for name in names {
+    let default = new_string(scope, DEFAULT_EXPORT).into_value();
+    let (export, value) = match exports.get(&new_string(scope, &name).into_value()) {
+      Some(value) => (Some(name.clone()), value.clone()),
+      None if spec.default && exports.has(&default) => (
+        Some(DEFAULT_EXPORT.to_owned()),
+        exports.get(&default).unwrap().clone(),
+      ),
+      None => match import_cycle(scope, &parent_module, &module) {
+        Some(cycle) => (
+          None,
+          new_live_binding(scope, &exports, &name, cycle).into_value(),
+        ),
+        None => {
+          return Err(format!(
+            "no such import {:?} defined in {:?}",
+            name,
+            spec.filename.value()
+          ))
+        }
+      },
+    };
+    let local_name = spec
+      .rename
+      .iter()
+      .find(|&&(ref from, _)| from == &name)
+      .map(|&(_, ref to)| to.value().deref().clone())
+      .unwrap_or(name);
+
+    bindings.push(ImportBinding {
+      name: local_name,
+      export,
+      value,
+    });
+  }
+
+  Ok(ImportBindings {
+    filename: spec.filename,
+    parent_module,
+    module,
+    bindings,
+  })
+}
+
+/// `(import a b "path")`, `(import "path" :as m)`, `(import "path" :all)` and
+/// `(import "path" :only [a b] :rename {a x})`, warns when a name is already
+/// defined in the importing scope with a different value.
+#[inline]
+pub fn import(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let import = match import_bindings(scope, args) {
+    Ok(import) => import,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let caller_scope = scope_parent(scope).expect("failed to get caller scope");
+
+  import.add_importers(scope, caller_scope.clone().into_value());
 
   let mut list = LinkedList::new();
 
   list.push_back(new_symbol(scope, "do").into_value());
 
-  for import_name_value in mut_args.iter() {
-    let import_name = import_name_value
-      .downcast_ref::<Object<Symbol>>()
-      .expect(format!("failed to downcast {:?} to Symbol", import_name_value).as_str());
-    let import_value = exports
-      .get(&new_string(scope, import_name.value().deref()).into_value())
-      .expect(&format!(
-        "no such import {:?} defined in {:?}",
-        import_name.value().deref(),
-        filename.value()
-      ))
-      .clone();
+  for ImportBinding { name, value, .. } in import.bindings {
+    match caller_scope.get(&name) {
+      Some(ref existing) if !is_same_value(existing, &value) => eprintln!(
+        "warning: import of {} from {:?} overwrites an existing definition",
+        name,
+        import.filename.value()
+      ),
+      _ => {}
+    }
+
+    let mut quotelist = LinkedList::new();
+    quotelist.push_back(new_symbol(scope, "quote").into_value());
+    quotelist.push_back(value);
 
     let mut deflist = LinkedList::new();
 
     deflist.push_back(new_symbol(scope, "def").into_value());
-    deflist.push_back(import_name_value.clone());
-    deflist.push_back(import_value);
+    deflist.push_back(new_symbol(scope, name).into_value());
+    deflist.push_back(new_list_from(scope, quotelist.into()).into_value());
 
     list.push_back(new_list_from(scope, deflist.into()).into_value());
   }
@@ -150,6 +455,54 @@ pub fn import(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Va
   new_list_from(scope, list.into()).into_value()
 }
 
+/// Takes the same arguments as `import` but adds the bindings to the current
+/// module's exports instead of defining them.
+#[inline]
+pub fn reexport(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let import = match import_bindings(scope, args) {
+    Ok(import) => import,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+  let caller_scope = scope_parent(scope).expect("failed to get caller scope");
+  let mut exports = module_exports(caller_scope);
+
+  import.add_importers(scope, exports.clone().into_value());
+
+  for ImportBinding { name, value, .. } in import.bindings {
+    let key = new_string(caller_scope, &name).into_value();
+
+    match exports.get(&key) {
+      Some(existing) if !is_same_value(existing, &value) => eprintln!(
+        "warning: reexport of {} from {:?} overwrites an existing export",
+        name,
+        import.filename.value()
+      ),
+      _ => {}
+    }
+    exports.set(key, value);
+  }
+
+  nil_value(scope).clone().into_value()
+}
+
+#[inline]
+fn is_same_value(a: &Gc<dyn Value>, b: &Gc<dyn Value>) -> bool {
+  a.as_ptr() as *const u8 == b.as_ptr() as *const u8 || a == b
+}
+
+#[inline]
+fn module_exports(scope: &Gc<Object<Scope>>) -> Gc<Object<Map>> {
+  scope_get(scope, "module")
+    .expect("module is not defined in the current Scope")
+    .downcast_ref::<Object<Map>>()
+    .expect("Failed to downcast module to Map")
+    .get(&new_string(scope, "exports").into_value())
+    .expect("exports not defined on module")
+    .downcast_ref::<Object<Map>>()
+    .expect("Failed to downcast exports to Map")
+    .clone()
+}
+
 #[inline]
 pub fn export(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
   let caller_scope = scope_parent(scope).expect("failed to get caller scope");
@@ -180,3 +533,74 @@ pub fn export(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Va
 
   nil_value(scope).clone().into_value()
 }
+
+/// Returns the paths of the modules that are currently loading, outermost first.
+#[inline]
+pub fn module_loading(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+) -> Option<Gc<Object<Vector>>> {
+  module
+    .get(&new_string(scope, "loading").into_value())
+    .and_then(|loading| loading.downcast_ref::<Object<Vector>>())
+    .map(Clone::clone)
+}
+
+/// If `module` is still loading, returns the chain of paths from it to the
+/// module importing it, ending with `module` again.
+#[inline]
+pub fn import_cycle(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  module: &Gc<Object<Map>>,
+) -> Option<Vec<String>> {
+  let filename = module
+    .get(&new_string(scope, "filename").into_value())
+    .and_then(|filename| filename.downcast_ref::<Object<String>>())
+    .map(|filename| filename.value().clone())?;
+  let loading = module_loading(scope, parent_module)?
+    .iter()
+    .filter_map(|path| path.downcast_ref::<Object<String>>())
+    .map(|path| path.value().clone())
+    .collect::<Vec<String>>();
+  let index = loading.iter().position(|path| path == &filename)?;
+
+  let mut cycle = loading[index..].to_vec();
+  cycle.push(filename);
+  Some(cycle)
+}
+
+/// Binds `name` from a module that is still loading, the export is looked
+/// up when the binding is first called so mutually recursive modules can
+/// import functions from each other.
+#[inline]
+fn new_live_binding(
+  scope: &Gc<Object<Scope>>,
+  exports: &Gc<Object<Map>>,
+  name: &str,
+  cycle: Vec<String>,
+) -> Gc<Object<Function>> {
+  let mut params = new_vector(scope);
+  params.push(new_symbol(scope, "...args").into_value());
+  let key = new_string(scope, name);
+  let error = format!(
+    "{} is not exported by {}, which was still loading in the import cycle {}",
+    name,
+    cycle.last().expect("cycle is empty"),
+    cycle.join(" -> ")
+  );
+
+  new_external_closure(
+    scope,
+    None,
+    params,
+    (exports.clone(), key),
+    move |&(ref exports, ref key), scope, args| match exports
+      .get(&key.clone().into_value())
+      .and_then(|value| value.downcast_ref::<Object<Function>>())
+    {
+      Some(function) => call_function(scope, function.clone(), args.clone()),
+      None => throw_error(scope, error.clone().into_value(scope)),
+    },
+  )
+}
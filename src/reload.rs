@@ -0,0 +1,575 @@
+use std::collections::LinkedList;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use gc::Gc;
+use runtime::{
+  catch_uncaught, new_list_from, new_string, new_symbol, new_vector, nil_value, scope_get,
+  scope_parent, scope_set, throw_error, IntoValue, Map, Object, Scope, Value, Vector,
+};
+
+use super::{
+  build_native_package, module_source, native_builder, new_dylib, resolve_native_library,
+  resolve_package_module, run_module, set_dylib_exports, DyLib, CARGO_MANIFEST,
+};
+
+const IMPORTERS: &'static str = "importers";
+
+static DYLIB_COPIES: AtomicUsize = AtomicUsize::new(0);
+
+/// Records that `target`, a scope or the exports of `importer`, bound `name`
+/// to the export `export` of `module`, or to all of its exports when `export`
+/// is `None`, so reloading `module` can update the binding.
+#[inline]
+pub fn add_importer(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+  importer: &Gc<Object<Map>>,
+  target: Gc<dyn Value>,
+  name: &str,
+  export: Option<&str>,
+) {
+  let mut importers = module_importers(scope, module);
+
+  importers.retain(|entry| {
+    let entry = Importer::from_value(entry);
+    !(is_same(&entry.target, &target) && entry.name == name)
+  });
+
+  let mut entry = new_vector(scope);
+  entry.push(importer.clone().into_value());
+  entry.push(target);
+  entry.push(new_string(scope, name).into_value());
+  entry.push(
+    export
+      .map(|export| new_string(scope, export).into_value())
+      .unwrap_or_else(|| nil_value(scope).clone().into_value()),
+  );
+  importers.push(entry.into_value());
+}
+
+/// Runs the file of `module` again in a fresh scope, its exports map is
+/// updated in place and the bindings importers made with `import` or
+/// `reexport` are set to the new values. Returns a description of each
+/// importer left with a value the module no longer exports, if the module
+/// throws its old exports are kept.
+#[inline]
+pub fn reload_module(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+) -> Result<Vec<String>, String> {
+  let path = module_path(scope, module)
+    .ok_or_else(|| "only modules loaded from lisp files can be reloaded".to_owned())?;
+
+  if !module_source(scope, module).is_file(&path) {
+    return Err(format!("failed to reload {:?}, it no longer exists", path));
+  }
+
+  let mut exports = module_exports(scope, module);
+  let old_exports = exports
+    .iter()
+    .map(|(key, value)| (key.clone(), value.clone()))
+    .collect::<Vec<(Gc<dyn Value>, Gc<dyn Value>)>>();
+
+  remove_importer(scope, module);
+  exports.clear();
+
+  let result = match catch_uncaught(scope, || run_module(scope, module.clone(), &path)) {
+    Ok(result) => result,
+    Err(error) => Err(format!("{:?}", error)),
+  };
+
+  if let Err(error) = result {
+    exports.clear();
+    for (key, value) in old_exports {
+      exports.set(key, value);
+    }
+    return Err(format!("failed to reload {:?}: {}", path, error));
+  }
+
+  Ok(update_importers(scope, module, &old_exports, &path))
+}
+
+/// Loads a copy of the library `module` was loaded from, so a rebuilt library
+/// is not confused with the loaded one, then points its exports and the
+/// bindings of its importers at the new functions. The old library is
+/// unloaded once no function from it is reachable.
+#[inline]
+pub fn reload_dylib(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+) -> Result<Vec<String>, String> {
+  let path = module
+    .get(&new_string(scope, "filename").into_value())
+    .and_then(|filename| filename.downcast_ref::<Object<String>>())
+    .map(|filename| PathBuf::from(filename.value()))
+    .filter(|path| {
+      path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| {
+          module_exports(scope, module)
+            .get(&new_string(scope, stem).into_value())
+            .map(|dylib| dylib.downcast_ref::<Object<DyLib>>().is_some())
+        })
+        .unwrap_or(false)
+    })
+    .ok_or_else(|| "only modules loaded from native libraries can be reloaded".to_owned())?;
+
+  let copy = env::temp_dir().join(format!(
+    "lisp-{}-{}-{}",
+    process::id(),
+    DYLIB_COPIES.fetch_add(1, Ordering::SeqCst),
+    path.file_name().unwrap().to_str().unwrap()
+  ));
+  fs::copy(&path, &copy).map_err(|error| format!("failed to reload {:?}: {}", path, error))?;
+  let dylib = unsafe { new_dylib(scope, copy.to_str().unwrap()) };
+  // the library stays mapped after its file is removed
+  let _ = fs::remove_file(&copy);
+  let dylib = dylib?;
+
+  let mut exports = module_exports(scope, module);
+  let old_exports = exports
+    .iter()
+    .map(|(key, value)| (key.clone(), value.clone()))
+    .collect::<Vec<(Gc<dyn Value>, Gc<dyn Value>)>>();
+
+  exports.clear();
+  set_dylib_exports(scope, module, &dylib, &path);
+
+  Ok(update_importers(scope, module, &old_exports, &path))
+}
+
+/// Reloads every cached module whose file changed since it was loaded,
+/// modules before the modules importing them, and returns their paths.
+#[inline]
+pub fn reload_changed(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+) -> Result<Vec<String>, String> {
+  let mut changed = module_cache(scope, module)
+    .iter()
+    .filter_map(|(_, module)| module.downcast_ref::<Object<Map>>())
+    .filter(
+      |module| match (module_path(scope, module), module_mtime(scope, module)) {
+        (Some(path), Some(mtime)) => {
+          let source = module_source(scope, module);
+          source.is_file(&path) && source.mtime(&path) != Some(mtime)
+        }
+        _ => false,
+      },
+    )
+    .map(Clone::clone)
+    .collect::<Vec<Gc<Object<Map>>>>();
+  let mut reloaded = Vec::new();
+
+  changed.sort_by_key(|module| module_path(scope, module));
+
+  while !changed.is_empty() {
+    let index = changed
+      .iter()
+      .position(|module| {
+        !changed
+          .iter()
+          .any(|dependency| imports(scope, module, dependency))
+      })
+      .unwrap_or(0);
+    let module = changed.remove(index);
+    let path = module_path(scope, &module).expect("changed module has no filename");
+
+    for stale in reload_module(scope, &module)? {
+      eprintln!("warning: {}", stale);
+    }
+    reloaded.push(path.to_str().unwrap().to_owned());
+  }
+
+  Ok(reloaded)
+}
+
+/// `(module.reload "path")` reloads a module that was already imported and
+/// returns its exports.
+#[inline]
+pub fn module_reload(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let caller_scope = scope_parent(scope).expect("failed to get caller scope");
+  let parent_module = scope_get(caller_scope, "module")
+    .expect("module is not defined in the current Scope")
+    .downcast_ref::<Object<Map>>()
+    .expect("Failed to downcast current module to Map")
+    .clone();
+  let filename = match args
+    .first()
+    .and_then(|filename| filename.downcast_ref::<Object<String>>())
+  {
+    Some(filename) => filename.value().clone(),
+    None => {
+      return throw_error(
+        scope,
+        "module path is required".to_owned().into_value(scope),
+      )
+    }
+  };
+
+  let module = match resolve_module_path(scope, &parent_module, &filename).and_then(|path| {
+    module_cache(scope, &parent_module)
+      .get(&new_string(scope, path.to_str().unwrap()).into_value())
+      .and_then(|module| module.downcast_ref::<Object<Map>>())
+      .map(Clone::clone)
+      .ok_or_else(|| format!("{:?} has not been imported", filename))
+  }) {
+    Ok(module) => module,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+
+  match reload_module(scope, &module) {
+    Ok(stale) => {
+      for stale in stale {
+        eprintln!("warning: {}", stale);
+      }
+    }
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  }
+
+  let mut quotelist = LinkedList::new();
+  quotelist.push_back(new_symbol(scope, "quote").into_value());
+  quotelist.push_back(module_exports(scope, &module).into_value());
+  new_list_from(scope, quotelist.into()).into_value()
+}
+
+/// `(dylib.reload "native:name")` reloads an imported native library and
+/// returns its exports, a `Cargo.toml` is built again first.
+#[inline]
+pub fn dylib_reload(scope: &Gc<Object<Scope>>, args: &Gc<Object<Vector>>) -> Gc<dyn Value> {
+  let caller_scope = scope_parent(scope).expect("failed to get caller scope");
+  let parent_module = scope_get(caller_scope, "module")
+    .expect("module is not defined in the current Scope")
+    .downcast_ref::<Object<Map>>()
+    .expect("Failed to downcast current module to Map")
+    .clone();
+  let filename = match args
+    .first()
+    .and_then(|filename| filename.downcast_ref::<Object<String>>())
+  {
+    Some(filename) => filename.value().clone(),
+    None => {
+      return throw_error(
+        scope,
+        "library path is required".to_owned().into_value(scope),
+      )
+    }
+  };
+
+  let module = match resolve_library_path(scope, &parent_module, &filename).and_then(|path| {
+    module_cache(scope, &parent_module)
+      .get(&new_string(scope, path.to_str().unwrap()).into_value())
+      .and_then(|module| module.downcast_ref::<Object<Map>>())
+      .map(Clone::clone)
+      .ok_or_else(|| format!("{:?} has not been imported", filename))
+  }) {
+    Ok(module) => module,
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  };
+
+  match reload_dylib(scope, &module) {
+    Ok(stale) => {
+      for stale in stale {
+        eprintln!("warning: {}", stale);
+      }
+    }
+    Err(error) => return throw_error(scope, error.into_value(scope)),
+  }
+
+  let mut quotelist = LinkedList::new();
+  quotelist.push_back(new_symbol(scope, "quote").into_value());
+  quotelist.push_back(module_exports(scope, &module).into_value());
+  new_list_from(scope, quotelist.into()).into_value()
+}
+
+/// `(module.reload_changed)` reloads the modules whose files changed and
+/// returns their paths.
+#[inline]
+pub fn module_reload_changed(
+  scope: &Gc<Object<Scope>>,
+  _args: &Gc<Object<Vector>>,
+) -> Gc<dyn Value> {
+  let module = scope_get(scope, "module")
+    .expect("module is not defined in the current Scope")
+    .downcast_ref::<Object<Map>>()
+    .expect("Failed to downcast current module to Map")
+    .clone();
+
+  match reload_changed(scope, &module) {
+    Ok(reloaded) => {
+      let mut paths = new_vector(scope);
+      for path in reloaded {
+        paths.push(new_string(scope, &path).into_value());
+      }
+      paths.into_value()
+    }
+    Err(error) => throw_error(scope, error.into_value(scope)),
+  }
+}
+
+struct Importer {
+  importer: Gc<Object<Map>>,
+  target: Gc<dyn Value>,
+  name: String,
+  export: Option<String>,
+}
+
+impl Importer {
+  #[inline]
+  fn from_value(value: &Gc<dyn Value>) -> Self {
+    let entry = value
+      .downcast_ref::<Object<Vector>>()
+      .expect("failed to downcast importer to Vector");
+
+    Importer {
+      importer: entry[0]
+        .downcast_ref::<Object<Map>>()
+        .expect("failed to downcast importer module to Map")
+        .clone(),
+      target: entry[1].clone(),
+      name: entry[2]
+        .downcast_ref::<Object<String>>()
+        .expect("failed to downcast importer name to String")
+        .value()
+        .clone(),
+      export: entry[3]
+        .downcast_ref::<Object<String>>()
+        .map(|export| export.value().clone()),
+    }
+  }
+
+  #[inline]
+  fn get(&self, scope: &Gc<Object<Scope>>) -> Option<Gc<dyn Value>> {
+    if let Some(target_scope) = self.target.downcast_ref::<Object<Scope>>() {
+      target_scope.get(&self.name)
+    } else if let Some(exports) = self.target.downcast_ref::<Object<Map>>() {
+      exports
+        .get(&new_string(scope, &self.name).into_value())
+        .map(Clone::clone)
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  fn set(&self, scope: &Gc<Object<Scope>>, value: Gc<dyn Value>) {
+    let mut target = self.target.clone();
+
+    if let Some(target_scope) = target.downcast_ref::<Object<Scope>>() {
+      scope_set(target_scope, &self.name, value);
+    } else if let Some(exports) = target.downcast_mut::<Object<Map>>() {
+      exports.set(new_string(scope, &self.name).into_value(), value);
+    }
+  }
+}
+
+/// Sets the bindings importers made to an old export of `module` to its new
+/// value, returns a description of each importer left with a value `module`
+/// no longer exports.
+#[inline]
+fn update_importers(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+  old_exports: &[(Gc<dyn Value>, Gc<dyn Value>)],
+  path: &Path,
+) -> Vec<String> {
+  let mut stale = Vec::new();
+  let exports = module_exports(scope, module);
+  let mut importers = module_importers(scope, module);
+
+  importers.retain(|entry| {
+    let entry = Importer::from_value(entry);
+    let export = match entry.export {
+      Some(ref export) => export,
+      None => return true,
+    };
+    let key = new_string(scope, export).into_value();
+    let old_value = match old_exports
+      .iter()
+      .find(|&&(ref old_key, _)| old_key == &key)
+    {
+      Some(&(_, ref old_value)) => old_value,
+      None => return true,
+    };
+
+    if !entry
+      .get(scope)
+      .is_some_and(|value| is_same(&value, old_value))
+    {
+      return true;
+    }
+
+    match exports.get(&key) {
+      Some(value) => {
+        entry.set(scope, value.clone());
+        true
+      }
+      None => {
+        stale.push(format!(
+          "{} in {} still holds the old value of {}, which {} no longer exports",
+          entry.name,
+          importer_name(scope, &entry.importer),
+          export,
+          path.display()
+        ));
+        false
+      }
+    }
+  });
+
+  stale
+}
+
+#[inline]
+fn resolve_module_path(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  filename: &str,
+) -> Result<PathBuf, String> {
+  let path = match resolve_package_module(scope, parent_module, filename)? {
+    Some(path) => path,
+    None => {
+      let dirname = parent_module
+        .get(&new_string(scope, "dirname").into_value())
+        .and_then(|dirname| dirname.downcast_ref::<Object<String>>())
+        .map(|dirname| dirname.value().clone())
+        .unwrap_or_else(|| ".".to_owned());
+      let mut path = Path::new(&dirname).join(filename);
+
+      if path.extension().is_none() {
+        path.set_extension("lisp");
+      }
+      path
+    }
+  };
+
+  module_source(scope, parent_module)
+    .canonicalize(&path)
+    .map_err(|error| format!("failed to resolve {:?}: {}", filename, error))
+}
+
+#[inline]
+fn resolve_library_path(
+  scope: &Gc<Object<Scope>>,
+  parent_module: &Gc<Object<Map>>,
+  filename: &str,
+) -> Result<PathBuf, String> {
+  if Path::new(filename)
+    .file_name()
+    .and_then(|name| name.to_str())
+    == Some(CARGO_MANIFEST)
+  {
+    let dirname = parent_module
+      .get(&new_string(scope, "dirname").into_value())
+      .and_then(|dirname| dirname.downcast_ref::<Object<String>>())
+      .map(|dirname| dirname.value().clone())
+      .unwrap_or_else(|| ".".to_owned());
+    let manifest = fs::canonicalize(Path::new(&dirname).join(filename))
+      .map_err(|error| format!("failed to find {:?}: {}", filename, error))?;
+
+    build_native_package(&native_builder(), &manifest)
+  } else {
+    resolve_native_library(scope, parent_module, filename)?
+      .ok_or_else(|| format!("{:?} is not a native library", filename))
+  }
+}
+
+#[inline]
+fn imports(
+  scope: &Gc<Object<Scope>>,
+  module: &Gc<Object<Map>>,
+  dependency: &Gc<Object<Map>>,
+) -> bool {
+  !is_same_module(module, dependency)
+    && module_importers(scope, dependency)
+      .iter()
+      .any(|entry| is_same_module(&Importer::from_value(entry).importer, module))
+}
+
+/// Forgets the bindings `module` made in other modules, it makes them again
+/// when it is run.
+#[inline]
+fn remove_importer(scope: &Gc<Object<Scope>>, module: &Gc<Object<Map>>) {
+  for (_, cached) in module_cache(scope, module).iter() {
+    if let Some(cached) = cached.downcast_ref::<Object<Map>>() {
+      module_importers(scope, cached)
+        .retain(|entry| !is_same_module(&Importer::from_value(entry).importer, module));
+    }
+  }
+}
+
+#[inline]
+fn importer_name(scope: &Gc<Object<Scope>>, importer: &Gc<Object<Map>>) -> String {
+  module_path(scope, importer)
+    .map(|path| path.display().to_string())
+    .unwrap_or_else(|| "the top level".to_owned())
+}
+
+#[inline]
+fn module_importers(scope: &Gc<Object<Scope>>, module: &Gc<Object<Map>>) -> Gc<Object<Vector>> {
+  let key = new_string(scope, IMPORTERS).into_value();
+
+  match module
+    .get(&key)
+    .and_then(|importers| importers.downcast_ref::<Object<Vector>>())
+  {
+    Some(importers) => importers.clone(),
+    None => {
+      let importers = new_vector(scope);
+      module.clone().set(key, importers.clone().into_value());
+      importers
+    }
+  }
+}
+
+#[inline]
+fn module_cache(scope: &Gc<Object<Scope>>, module: &Gc<Object<Map>>) -> Gc<Object<Map>> {
+  module
+    .get(&new_string(scope, "cache").into_value())
+    .expect("cache not defined on module")
+    .downcast_ref::<Object<Map>>()
+    .expect("Failed to downcast cache to Map")
+    .clone()
+}
+
+#[inline]
+fn module_exports(scope: &Gc<Object<Scope>>, module: &Gc<Object<Map>>) -> Gc<Object<Map>> {
+  module
+    .get(&new_string(scope, "exports").into_value())
+    .expect("exports not defined on module")
+    .downcast_ref::<Object<Map>>()
+    .expect("Failed to downcast exports to Map")
+    .clone()
+}
+
+#[inline]
+fn module_path(scope: &Gc<Object<Scope>>, module: &Gc<Object<Map>>) -> Option<PathBuf> {
+  module_mtime(scope, module)?;
+  module
+    .get(&new_string(scope, "filename").into_value())
+    .and_then(|filename| filename.downcast_ref::<Object<String>>())
+    .map(|filename| PathBuf::from(filename.value()))
+}
+
+#[inline]
+fn module_mtime(scope: &Gc<Object<Scope>>, module: &Gc<Object<Map>>) -> Option<u64> {
+  module
+    .get(&new_string(scope, "mtime").into_value())
+    .and_then(|mtime| mtime.downcast_ref::<Object<u64>>())
+    .map(|mtime| *mtime.value())
+}
+
+#[inline]
+fn is_same_module(a: &Gc<Object<Map>>, b: &Gc<Object<Map>>) -> bool {
+  a.as_ptr() == b.as_ptr()
+}
+
+#[inline]
+fn is_same(a: &Gc<dyn Value>, b: &Gc<dyn Value>) -> bool {
+  a.as_ptr() as *const u8 == b.as_ptr() as *const u8
+}
@@ -0,0 +1,128 @@
+use std::env::var_os;
+use std::path::PathBuf;
+
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Helper, Result};
+
+pub const HISTORY_ENV: &'static str = "LISP_HISTORY";
+pub const HISTORY_FILE: &'static str = ".lisp_history";
+
+/// Keeps the REPL reading lines until the input holds complete forms.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReplHelper;
+
+impl Completer for ReplHelper {
+  type Candidate = String;
+}
+
+impl Hinter for ReplHelper {
+  type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {
+  #[inline]
+  fn validate(&self, ctx: &mut ValidationContext) -> Result<ValidationResult> {
+    if top_level_forms(ctx.input()).is_some() {
+      Ok(ValidationResult::Valid(None))
+    } else {
+      Ok(ValidationResult::Incomplete)
+    }
+  }
+}
+
+impl Helper for ReplHelper {}
+
+/// Splits `source` into its top-level forms the way the reader reads them,
+/// `None` while a list, vector, map, string or char is left open.
+#[inline]
+pub fn top_level_forms(source: &str) -> Option<Vec<&str>> {
+  let mut forms = Vec::new();
+  let mut chars = source.char_indices();
+  let mut depth = 0_usize;
+  let mut start = None;
+  let mut in_atom = false;
+
+  while let Some((index, ch)) = chars.next() {
+    if in_atom && !is_whitespace(ch) && !is_closer(ch) {
+      continue;
+    }
+    in_atom = false;
+    let mut end = index + ch.len_utf8();
+
+    match ch {
+      '(' | '[' | '{' => {
+        start.get_or_insert(index);
+        depth += 1;
+      }
+      ')' | ']' | '}' => {
+        start.get_or_insert(index);
+        depth = depth.saturating_sub(1);
+      }
+      '"' | '\'' => {
+        start.get_or_insert(index);
+        match chars.by_ref().find(|&(_, next)| next == ch) {
+          Some((close, _)) => end = close + ch.len_utf8(),
+          None => return None,
+        }
+      }
+      ';' => {
+        chars.by_ref().any(|(_, next)| next == '\n');
+        continue;
+      }
+      '`' => {
+        start.get_or_insert(index);
+        continue;
+      }
+      ch if is_whitespace(ch) => {}
+      _ => {
+        start.get_or_insert(index);
+        in_atom = true;
+      }
+    }
+
+    if depth == 0 && !in_atom {
+      if let Some(form_start) = start.take() {
+        let form = source[form_start..end].trim_end();
+
+        if !form.is_empty() {
+          forms.push(form);
+        }
+      }
+    }
+  }
+
+  if depth != 0 {
+    None
+  } else {
+    if let Some(form_start) = start {
+      forms.push(&source[form_start..]);
+    }
+    Some(forms)
+  }
+}
+
+/// Where the REPL keeps its history, `LISP_HISTORY` when set, otherwise
+/// `~/.lisp_history`.
+#[inline]
+pub fn history_path() -> Option<PathBuf> {
+  var_os(HISTORY_ENV).map(PathBuf::from).or_else(|| {
+    var_os("HOME")
+      .or_else(|| var_os("USERPROFILE"))
+      .map(|home| PathBuf::from(home).join(HISTORY_FILE))
+  })
+}
+
+#[inline]
+fn is_whitespace(ch: char) -> bool {
+  ch.is_whitespace() || ch == ','
+}
+
+#[inline]
+fn is_closer(ch: char) -> bool {
+  ch == ')' || ch == ']' || ch == '}'
+}
@@ -0,0 +1,388 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A `major.minor.patch[-pre][+build]` version, build metadata is ignored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+  pub major: u64,
+  pub minor: u64,
+  pub patch: u64,
+  pub pre: Vec<String>,
+}
+
+impl Version {
+  #[inline]
+  pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+    Version {
+      major,
+      minor,
+      patch,
+      pre: Vec::new(),
+    }
+  }
+
+  #[inline]
+  pub fn parse(version: &str) -> Result<Self, String> {
+    let version = version.trim();
+    let version = version.split('+').next().unwrap();
+    let (numbers, pre) = match version.find('-') {
+      Some(index) => (&version[..index], Some(&version[(index + 1)..])),
+      None => (version, None),
+    };
+    let parts = numbers
+      .split('.')
+      .map(|part| parse_number(part, version))
+      .collect::<Result<Vec<u64>, String>>()?;
+
+    if parts.len() != 3 {
+      return Err(format!(
+        "invalid version {:?}, expected major.minor.patch",
+        version
+      ));
+    }
+
+    Ok(Version {
+      major: parts[0],
+      minor: parts[1],
+      patch: parts[2],
+      pre: match pre {
+        Some(pre) => parse_pre(pre, version)?,
+        None => Vec::new(),
+      },
+    })
+  }
+
+  #[inline]
+  pub fn is_prerelease(&self) -> bool {
+    !self.pre.is_empty()
+  }
+}
+
+impl Ord for Version {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    (self.major, self.minor, self.patch)
+      .cmp(&(other.major, other.minor, other.patch))
+      .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => compare_pre(&self.pre, &other.pre),
+      })
+  }
+}
+
+impl PartialOrd for Version {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl fmt::Display for Version {
+  #[inline]
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+    if !self.pre.is_empty() {
+      write!(f, "-{}", self.pre.join("."))?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+  Exact,
+  Greater,
+  GreaterEq,
+  Less,
+  LessEq,
+  Tilde,
+  Caret,
+  Wildcard,
+}
+
+/// One comparison of a requirement, missing minor or patch parts match any
+/// value, so `=1.2` matches `1.2.5`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comparator {
+  pub op: Op,
+  pub major: u64,
+  pub minor: Option<u64>,
+  pub patch: Option<u64>,
+  pub pre: Vec<String>,
+}
+
+impl Comparator {
+  #[inline]
+  pub fn parse(comparator: &str) -> Result<Self, String> {
+    let comparator = comparator.trim();
+    let (op, rest) = [
+      (">=", Op::GreaterEq),
+      ("<=", Op::LessEq),
+      (">", Op::Greater),
+      ("<", Op::Less),
+      ("=", Op::Exact),
+      ("~", Op::Tilde),
+      ("^", Op::Caret),
+    ]
+    .iter()
+    .find(|&&(prefix, _)| comparator.starts_with(prefix))
+    .map(|&(prefix, op)| (op, comparator[prefix.len()..].trim()))
+    .unwrap_or((Op::Caret, comparator));
+
+    let (numbers, pre) = match rest.find('-') {
+      Some(index) => (&rest[..index], Some(&rest[(index + 1)..])),
+      None => (rest, None),
+    };
+    let mut parts = Vec::new();
+    let mut wildcard = false;
+
+    for part in numbers.split('.') {
+      if part == "*" || part == "x" || part == "X" {
+        wildcard = true;
+      } else if wildcard {
+        return Err(format!("invalid version requirement {:?}", comparator));
+      } else {
+        parts.push(parse_number(part, comparator)?);
+      }
+    }
+
+    if parts.is_empty() || parts.len() > 3 {
+      return if wildcard && parts.is_empty() && op == Op::Caret {
+        Ok(Comparator {
+          op: Op::GreaterEq,
+          major: 0,
+          minor: Some(0),
+          patch: Some(0),
+          pre: Vec::new(),
+        })
+      } else {
+        Err(format!("invalid version requirement {:?}", comparator))
+      };
+    }
+
+    Ok(Comparator {
+      op: if wildcard && op == Op::Caret {
+        Op::Wildcard
+      } else {
+        op
+      },
+      major: parts[0],
+      minor: parts.get(1).map(Clone::clone),
+      patch: parts.get(2).map(Clone::clone),
+      pre: match pre {
+        Some(pre) if parts.len() == 3 => parse_pre(pre, comparator)?,
+        Some(_) => return Err(format!("invalid version requirement {:?}", comparator)),
+        None => Vec::new(),
+      },
+    })
+  }
+
+  #[inline]
+  pub fn matches(&self, version: &Version) -> bool {
+    match self.op {
+      Op::Exact | Op::Wildcard => self.matches_exact(version),
+      Op::Greater => self.matches_greater(version),
+      Op::GreaterEq => self.matches_exact(version) || self.matches_greater(version),
+      Op::Less => !self.matches_exact(version) && !self.matches_greater(version),
+      Op::LessEq => !self.matches_greater(version),
+      Op::Tilde => {
+        version.major == self.major
+          && self.minor.is_none_or(|minor| version.minor == minor)
+          && self.matches_lower_bound(version)
+      }
+      Op::Caret => self.matches_caret(version),
+    }
+  }
+
+  #[inline]
+  fn matches_exact(&self, version: &Version) -> bool {
+    version.major == self.major
+      && self.minor.is_none_or(|minor| version.minor == minor)
+      && self.patch.is_none_or(|patch| version.patch == patch)
+      && (self.patch.is_none() || version.pre == self.pre)
+  }
+
+  #[inline]
+  fn matches_greater(&self, version: &Version) -> bool {
+    if version.major != self.major {
+      return version.major > self.major;
+    }
+    match self.minor {
+      None => return false,
+      Some(minor) if version.minor != minor => return version.minor > minor,
+      _ => {}
+    }
+    match self.patch {
+      None => return false,
+      Some(patch) if version.patch != patch => return version.patch > patch,
+      _ => {}
+    }
+    !self.pre.is_empty()
+      && (version.pre.is_empty() || compare_pre(&version.pre, &self.pre) == Ordering::Greater)
+  }
+
+  #[inline]
+  fn matches_lower_bound(&self, version: &Version) -> bool {
+    let lower = Version {
+      major: self.major,
+      minor: self.minor.unwrap_or(0),
+      patch: self.patch.unwrap_or(0),
+      pre: self.pre.clone(),
+    };
+    version >= &lower
+  }
+
+  #[inline]
+  fn matches_caret(&self, version: &Version) -> bool {
+    if version.major != self.major || !self.matches_lower_bound(version) {
+      return false;
+    }
+    match (self.major, self.minor, self.patch) {
+      (0, Some(0), Some(patch)) => version.minor == 0 && version.patch == patch,
+      (0, Some(minor), _) => version.minor == minor,
+      _ => true,
+    }
+  }
+}
+
+impl fmt::Display for Comparator {
+  #[inline]
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let op = match self.op {
+      Op::Exact => "=",
+      Op::Greater => ">",
+      Op::GreaterEq => ">=",
+      Op::Less => "<",
+      Op::LessEq => "<=",
+      Op::Tilde => "~",
+      Op::Caret => "^",
+      Op::Wildcard => "",
+    };
+    write!(f, "{}{}", op, self.major)?;
+    match self.minor {
+      Some(minor) => write!(f, ".{}", minor)?,
+      None if self.op == Op::Wildcard => return write!(f, ".*"),
+      None => return Ok(()),
+    }
+    match self.patch {
+      Some(patch) => write!(f, ".{}", patch)?,
+      None if self.op == Op::Wildcard => return write!(f, ".*"),
+      None => return Ok(()),
+    }
+    if !self.pre.is_empty() {
+      write!(f, "-{}", self.pre.join("."))?;
+    }
+    Ok(())
+  }
+}
+
+/// Comma separated comparators that all have to match, a bare version like
+/// `0.1` is a caret requirement, `^0.1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionReq {
+  pub comparators: Vec<Comparator>,
+}
+
+impl VersionReq {
+  #[inline]
+  pub fn any() -> Self {
+    VersionReq {
+      comparators: Vec::new(),
+    }
+  }
+
+  #[inline]
+  pub fn parse(requirement: &str) -> Result<Self, String> {
+    let requirement = requirement.trim();
+
+    if requirement.is_empty() || requirement == "*" {
+      Ok(VersionReq::any())
+    } else {
+      Ok(VersionReq {
+        comparators: requirement
+          .split(',')
+          .map(Comparator::parse)
+          .collect::<Result<Vec<Comparator>, String>>()?,
+      })
+    }
+  }
+
+  /// Pre-release versions only match when a comparator names the same
+  /// `major.minor.patch` with a pre-release.
+  #[inline]
+  pub fn matches(&self, version: &Version) -> bool {
+    self
+      .comparators
+      .iter()
+      .all(|comparator| comparator.matches(version))
+      && (!version.is_prerelease()
+        || self.comparators.iter().any(|comparator| {
+          !comparator.pre.is_empty()
+            && comparator.major == version.major
+            && comparator.minor == Some(version.minor)
+            && comparator.patch == Some(version.patch)
+        }))
+  }
+}
+
+impl fmt::Display for VersionReq {
+  #[inline]
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.comparators.is_empty() {
+      return write!(f, "*");
+    }
+    for (index, comparator) in self.comparators.iter().enumerate() {
+      if index != 0 {
+        write!(f, ", ")?;
+      }
+      write!(f, "{}", comparator)?;
+    }
+    Ok(())
+  }
+}
+
+#[inline]
+fn parse_number(part: &str, source: &str) -> Result<u64, String> {
+  if part.is_empty() || (part.len() > 1 && part.starts_with('0')) {
+    return Err(format!("invalid version {:?}", source));
+  }
+  part
+    .parse::<u64>()
+    .map_err(|_| format!("invalid version {:?}", source))
+}
+
+#[inline]
+fn parse_pre(pre: &str, source: &str) -> Result<Vec<String>, String> {
+  pre
+    .split('.')
+    .map(|identifier| {
+      if !identifier.is_empty()
+        && identifier
+          .chars()
+          .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+      {
+        Ok(identifier.to_owned())
+      } else {
+        Err(format!("invalid pre-release in version {:?}", source))
+      }
+    })
+    .collect()
+}
+
+#[inline]
+fn compare_pre(a: &[String], b: &[String]) -> Ordering {
+  for (a, b) in a.iter().zip(b.iter()) {
+    let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+      (Ok(a), Ok(b)) => a.cmp(&b),
+      (Ok(_), Err(_)) => Ordering::Less,
+      (Err(_), Ok(_)) => Ordering::Greater,
+      (Err(_), Err(_)) => a.cmp(b),
+    };
+    if ordering != Ordering::Equal {
+      return ordering;
+    }
+  }
+  a.len().cmp(&b.len())
+}
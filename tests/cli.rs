@@ -0,0 +1,179 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+fn lisp(args: &[&str], stdin: &str) -> Output {
+  lisp_with_history(args, stdin, &env::temp_dir().join("lisp-cli-history"))
+}
+
+fn lisp_with_history(args: &[&str], stdin: &str, history: &Path) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_lisp"))
+    .args(args)
+    .env("LISP_HISTORY", history)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  child
+    .stdin
+    .take()
+    .unwrap()
+    .write_all(stdin.as_bytes())
+    .unwrap();
+  child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_eval_argv_and_exit() {
+  let output = lisp(&["-e", "(println argv)", "a", "--b"], "");
+  assert_eq!(output.status.code(), Some(0));
+  assert_eq!(stdout(&output), "[\"-e\", \"a\", \"--b\"]\n");
+
+  let output = lisp(&["-", "x"], "(println argv) (exit 3) (println 1)");
+  assert_eq!(output.status.code(), Some(3));
+  assert_eq!(stdout(&output), "[\"-\", \"x\"]\n");
+
+  let output = lisp(&["-e", "(exit)"], "");
+  assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_uncaught_error_exit_code() {
+  let output = lisp(&["-e", "(throw \"boom\")"], "");
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(
+    stderr
+      .lines()
+      .any(|line| line.starts_with("error: ") && line.contains("boom")),
+    "{}",
+    stderr
+  );
+  assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn test_input_file_errors_and_eval_argv() {
+  let dir = env::temp_dir().join(format!("lisp-cli-input-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let script = dir.join("script.lisp");
+  fs::write(&script, "(println \"ran\") (throw \"boom\")").unwrap();
+  let script = script.to_str().unwrap();
+
+  let output = lisp(&[script], "");
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert_eq!(output.status.code(), Some(1));
+  assert_eq!(stdout(&output), "\"ran\"\n");
+  assert!(
+    stderr.starts_with("error: ") && stderr.contains("boom"),
+    "{}",
+    stderr
+  );
+  assert!(!stderr.contains("panicked"), "{}", stderr);
+
+  let missing = dir.join("missing.lisp");
+  let output = lisp(&[missing.to_str().unwrap()], "");
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert_eq!(output.status.code(), Some(1));
+  assert!(stderr.starts_with("error: failed to read"), "{}", stderr);
+
+  // with -e the input file is only passed in argv
+  let output = lisp(&["-e", "(println argv)", script], "");
+  assert_eq!(output.status.code(), Some(0));
+  assert_eq!(stdout(&output), format!("[\"-e\", {:?}]\n", script));
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_repl_multi_line_input_and_history() {
+  let history = env::temp_dir().join(format!("lisp-history-{}", std::process::id()));
+  let output = lisp_with_history(
+    &[],
+    "(def-fn add [a b]\n  (isize.add a b))\n(add 1 2) (add 3 4)\n",
+    &history,
+  );
+
+  assert_eq!(output.status.code(), Some(0));
+  assert_eq!(
+    stdout(&output),
+    concat!(
+      "Welcome to lisp v",
+      env!("CARGO_PKG_VERSION"),
+      "\n3\n7\nCTRL-D\n"
+    )
+  );
+  let saved = fs::read_to_string(&history).unwrap();
+  assert!(saved.contains("(add 1 2) (add 3 4)"), "{}", saved);
+
+  fs::remove_file(history).unwrap();
+}
+
+#[test]
+fn test_import_overwrite_warnings() {
+  let dir = env::temp_dir().join(format!("lisp-cli-overwrite-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  fs::write(dir.join("a.lisp"), "(def x 1) (export x)").unwrap();
+  fs::write(dir.join("b.lisp"), "(def x 2) (export x)").unwrap();
+  fs::write(
+    dir.join("c.lisp"),
+    "(reexport \"./a\" :only [x]) (reexport \"./b\" :only [x])",
+  )
+  .unwrap();
+  let a = dir.join("a.lisp");
+  let b = dir.join("b.lisp");
+  let c = dir.join("c.lisp");
+
+  let output = lisp(
+    &[
+      "-e",
+      &format!(
+        "(import x \"{}\") (import x \"{}\") (import x \"{}\") (println x)",
+        a.display(),
+        a.display(),
+        b.display()
+      ),
+    ],
+    "",
+  );
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(0));
+  assert_eq!(stdout(&output), "2\n");
+  assert_eq!(
+    stderr
+      .lines()
+      .filter(|line| line.contains("overwrites an existing definition"))
+      .collect::<Vec<_>>(),
+    vec![format!(
+      "warning: import of x from {:?} overwrites an existing definition",
+      b.display().to_string()
+    )],
+    "{}",
+    stderr
+  );
+
+  let output = lisp(
+    &["-e", &format!("(import x \"{}\") (println x)", c.display())],
+    "",
+  );
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(0));
+  assert_eq!(stdout(&output), "2\n");
+  assert!(
+    stderr.contains("warning: reexport of x from \"./b\" overwrites an existing export"),
+    "{}",
+    stderr
+  );
+
+  fs::remove_dir_all(dir).unwrap();
+}
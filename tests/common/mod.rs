@@ -0,0 +1,75 @@
+// shared by the tests that load real native libraries, each test uses a part
+#![allow(dead_code)]
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use lisp::native_library_filename;
+
+/// A fresh directory in the temp dir for the test `name`.
+pub fn test_dir(name: &str) -> PathBuf {
+  let dir = env::temp_dir().join(format!("lisp-{}-{}", name, std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+/// Builds `examples/hello_world` with cargo and returns its cdylib.
+pub fn build_hello_world() -> PathBuf {
+  let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("hello_world");
+  let output = Command::new(env!("CARGO"))
+    .arg("build")
+    .arg("--manifest-path")
+    .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+    .arg("--package")
+    .arg("lisp-hello-world")
+    .arg("--target-dir")
+    .arg(&target_dir)
+    .output()
+    .expect("failed to run cargo");
+
+  assert!(
+    output.status.success(),
+    "failed to build examples/hello_world\n{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  target_dir
+    .join("debug")
+    .join(native_library_filename("lisp_hello_world"))
+}
+
+/// Compiles `examples/hello_c/hello_c.c` into `library` with `CC` or `cc`,
+/// after replacing each `from` in the source with `to`.
+pub fn build_hello_c(library: &Path, replacements: &[(&str, &str)]) {
+  let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+  let mut source = fs::read_to_string(root.join("examples/hello_c/hello_c.c")).unwrap();
+
+  for &(from, to) in replacements {
+    assert!(
+      source.contains(from),
+      "hello_c.c does not contain {:?}",
+      from
+    );
+    source = source.replace(from, to);
+  }
+  let source_path = library.with_extension("c");
+  fs::write(&source_path, source).unwrap();
+
+  // the library is replaced, not written over, while an old copy is loaded
+  let _ = fs::remove_file(library);
+  let status = Command::new(env::var_os("CC").unwrap_or_else(|| OsString::from("cc")))
+    .arg("-shared")
+    .arg("-fPIC")
+    .arg("-I")
+    .arg(root.join("plugin/include"))
+    .arg("-o")
+    .arg(library)
+    .arg(&source_path)
+    .status()
+    .expect("failed to run the C compiler");
+
+  assert!(status.success(), "failed to compile {:?}", source_path);
+}
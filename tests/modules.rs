@@ -0,0 +1,560 @@
+extern crate lisp;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use lisp::runtime::{new_string, run_in_scope, scope_get_with_kind, FromValue, Map};
+use lisp::MemoryModuleSource;
+
+fn module_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+  let dir = env::temp_dir().join(format!("lisp-modules-{}-{}", name, std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  for &(filename, content) in files {
+    fs::write(dir.join(filename), content).unwrap();
+  }
+  dir
+}
+
+#[test]
+fn test_mutually_recursive_modules() {
+  let dir = module_dir(
+    "recursive",
+    &[
+      (
+        "even.lisp",
+        "(import odd \"./odd\")
+         (def-fn even [n] (if (= n 0) true (odd (isize.sub n 1))))
+         (export even)",
+      ),
+      (
+        "odd.lisp",
+        "(import even \"./even\")
+         (def-fn odd [n] (if (= n 0) false (even (isize.sub n 1))))
+         (export odd)",
+      ),
+    ],
+  );
+  let scope = lisp::new();
+
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(import even \"{}\") (even 10)",
+      dir.join("even.lisp").display()
+    ),
+  );
+  assert_eq!(bool::from_value(&scope, &value), Ok(true));
+  let value = run_in_scope(&scope, "(even 7)");
+  assert_eq!(bool::from_value(&scope, &value), Ok(false));
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_root_import_resolves_from_the_calling_module() {
+  let dir = module_dir(
+    "root-import",
+    &[
+      ("b.lisp", "(def value 1) (export value)"),
+      ("a.lisp", "(import value \"./b\") (export value)"),
+    ],
+  );
+  let scope = lisp::new();
+
+  // import and export are bound once in the root scope, a module still
+  // resolves relative paths from its own dirname since macros expand in the
+  // calling scope
+  let value = run_in_scope(
+    &scope,
+    format!("(import value \"{}\") value", dir.join("a.lisp").display()),
+  );
+  assert_eq!(isize::from_value(&scope, &value), Ok(1));
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_missing_export_in_cycle_reports_chain() {
+  let dir = module_dir(
+    "cycle",
+    &[
+      (
+        "a.lisp",
+        "(import call_missing \"./b\")
+         (export call_missing)",
+      ),
+      (
+        "b.lisp",
+        "(import missing \"./a\")
+         (def-fn call_missing [] (try (missing) (fn [error] (map.get error :value))))
+         (export call_missing)",
+      ),
+    ],
+  );
+  let scope = lisp::new();
+
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(import call_missing \"{}\") (call_missing)",
+      dir.join("a.lisp").display()
+    ),
+  );
+  let a = dir.join("a.lisp").canonicalize().unwrap();
+  let b = dir.join("b.lisp").canonicalize().unwrap();
+  assert_eq!(
+    String::from_value(&scope, &value),
+    Ok(format!(
+      "missing is not exported by {a}, which was still loading in the import cycle {a} -> {b} -> {a}",
+      a = a.display(),
+      b = b.display()
+    ))
+  );
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_import_forms() {
+  let dir = module_dir(
+    "forms",
+    &[
+      (
+        "lib.lisp",
+        "(def-fn a [] 1) (def-fn b [] 2) (def-fn c [] 3) (export a b c)",
+      ),
+      (
+        "facade.lisp",
+        "(reexport \"./lib\" :only [a] :rename {b bee})",
+      ),
+      (
+        "main.lisp",
+        "(import \"./lib\" :as m)
+         (import \"./lib\" :only [a] :rename {b x})
+         (import \"./facade\" :as f)
+         (def result [(m/c) (a) (x) (f/a) (f/bee) f/b])
+         (export result)",
+      ),
+      (
+        "all.lisp",
+        "(import \"./lib\" :all) (def result [(a) (b) (c)]) (export result)",
+      ),
+    ],
+  );
+  let scope = lisp::new();
+  let import = |filename: &str| {
+    let value = run_in_scope(
+      &scope,
+      format!(
+        "(import result \"{}\") result",
+        dir.join(filename).display()
+      ),
+    );
+    Vec::<Option<isize>>::from_value(&scope, &value).unwrap()
+  };
+
+  assert_eq!(
+    import("main.lisp"),
+    vec![Some(3), Some(1), Some(2), Some(1), Some(2), None]
+  );
+  assert_eq!(import("all.lisp"), vec![Some(1), Some(2), Some(3)]);
+
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(try (import \"{}\" :only [nope]) (fn [error] (map.get error :value)))",
+      dir.join("lib.lisp").display()
+    ),
+  );
+  assert!(String::from_value(&scope, &value)
+    .unwrap()
+    .starts_with("no such import \"nope\""));
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_reload_module() {
+  let dir = module_dir(
+    "reload",
+    &[
+      (
+        "a.lisp",
+        "(def-fn greet [] \"v1\") (def value 1) (export greet value)",
+      ),
+      (
+        "b.lisp",
+        "(import greet \"./a\") (def-fn hello [] (greet)) (export hello)",
+      ),
+    ],
+  );
+  let a = dir.join("a.lisp").canonicalize().unwrap();
+  let scope = lisp::new();
+  let eval = |source: &str| {
+    let value = run_in_scope(&scope, source);
+    String::from_value(&scope, &value).unwrap()
+  };
+
+  run_in_scope(
+    &scope,
+    format!(
+      "(import greet value \"{0}\") (import \"{0}\" :as a) (import hello \"{1}\")",
+      a.display(),
+      dir.join("b.lisp").display()
+    ),
+  );
+  assert_eq!(eval("(hello)"), "v1");
+
+  fs::write(
+    &a,
+    "(def-fn greet [] \"v2\") (def value 2) (export greet value)",
+  )
+  .unwrap();
+  run_in_scope(&scope, format!("(module.reload \"{}\")", a.display()));
+  assert_eq!(eval("(greet)"), "v2");
+  assert_eq!(eval("(hello)"), "v2");
+  assert_eq!(eval("(a/greet)"), "v2");
+  let value = run_in_scope(&scope, "value");
+  assert_eq!(isize::from_value(&scope, &value), Ok(2));
+
+  fs::write(&a, "(def-fn greet [] \"v3\") (export greet)").unwrap();
+  let root = scope_get_with_kind::<Map>(&scope, "module").unwrap();
+  let module = lisp::load(&scope, root, new_string(&scope, a.to_str().unwrap())).unwrap();
+  let stale = lisp::reload_module(&scope, &module).unwrap();
+  assert_eq!(stale.len(), 1);
+  assert!(stale[0].starts_with("value in the top level still holds the old value of value"));
+  assert_eq!(eval("(hello)"), "v3");
+
+  fs::write(&a, "(def-fn greet [] \"v4\") (export greet)").unwrap();
+  fs::File::options()
+    .write(true)
+    .open(&a)
+    .unwrap()
+    .set_modified(SystemTime::now() + Duration::from_secs(1))
+    .unwrap();
+  let value = run_in_scope(&scope, "(module.reload_changed)");
+  assert_eq!(
+    Vec::<String>::from_value(&scope, &value),
+    Ok(vec![a.to_str().unwrap().to_owned()])
+  );
+  assert_eq!(eval("(greet)"), "v4");
+  assert_eq!(eval("(hello)"), "v4");
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_missing_and_failed_modules_are_catchable() {
+  let dir = module_dir("failed", &[("a.lisp", "(throw \"boom\")")]);
+  let scope = lisp::new();
+  let caught = |filename: &str| {
+    let value = run_in_scope(
+      &scope,
+      format!(
+        "(try (import value \"{}\") (fn [error] (map.get error :value)))",
+        dir.join(filename).display()
+      ),
+    );
+    String::from_value(&scope, &value).unwrap()
+  };
+
+  assert!(caught("missing.lisp").starts_with("failed to find module"));
+  assert_eq!(caught("a.lisp"), "boom");
+
+  // the failed run was not cached, so the fixed module runs again
+  fs::write(dir.join("a.lisp"), "(def value 1) (export value)").unwrap();
+  let value = run_in_scope(
+    &scope,
+    format!("(import value \"{}\") value", dir.join("a.lisp").display()),
+  );
+  assert_eq!(isize::from_value(&scope, &value), Ok(1));
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_reload_errors_keep_the_old_exports() {
+  let dir = module_dir(
+    "reload-error",
+    &[("a.lisp", "(def-fn greet [] \"v1\") (export greet)")],
+  );
+  let a = dir.join("a.lisp").canonicalize().unwrap();
+  let scope = lisp::new();
+  let root = scope_get_with_kind::<Map>(&scope, "module").unwrap();
+  let eval = |source: &str| {
+    let value = run_in_scope(&scope, source);
+    String::from_value(&scope, &value).unwrap()
+  };
+  let touch = || {
+    fs::File::options()
+      .write(true)
+      .open(&a)
+      .unwrap()
+      .set_modified(SystemTime::now() + Duration::from_secs(1))
+      .unwrap();
+  };
+
+  run_in_scope(&scope, format!("(import greet \"{}\")", a.display()));
+
+  // the watcher reloads outside of any evaluation
+  fs::write(
+    &a,
+    "(throw \"boom\") (def-fn greet [] \"v2\") (export greet)",
+  )
+  .unwrap();
+  touch();
+  let error = lisp::reload_changed(&scope, &root).unwrap_err();
+  assert!(error.contains("boom"), "{}", error);
+  assert_eq!(eval("(greet)"), "v1");
+
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(try (module.reload \"{}\") (fn [error] (map.get error :value)))",
+      a.display()
+    ),
+  );
+  assert!(
+    String::from_value(&scope, &value).unwrap().contains("boom"),
+    "{:?}",
+    value
+  );
+  assert_eq!(eval("(greet)"), "v1");
+
+  fs::write(&a, "(def-fn greet [] \"v3\") (export greet)").unwrap();
+  touch();
+  assert_eq!(
+    lisp::reload_changed(&scope, &root),
+    Ok(vec![a.to_str().unwrap().to_owned()])
+  );
+  assert_eq!(eval("(greet)"), "v3");
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_add_loader() {
+  let scope = lisp::new();
+
+  let value = run_in_scope(
+    &scope,
+    "(def-fn const_loader [module filename]
+       (if (= filename \"const:answer\")
+         ((fn [m] (do (map.set (map.get m \"exports\") \"answer\" 42) m)) (module.new module))
+         nil))
+     (module.add_loader const_loader :before (quote file_loader))
+     (import answer \"const:answer\")
+     answer",
+  );
+  assert_eq!(isize::from_value(&scope, &value), Ok(42));
+
+  let value = run_in_scope(
+    &scope,
+    "(try (module.add_loader const_loader :after :nope) (fn [error] (map.get error :value)))",
+  );
+  assert_eq!(
+    String::from_value(&scope, &value),
+    Ok("no loader named nope".to_owned())
+  );
+}
+
+#[test]
+fn test_add_loader_by_documented_name() {
+  let scope = lisp::new();
+
+  let value = run_in_scope(
+    &scope,
+    "(def-fn const_loader [module filename]
+       (if (= filename \"const:answer\")
+         ((fn [m] (do (map.set (map.get m \"exports\") \"answer\" 42) m)) (module.new module))
+         nil))
+     (module.add-loader const_loader :before :file_loader)
+     (import answer \"const:answer\")
+     answer",
+  );
+  assert_eq!(isize::from_value(&scope, &value), Ok(42));
+
+  // a loader that throws stops the loaders after it
+  let value = run_in_scope(
+    &scope,
+    "(def loaded (atom.new false))
+     (def-fn failing_loader [module filename] (throw \"loader failed\"))
+     (def-fn marking_loader [module filename] (do (atom.set loaded true) nil))
+     (module.add-loader marking_loader)
+     (module.add-loader failing_loader)
+     (try (import other \"const:other\") (fn [error] (map.get error :value)))",
+  );
+  assert_eq!(
+    String::from_value(&scope, &value),
+    Ok("loader failed".to_owned())
+  );
+  let value = run_in_scope(&scope, "(atom.get loaded)");
+  assert_eq!(bool::from_value(&scope, &value), Ok(false));
+}
+
+#[test]
+fn test_memory_module_source() {
+  let source = Arc::new(
+    MemoryModuleSource::new()
+      .with_file(
+        "/app/package.lisp",
+        "(def package { :name :app :deps { :greeting { :path \"../pkgs/greeting\" } } })
+         (export package)",
+      )
+      .with_file(
+        "/app/src/main.lisp",
+        "(import greet \"greeting\") (import twice \"./util\") (def result (twice (greet))) (export result)",
+      )
+      .with_file(
+        "/app/src/util.lisp",
+        "(def-fn twice [a] [a a]) (export twice)",
+      )
+      .with_file(
+        "/pkgs/greeting/src/lib.lisp",
+        "(def-fn greet [] \"hello\") (export greet)",
+      ),
+  );
+  let scope = lisp::new();
+  lisp::set_module_source(&scope, source.clone());
+
+  let value = run_in_scope(&scope, "(import result \"/app/src/main.lisp\") result");
+  assert_eq!(
+    Vec::<String>::from_value(&scope, &value),
+    Ok(vec!["hello".to_owned(), "hello".to_owned()])
+  );
+  assert!(!PathBuf::from("/app/src/main.lisp").exists());
+
+  source.insert(
+    "/pkgs/greeting/src/lib.lisp",
+    "(def-fn greet [] \"hi\") (export greet)",
+  );
+  let value = run_in_scope(&scope, "(module.reload_changed)");
+  assert_eq!(
+    Vec::<String>::from_value(&scope, &value),
+    Ok(vec!["/pkgs/greeting/src/lib.lisp".to_owned()])
+  );
+  let value = run_in_scope(
+    &scope,
+    "(import twice \"/app/src/util.lisp\") (import greet \"/pkgs/greeting/src/lib.lisp\") (twice (greet))",
+  );
+  assert_eq!(
+    Vec::<String>::from_value(&scope, &value),
+    Ok(vec!["hi".to_owned(), "hi".to_owned()])
+  );
+}
+
+#[test]
+fn test_data_loaders() {
+  let dir = module_dir(
+    "data",
+    &[
+      ("config.json", "{\"port\": 8080, \"hosts\": [\"a\", \"b\"]}"),
+      ("settings.edn", "{:debug true :name \"app\"}"),
+      (
+        "main.lisp",
+        "(import config \"./config.json\")
+         (import settings \"./settings.edn\")
+         (def result [(map.get config \"port\") (map.get settings :debug)])
+         (export result)",
+      ),
+    ],
+  );
+  let scope = lisp::new();
+
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(import result \"{}\") result",
+      dir.join("main.lisp").display()
+    ),
+  );
+  assert_eq!(format!("{:?}", value), "[8080, true]");
+
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(import \"{}\" :as config) (map.get config/default \"hosts\")",
+      dir.join("config.json").display()
+    ),
+  );
+  assert_eq!(
+    Vec::<String>::from_value(&scope, &value),
+    Ok(vec!["a".to_owned(), "b".to_owned()])
+  );
+
+  // only the single name form falls back to the default export
+  let config = dir.join("config.json").display().to_string();
+  for &(import, missing) in &[
+    ("(import \"{}\" :only [typo])", "typo"),
+    ("(import port typo \"{}\")", "port"),
+    ("(import \"{}\" :rename {typo port})", "typo"),
+  ] {
+    let value = run_in_scope(
+      &scope,
+      format!(
+        "(try {} (fn [error] (map.get error :value)))",
+        import.replace("{}", &config)
+      ),
+    );
+    assert_eq!(
+      String::from_value(&scope, &value),
+      Ok(format!(
+        "no such import {:?} defined in {:?}",
+        missing, config
+      )),
+      "{}",
+      import
+    );
+  }
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_try_catches_loader_errors() {
+  let dir = module_dir(
+    "loader-error",
+    &[
+      ("bad.json", "{\"port\": }"),
+      ("throws.lisp", "(throw \"module failed\") (export x)"),
+    ],
+  );
+  let scope = lisp::new();
+
+  for path in &[
+    dir.join("bad.json").display().to_string(),
+    "native:nope".to_owned(),
+    "nopkg".to_owned(),
+  ] {
+    let value = run_in_scope(
+      &scope,
+      format!("(try (import data \"{}\") (fn [error] \"caught\"))", path),
+    );
+    assert_eq!(
+      String::from_value(&scope, &value),
+      Ok("caught".to_owned()),
+      "{}",
+      path
+    );
+  }
+
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(try (import x \"{}\") (fn [error] (map.get error :value)))",
+      dir.join("throws.lisp").display()
+    ),
+  );
+  assert_eq!(
+    String::from_value(&scope, &value),
+    Ok("module failed".to_owned())
+  );
+
+  let value = run_in_scope(&scope, "(isize.add 1 2)");
+  assert_eq!(isize::from_value(&scope, &value), Ok(3));
+
+  fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,188 @@
+extern crate lisp;
+
+use std::env;
+use std::fs;
+
+use lisp::runtime::{new_string, new_vector, run_in_scope, scope_get_with_kind, Map, Object};
+use lisp::{
+  build_native_package, cargo_loader, native_library_filename, native_package_library,
+  reload_dylib, resolve_native_library,
+};
+
+#[test]
+fn test_resolve_native_library() {
+  let dir = env::temp_dir().join(format!("lisp-native-{}", std::process::id()));
+  let native_dir = dir.join("native");
+  fs::create_dir_all(&native_dir).unwrap();
+  fs::write(native_dir.join(native_library_filename("found")), b"").unwrap();
+
+  let scope = lisp::new();
+  let mut module = scope_get_with_kind::<Map>(&scope, "module")
+    .unwrap()
+    .clone();
+  module.set(
+    new_string(&scope, "dirname").into_value(),
+    new_string(&scope, dir.to_str().unwrap()).into_value(),
+  );
+  let mut native_path = new_vector(&scope);
+  native_path.push(new_string(&scope, native_dir.to_str().unwrap()).into_value());
+  module.set(
+    new_string(&scope, "native_path").into_value(),
+    native_path.into_value(),
+  );
+
+  assert_eq!(
+    resolve_native_library(&scope, &module, "native:found"),
+    Ok(Some(
+      native_dir
+        .join(native_library_filename("found"))
+        .canonicalize()
+        .unwrap()
+    ))
+  );
+  assert_eq!(
+    resolve_native_library(&scope, &module, "./file.lisp"),
+    Ok(None)
+  );
+
+  let error = resolve_native_library(&scope, &module, "native:missing").unwrap_err();
+  let missing = native_library_filename("missing");
+  assert!(error.starts_with("failed to find native library missing, tried:"));
+  for path in &[
+    dir.join(&missing),
+    dir.join("lisp-builder-out").join(&missing),
+    native_dir.join(&missing),
+  ] {
+    assert!(error.contains(path.to_str().unwrap()), "{}", error);
+  }
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_build_native_package() {
+  use std::os::unix::fs::PermissionsExt;
+  use std::thread::sleep;
+  use std::time::Duration;
+
+  let dir = env::temp_dir().join(format!("lisp-cargo-{}", std::process::id()));
+  let package_dir = dir.join("native");
+  fs::create_dir_all(package_dir.join("src")).unwrap();
+  fs::write(
+    package_dir.join("Cargo.toml"),
+    "[package]\nname = \"my-lib\"\n\n[lib]\ncrate-type = [\"cdylib\"]\n",
+  )
+  .unwrap();
+  fs::write(package_dir.join("src").join("lib.rs"), "").unwrap();
+
+  let builds = dir.join("builds");
+  let builder = dir.join("builder.sh");
+  fs::write(
+    &builder,
+    format!(
+      "#!/bin/sh\necho \"$2\" >> {builds}\nif [ -f {fail} ]; then echo broken >&2; exit 1; fi\ntouch \"$4/{library}\"\n",
+      builds = builds.display(),
+      fail = dir.join("fail").display(),
+      library = native_library_filename("my_lib"),
+    ),
+  )
+  .unwrap();
+  fs::set_permissions(&builder, fs::Permissions::from_mode(0o755)).unwrap();
+
+  let manifest = package_dir.join("Cargo.toml").canonicalize().unwrap();
+  let library = native_package_library(&manifest).unwrap();
+  assert_eq!(
+    library,
+    manifest
+      .parent()
+      .unwrap()
+      .join("lisp-builder-out")
+      .join(native_library_filename("my_lib"))
+  );
+  let build_count = || {
+    fs::read_to_string(&builds)
+      .map(|builds| builds.lines().count())
+      .unwrap_or(0)
+  };
+
+  assert_eq!(
+    build_native_package(&builder, &manifest),
+    Ok(library.clone())
+  );
+  assert_eq!(build_count(), 1);
+  assert_eq!(
+    build_native_package(&builder, &manifest),
+    Ok(library.clone())
+  );
+  assert_eq!(build_count(), 1);
+
+  sleep(Duration::from_millis(10));
+  fs::write(package_dir.join("src").join("lib.rs"), "// changed").unwrap();
+  fs::write(dir.join("fail"), "").unwrap();
+  let error = build_native_package(&builder, &manifest).unwrap_err();
+  assert!(error.starts_with("failed to build"), "{}", error);
+  assert!(error.ends_with("broken"), "{}", error);
+  assert_eq!(build_count(), 2);
+
+  let scope = lisp::new();
+  let mut module = scope_get_with_kind::<Map>(&scope, "module")
+    .unwrap()
+    .clone();
+  module.set(
+    new_string(&scope, "dirname").into_value(),
+    new_string(&scope, dir.to_str().unwrap()).into_value(),
+  );
+  assert_eq!(
+    cargo_loader(&scope, &module, &"./file.lisp".to_owned()).map(|module| module.is_some()),
+    Ok(false)
+  );
+  env::set_var("LISP_BUILDER", &builder);
+  let error = cargo_loader(&scope, &module, &"./native/Cargo.toml".to_owned()).unwrap_err();
+  assert!(error.ends_with("broken"), "{}", error);
+  assert_eq!(build_count(), 3);
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_reload_dylib_requires_native_module() {
+  let dir = env::temp_dir().join(format!("lisp-reload-dylib-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  fs::write(dir.join("lib.lisp"), "(def-fn f [] 1)\n(export f)\n").unwrap();
+
+  let scope = lisp::new();
+  run_in_scope(
+    &scope,
+    format!("(import f \"{}\")", dir.join("lib.lisp").display()),
+  );
+  let module = scope_get_with_kind::<Map>(&scope, "module")
+    .unwrap()
+    .get(&new_string(&scope, "cache").into_value())
+    .and_then(|cache| cache.downcast_ref::<Object<Map>>())
+    .and_then(|cache| {
+      cache
+        .get(
+          &new_string(
+            &scope,
+            dir
+              .join("lib.lisp")
+              .canonicalize()
+              .unwrap()
+              .to_str()
+              .unwrap(),
+          )
+          .into_value(),
+        )
+        .and_then(|module| module.downcast_ref::<Object<Map>>())
+        .map(Clone::clone)
+    })
+    .unwrap();
+
+  assert_eq!(
+    reload_dylib(&scope, &module),
+    Err("only modules loaded from native libraries can be reloaded".to_owned())
+  );
+
+  fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,113 @@
+extern crate lisp;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use lisp::runtime::{new_string, run_in_scope, scope_get_with_kind, FromValue, Map};
+use lisp::{resolve_package_module, PACKAGE_PATH_ENV};
+
+fn package_dir(files: &[(&str, &str)]) -> PathBuf {
+  let dir = env::temp_dir().join(format!("lisp-packages-{}", std::process::id()));
+  for &(filename, content) in files {
+    let path = dir.join(filename);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+  }
+  dir.canonicalize().unwrap()
+}
+
+#[test]
+fn test_package_resolution() {
+  let dir = package_dir(&[
+    (
+      "app/package.lisp",
+      "(def package {
+         :name :app
+         :deps { :util { :version \"0.1\" :path \"../util\" } :installed { :version \"0.1\" } }
+       })
+       (export package)",
+    ),
+    (
+      "app/src/main.lisp",
+      "(import add \"util\")
+       (import twice \"util/math\")
+       (def result (twice (add 1 2)))
+       (export result)",
+    ),
+    (
+      "util/package.lisp",
+      "(def package { :name :util :version \"0.1.3\" }) (export package)",
+    ),
+    (
+      "util/src/lib.lisp",
+      "(def-fn add [a b] (isize.add a b)) (export add)",
+    ),
+    (
+      "util/src/math.lisp",
+      "(import add \"util\") (def-fn twice [a] (add a a)) (export twice)",
+    ),
+    ("extra/src/lib.lisp", "(def extra 1) (export extra)"),
+    ("app/lisp_packages/installed/src/lib.lisp", ""),
+    (
+      "app/lisp_packages/installed/package.lisp",
+      "(def package { :name :installed :version \"0.1.0\" }) (export package)",
+    ),
+    (
+      "old/package.lisp",
+      "(def package { :name :old :deps { :util { :version \">=0.2\" :path \"../util\" } } })
+       (export package)",
+    ),
+    ("old/src/lib.lisp", ""),
+  ]);
+  let scope = lisp::new();
+
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(import result \"{}\") result",
+      dir.join("app/src/main.lisp").display()
+    ),
+  );
+  assert_eq!(isize::from_value(&scope, &value), Ok(6));
+
+  let mut module = scope_get_with_kind::<Map>(&scope, "module")
+    .unwrap()
+    .clone();
+  module.set(
+    new_string(&scope, "dirname").into_value(),
+    new_string(&scope, dir.join("app/src").to_str().unwrap()).into_value(),
+  );
+  env::set_var(PACKAGE_PATH_ENV, &dir);
+
+  assert_eq!(
+    resolve_package_module(&scope, &module, "util/math"),
+    Ok(Some(dir.join("util/src/math.lisp")))
+  );
+  assert_eq!(
+    resolve_package_module(&scope, &module, "extra"),
+    Ok(Some(dir.join("extra/src/lib.lisp")))
+  );
+  assert_eq!(resolve_package_module(&scope, &module, "./util"), Ok(None));
+
+  let error = resolve_package_module(&scope, &module, "missing/thing").unwrap_err();
+  assert!(error.starts_with("failed to resolve package missing for \"missing/thing\", consulted:"));
+  assert!(error.contains(dir.join("app/package.lisp").to_str().unwrap()));
+  assert!(error.contains(dir.join("missing").to_str().unwrap()));
+
+  let error = resolve_package_module(&scope, &module, "util/nope").unwrap_err();
+  assert!(error.contains("has no module"), "{}", error);
+
+  module.set(
+    new_string(&scope, "dirname").into_value(),
+    new_string(&scope, dir.join("old/src").to_str().unwrap()).into_value(),
+  );
+  let error = resolve_package_module(&scope, &module, "util").unwrap_err();
+  assert!(
+    error.contains("requires version >=0.2 but") && error.ends_with("is 0.1.3"),
+    "{}",
+    error
+  );
+
+  fs::remove_dir_all(dir).unwrap();
+}
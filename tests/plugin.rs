@@ -0,0 +1,271 @@
+extern crate lisp;
+
+mod common;
+
+use lisp::gc::Gc;
+use lisp::plugin::{
+  Context, LispContext, LispHost, LispValue, LISP_PLUGIN_ABI_VERSION, LISP_TYPE_INT,
+  LISP_TYPE_STRING,
+};
+use lisp::runtime::{
+  new_context, new_keyword, new_string, nil_value, run_in_scope, scope_set, FromValue, Function,
+  IntoValue, Object, Value,
+};
+use lisp::{call_plugin_function, native_library_filename, new_dylib};
+
+unsafe extern "C" fn sum(
+  host: *const LispHost,
+  context: *mut LispContext,
+  args: *const LispValue,
+  len: usize,
+) -> LispValue {
+  let context = Context::from_raw(host, context, args, len);
+  let mut total = 0;
+
+  for &arg in context.args() {
+    match context.get_int(arg) {
+      Some(value) => total += value,
+      None => return context.throw_error("expected integers"),
+    }
+  }
+  context.new_int(total)
+}
+
+unsafe extern "C" fn describe(
+  host: *const LispHost,
+  context: *mut LispContext,
+  args: *const LispValue,
+  len: usize,
+) -> LispValue {
+  let context = Context::from_raw(host, context, args, len);
+  let vector = context.arg(0).unwrap();
+  let len = context.vector_len(vector).unwrap();
+  let items = (0..len)
+    .map(|index| context.vector_get(vector, index))
+    .collect::<Vec<LispValue>>();
+
+  assert_eq!(context.type_of(items[0]), LISP_TYPE_INT);
+  assert_eq!(context.type_of(items[1]), LISP_TYPE_STRING);
+  let name = context.get_str(items[1]).unwrap();
+  let keyword = context.new_keyword(name);
+  context.new_vector(&[keyword, context.new_bool(true), context.new_float(0.5)])
+}
+
+#[test]
+fn test_call_plugin_function() {
+  let scope = new_context();
+  let args: Vec<Gc<dyn Value>> = vec![1_i64.into_value(&scope), 2_isize.into_value(&scope)];
+
+  let value = call_plugin_function(&scope, sum, &args).unwrap();
+  assert_eq!(i64::from_value(&scope, &value), Ok(3));
+
+  let args = vec!["a".into_value(&scope)];
+  assert_eq!(
+    call_plugin_function(&scope, sum, &args),
+    Err("expected integers".to_owned())
+  );
+}
+
+#[test]
+fn test_plugin_values() {
+  let scope = new_context();
+  let args = vec![(1_i64, "name".to_owned()).into_value(&scope)];
+
+  let value = call_plugin_function(&scope, describe, &args).unwrap();
+  let (keyword, flag, float) = <(Gc<dyn Value>, bool, f64)>::from_value(&scope, &value).unwrap();
+  assert_eq!(keyword.kind().name(), "Keyword");
+  assert!(flag);
+  assert_eq!(float, 0.5);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dylib_call_only_calls_exports() {
+  let dir = common::test_dir("dylib-call");
+  let library = dir.join(native_library_filename("hello_c"));
+  common::build_hello_c(&library, &[]);
+
+  let scope = lisp::new();
+  let dylib = unsafe { new_dylib(&scope, library.to_str().unwrap()) }.unwrap();
+  scope_set(&scope, "lib", dylib.into_value());
+
+  let value = run_in_scope(&scope, "(dylib.call lib :lisp_hello_c 1 2)");
+  assert_eq!(i64::from_value(&scope, &value), Ok(3));
+  assert_eq!(
+    run_in_scope(
+      &scope,
+      "(try (dylib.call lib :lisp_plugin_init) (fn [error] (map.get error :value)))"
+    ),
+    new_string(
+      &scope,
+      "hello_c does not export a function named lisp_plugin_init"
+    )
+    .into_value()
+  );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_import_hello_c() {
+  let dir = common::test_dir("hello-c");
+  let library = dir.join(native_library_filename("hello_c"));
+  common::build_hello_c(&library, &[]);
+
+  let scope = lisp::new();
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(import lisp_hello_c {:?}) (lisp_hello_c 40 2)",
+      library.to_str().unwrap()
+    ),
+  );
+  assert_eq!(i64::from_value(&scope, &value), Ok(42));
+  assert_eq!(
+    run_in_scope(
+      &scope,
+      "(try (lisp_hello_c 1) (fn [error] (map.get error :value)))"
+    ),
+    new_string(&scope, "expected two integers").into_value()
+  );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_reject_plugin_abi_version() {
+  let dir = common::test_dir("plugin-abi");
+  let scope = lisp::new();
+
+  let newer = dir.join(native_library_filename("newer"));
+  common::build_hello_c(
+    &newer,
+    &[(
+      "{LISP_PLUGIN_ABI_VERSION, \"hello_c\"",
+      "{LISP_PLUGIN_ABI_VERSION + 1, \"hello_c\"",
+    )],
+  );
+  let error = unsafe { new_dylib(&scope, newer.to_str().unwrap()) }.unwrap_err();
+  assert!(
+    error.ends_with(&format!(
+      "was built for plugin ABI version {}, expected {}",
+      LISP_PLUGIN_ABI_VERSION + 1,
+      LISP_PLUGIN_ABI_VERSION
+    )),
+    "{}",
+    error
+  );
+
+  let older = dir.join(native_library_filename("older"));
+  common::build_hello_c(
+    &older,
+    &[(
+      "host->abi_version != LISP_PLUGIN_ABI_VERSION",
+      "host->abi_version != LISP_PLUGIN_ABI_VERSION - 1",
+    )],
+  );
+  let error = unsafe { new_dylib(&scope, older.to_str().unwrap()) }.unwrap_err();
+  assert!(
+    error.ends_with(&format!(
+      "does not support plugin ABI version {}",
+      LISP_PLUGIN_ABI_VERSION
+    )),
+    "{}",
+    error
+  );
+}
+
+#[test]
+fn test_import_hello_world_manifest() {
+  let library = common::build_hello_world();
+  let scope = lisp::new();
+
+  let value = run_in_scope(
+    &scope,
+    format!(
+      "(import lisp_hello_world {:?}) lisp_hello_world",
+      library.to_str().unwrap()
+    ),
+  );
+  let function = value
+    .downcast_ref::<Object<Function>>()
+    .expect("lisp_hello_world is not a function");
+  let params = function
+    .params()
+    .iter()
+    .map(|param| format!("{:?}", param))
+    .collect::<Vec<String>>();
+  assert_eq!(params, vec!["...name".to_owned()]);
+
+  let doc = function
+    .meta()
+    .and_then(|meta| meta.get(&new_keyword(&scope, "doc").into_value()).cloned());
+  assert_eq!(
+    doc,
+    Some(new_string(&scope, "Prints a greeting to name, or to the world").into_value())
+  );
+
+  assert_eq!(
+    run_in_scope(&scope, "(lisp_hello_world \"lisp\")"),
+    nil_value(&scope).clone().into_value()
+  );
+  assert_eq!(
+    run_in_scope(
+      &scope,
+      "(try (lisp_hello_world 1) (fn [error] (map.get error :value)))"
+    ),
+    new_string(&scope, "expected a name").into_value()
+  );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_reload_rebuilt_hello_c() {
+  use lisp::gc::Trace;
+  use lisp::DyLib;
+
+  let dir = common::test_dir("hello-c-reload");
+  // without a prefix the library is exported as hello_c
+  let library = dir.join(format!("hello_c{}", std::env::consts::DLL_SUFFIX));
+  let path = library.to_str().unwrap();
+  common::build_hello_c(&library, &[]);
+
+  let scope = lisp::new();
+  run_in_scope(
+    &scope,
+    format!(
+      "(import lisp_hello_c hello_c {:?}) (import {:?} :as native)",
+      path, path
+    ),
+  );
+  let value = run_in_scope(&scope, "(lisp_hello_c 40 2)");
+  assert_eq!(i64::from_value(&scope, &value), Ok(42));
+  let old_dylib = run_in_scope(&scope, "hello_c");
+  let old_function = run_in_scope(&scope, "lisp_hello_c");
+  assert!(old_dylib.downcast_ref::<Object<DyLib>>().is_some());
+
+  common::build_hello_c(&library, &[("a + b", "a * b")]);
+  run_in_scope(&scope, format!("(dylib.reload {:?})", path));
+
+  // the imported names and the module's exports call the rebuilt library
+  let value = run_in_scope(&scope, "(lisp_hello_c 40 2)");
+  assert_eq!(i64::from_value(&scope, &value), Ok(80));
+  let value = run_in_scope(&scope, "(native/lisp_hello_c 40 2)");
+  assert_eq!(i64::from_value(&scope, &value), Ok(80));
+  let new_dylib = run_in_scope(&scope, "hello_c");
+  assert!(new_dylib.downcast_ref::<Object<DyLib>>().is_some());
+  assert_ne!(
+    old_dylib.as_ptr() as *const u8,
+    new_dylib.as_ptr() as *const u8
+  );
+
+  // nothing in the context refers to the old library or its functions, so
+  // the collector can drop it
+  let mut root = scope.clone();
+  root.trace(true);
+  let reachable = (
+    old_dylib.as_ref().is_marked(),
+    old_function.as_ref().is_marked(),
+    new_dylib.as_ref().is_marked(),
+  );
+  root.trace(false);
+  assert_eq!(reachable, (false, false, true));
+}
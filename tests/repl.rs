@@ -0,0 +1,23 @@
+extern crate lisp;
+
+use lisp::top_level_forms;
+
+#[test]
+fn test_top_level_forms() {
+  assert_eq!(
+    top_level_forms("(def-fn add [a b]\n  (isize.add a b))\n(add 1 2) x"),
+    Some(vec![
+      "(def-fn add [a b]\n  (isize.add a b))",
+      "(add 1 2)",
+      "x"
+    ])
+  );
+  assert_eq!(
+    top_level_forms("\"a (\" ')' ; (\n`(a) {:a [1]}"),
+    Some(vec!["\"a (\"", "')'", "`(a)", "{:a [1]}"])
+  );
+  assert_eq!(top_level_forms("  ; comment\n"), Some(vec![]));
+  assert_eq!(top_level_forms("(def-fn add [a b]"), None);
+  assert_eq!(top_level_forms("(println \"a"), None);
+  assert_eq!(top_level_forms("{:a [1 2}"), None);
+}
@@ -0,0 +1,94 @@
+extern crate lisp;
+
+use lisp::{Version, VersionReq};
+
+fn matches(requirement: &str, version: &str) -> bool {
+  VersionReq::parse(requirement)
+    .unwrap()
+    .matches(&Version::parse(version).unwrap())
+}
+
+#[test]
+fn test_parse_and_order_versions() {
+  assert_eq!(Version::parse("1.2.3"), Ok(Version::new(1, 2, 3)));
+  assert_eq!(
+    Version::parse("1.2.3-alpha.1+build").unwrap().to_string(),
+    "1.2.3-alpha.1"
+  );
+  assert!(Version::parse("1.2").is_err());
+  assert!(Version::parse("01.2.3").is_err());
+  assert!(VersionReq::parse("1.*.3").is_err());
+
+  let mut versions = [
+    "1.0.0",
+    "1.0.0-rc.1",
+    "1.0.0-alpha",
+    "0.9.10",
+    "0.9.9",
+    "1.0.0-alpha.2",
+  ]
+  .iter()
+  .map(|version| Version::parse(version).unwrap())
+  .collect::<Vec<Version>>();
+  versions.sort();
+  assert_eq!(
+    versions
+      .iter()
+      .map(ToString::to_string)
+      .collect::<Vec<String>>(),
+    vec![
+      "0.9.9",
+      "0.9.10",
+      "1.0.0-alpha",
+      "1.0.0-alpha.2",
+      "1.0.0-rc.1",
+      "1.0.0"
+    ]
+  );
+}
+
+#[test]
+fn test_requirements() {
+  for &(requirement, version, expected) in &[
+    ("0.1", "0.1.0", true),
+    ("0.1", "0.1.9", true),
+    ("0.1", "0.2.0", false),
+    ("^1.2.3", "1.9.0", true),
+    ("^1.2.3", "1.2.2", false),
+    ("^1.2.3", "2.0.0", false),
+    ("^0.0.3", "0.0.3", true),
+    ("^0.0.3", "0.0.4", false),
+    ("^0", "0.9.0", true),
+    ("~1.2.3", "1.2.9", true),
+    ("~1.2.3", "1.3.0", false),
+    ("~1", "1.9.0", true),
+    ("=1.2", "1.2.7", true),
+    ("=1.2.3", "1.2.4", false),
+    (">1.2", "1.2.9", false),
+    (">1.2", "1.3.0", true),
+    (">=1.2", "1.2.0", true),
+    ("<1.2", "1.1.9", true),
+    ("<1.2", "1.2.0", false),
+    ("<=1.2", "1.2.9", true),
+    ("1.*", "1.5.0", true),
+    ("1.2.x", "1.3.0", false),
+    ("*", "3.0.0", true),
+    (">=1.0, <2", "1.9.9", true),
+    (">=1.0, <2", "2.0.0", false),
+    ("^1.0.0", "1.1.0-alpha", false),
+    (">=1.1.0-alpha", "1.1.0-beta", true),
+    (">=1.1.0-alpha", "1.2.0-beta", false),
+  ] {
+    assert_eq!(
+      matches(requirement, version),
+      expected,
+      "{} matches {}",
+      requirement,
+      version
+    );
+  }
+  assert_eq!(
+    VersionReq::parse(">= 1.2, <2").unwrap().to_string(),
+    ">=1.2, <2"
+  );
+}
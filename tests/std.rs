@@ -0,0 +1,73 @@
+#![cfg(feature = "bundled-std")]
+
+extern crate lisp;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lisp::runtime::{run_in_scope, scope_get_with_kind, FromValue, Map};
+use lisp::ModuleSource;
+
+fn lisp_files(dir: &Path, files: &mut Vec<PathBuf>) {
+  for entry in fs::read_dir(dir).unwrap() {
+    let path = entry.unwrap().path();
+
+    if path.is_dir() {
+      lisp_files(&path, files);
+    } else if path.extension().and_then(|extension| extension.to_str()) == Some("lisp") {
+      files.push(path);
+    }
+  }
+}
+
+#[test]
+fn test_bundled_std() {
+  let scope = lisp::new();
+
+  let value = run_in_scope(
+    &scope,
+    "(import option.some option.none option.is_some option.unwrap \"std/core/option\")
+     [(option.is_some (option.some 1)) (option.is_some (option.none)) (option.unwrap (option.some true))]",
+  );
+  assert_eq!(
+    Vec::<bool>::from_value(&scope, &value),
+    Ok(vec![true, false, true])
+  );
+
+  let module = scope_get_with_kind::<Map>(&scope, "module").unwrap();
+  assert_eq!(
+    lisp::std_loader(&scope, &module, &"std/core/missing".to_owned()).err(),
+    Some("no module \"std/core/missing\" in the bundled std".to_owned())
+  );
+  assert!(
+    lisp::std_loader(&scope, &module, &"./std/core/option".to_owned())
+      .unwrap()
+      .is_none()
+  );
+}
+
+#[test]
+fn test_bundled_std_has_every_file() {
+  let std_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("packages/std");
+  let source = lisp::std_source();
+  let mut files = Vec::new();
+
+  lisp_files(&std_dir, &mut files);
+  assert!(!files.is_empty());
+
+  for file in files {
+    let relative = file.strip_prefix(&std_dir).unwrap();
+
+    assert!(
+      source.is_file(&Path::new(lisp::STD_ROOT).join(relative)),
+      "{} is missing from STD_FILES in src/loader/std_loader.rs",
+      relative.display()
+    );
+    assert_eq!(
+      source
+        .read_to_string(&Path::new(lisp::STD_ROOT).join(relative))
+        .unwrap(),
+      fs::read_to_string(&file).unwrap()
+    );
+  }
+}